@@ -3,9 +3,7 @@
     windows_subsystem = "windows"
 )]
 
-mod aggregation;
 mod rpc;
-mod xap;
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
@@ -22,11 +20,14 @@ use tauri::{
 };
 use tauri::{AppHandle, Manager};
 
-use rpc::commands::{device_get, devices_get, keymap_get, remap_key, xap_constants_get};
+use rpc::commands::{
+    device_auto_unlock_set, device_get, device_host_layout_set, device_log_clear,
+    device_log_export, device_log_get, devices_get, keycode_parse, keycodes_search, keymap_get,
+    remap_key, route_invoke, routes_metadata_get, xap_constants_get,
+};
 use rpc::events::XapEvent;
-use xap::client::XapClient;
-
-use xap_specs::constants::XapConstants;
+use xap_client::{recording::ReplayDiscovery, XapClient, XapClientEvent};
+use xap_specs::{constants::XapConstants, dynamic::RouteTable};
 
 static RUNNING: AtomicBool = AtomicBool::new(true);
 
@@ -90,8 +91,8 @@ impl App {
         }
     }
 
-    fn emit_event(&self, event: XapEvent) {
-        if let Err(err) = self.handle.emit("xap", event) {
+    fn emit_event(&self, event: XapClientEvent) {
+        if let Err(err) = self.handle.emit("xap", XapEvent(event)) {
             error!("failed to emit event: {err}");
         }
     }
@@ -105,7 +106,7 @@ fn main() -> Result<()> {
         .formatter(specta::ts::formatter::prettier);
 
     let mut specta_builder =
-        generate_specta_builder!(commands: [xap_constants_get, remap_key, keymap_get, device_get, devices_get], events: [XapEvent])
+        generate_specta_builder!(commands: [xap_constants_get, keycode_parse, keycodes_search, remap_key, keymap_get, device_get, device_auto_unlock_set, device_host_layout_set, device_log_get, device_log_clear, device_log_export, devices_get, route_invoke, routes_metadata_get], events: [XapEvent])
             .config(specta_config);
 
     if cfg!(debug_assertions) {
@@ -124,9 +125,29 @@ fn main() -> Result<()> {
                 .path()
                 .resolve("../xap-specs/assets", BaseDirectory::Resource)?;
 
-            let state = Arc::new(Mutex::new(XapClient::new(XapConstants::new(xap_specs)?)?));
+            let routes = RouteTable::from_dir(&xap_specs)?;
+            let xap_constants = XapConstants::new(xap_specs)?;
+
+            // Serve a recorded session instead of the attached devices, e.g. to reproduce bugs
+            let mut client = match std::env::var_os("QMK_XAP_REPLAY") {
+                Some(recording) => {
+                    info!("replaying XAP recording {recording:?}");
+                    XapClient::with_discovery(
+                        xap_constants,
+                        Box::new(ReplayDiscovery::from_files([recording])?),
+                    )
+                }
+                None => XapClient::new(xap_constants)?,
+            };
+
+            if let Some(directory) = std::env::var_os("QMK_XAP_RECORD_DIR") {
+                client.record_to(directory.into());
+            }
+
+            let state = Arc::new(Mutex::new(client));
 
             app.manage(Arc::clone(&state));
+            app.manage(Arc::new(routes));
 
             let handle = app.handle().clone();
             std::thread::spawn(|| App::new(handle, state).start_event_loop());
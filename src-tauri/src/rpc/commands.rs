@@ -1,20 +1,78 @@
 use std::result::Result;
 use std::sync::{Arc, Mutex};
 
+use serde_json::Value;
 use tauri::State;
 use uuid::Uuid;
+use xap_specs::constants::keycode::KeyCode;
 use xap_specs::constants::XapConstants;
+use xap_specs::dynamic::RouteTable;
+use xap_specs::metadata::{RouteMetadata, ROUTES};
 
-use crate::aggregation::keymap::MappedKeymap;
-use crate::xap::device::XapDeviceState;
-use crate::xap::{client::XapClient, spec::remapping::RemappingSetKeycodeArg};
+use xap_client::aggregation::keymap::MappedKeymap;
+use xap_client::console::ConsoleLine;
+use xap_client::spec::remapping::RemappingSetKeycodeArg;
+use xap_client::{XapClient, XapDeviceState};
 
 use crate::rpc::spec::error::Error;
 
 #[tauri::command]
 #[specta::specta]
 pub fn xap_constants_get(state: State<'_, Arc<Mutex<XapClient>>>) -> XapConstants {
-    state.lock().unwrap().xap_constants()
+    let constants = state.lock().unwrap().xap_constants();
+    constants.as_ref().clone()
+}
+
+/// Parses a keycode as written in a QMK keymap, e.g. `LT(2, KC_SPC)`. The custom keycodes of the
+/// device with the given id are accepted as well, the keycode is labeled for the given host layout
+/// or the one of the device.
+#[tauri::command]
+#[specta::specta]
+pub fn keycode_parse(
+    expression: String,
+    id: Option<Uuid>,
+    layout: Option<String>,
+    state: State<'_, Arc<Mutex<XapClient>>>,
+) -> Result<KeyCode, Error> {
+    let (constants, layout) = keycode_constants(id, layout, &state.lock().unwrap())?;
+    let code = constants.parse_keycode(&expression)?;
+    Ok(constants.get_localized_keycode(code, layout.as_deref()))
+}
+
+/// Fuzzy search over the names, aliases and labels of all keycodes for the keycode picker,
+/// including the custom keycodes of the device with the given id. The keycodes are localized to
+/// the given host layout or the one of the device, e.g. `DE_ODIA` finds `KC_SCLN`.
+#[tauri::command]
+#[specta::specta]
+pub fn keycodes_search(
+    query: String,
+    limit: u32,
+    id: Option<Uuid>,
+    layout: Option<String>,
+    state: State<'_, Arc<Mutex<XapClient>>>,
+) -> Result<Vec<KeyCode>, Error> {
+    let (constants, layout) = keycode_constants(id, layout, &state.lock().unwrap())?;
+    Ok(constants.search_keycodes(&query, limit as usize, layout.as_deref()))
+}
+
+/// Constants and host layout to look up keycodes with, an explicitly given layout takes precedence
+/// over the one of the device. The constants are shared, so the lookup can run after the client is
+/// unlocked again.
+fn keycode_constants(
+    id: Option<Uuid>,
+    layout: Option<String>,
+    client: &XapClient,
+) -> Result<(Arc<XapConstants>, Option<String>), Error> {
+    match id {
+        Some(id) => {
+            let device = client.get_device(&id)?;
+            Ok((
+                Arc::clone(device.constants()),
+                layout.or_else(|| device.state().host_layout.clone()),
+            ))
+        }
+        None => Ok((client.xap_constants(), layout)),
+    }
 }
 
 #[tauri::command]
@@ -51,6 +109,104 @@ pub fn device_get(
     Ok(state.lock().unwrap().get_device(&id)?.state().clone())
 }
 
+#[tauri::command]
+#[specta::specta]
+pub fn device_auto_unlock_set(
+    id: Uuid,
+    enabled: bool,
+    state: State<'_, Arc<Mutex<XapClient>>>,
+) -> Result<(), Error> {
+    state
+        .lock()
+        .unwrap()
+        .get_device_mut(&id)?
+        .set_auto_unlock(enabled);
+    Ok(())
+}
+
+/// Localizes the keycode labels of the device to a host keyboard layout, e.g. `german`.
+#[tauri::command]
+#[specta::specta]
+pub fn device_host_layout_set(
+    id: Uuid,
+    layout: Option<String>,
+    state: State<'_, Arc<Mutex<XapClient>>>,
+) -> Result<(), Error> {
+    Ok(state
+        .lock()
+        .unwrap()
+        .get_device_mut(&id)?
+        .set_host_layout(layout)?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn device_log_get(
+    id: Uuid,
+    state: State<'_, Arc<Mutex<XapClient>>>,
+) -> Result<Vec<ConsoleLine>, Error> {
+    Ok(state
+        .lock()
+        .unwrap()
+        .get_device(&id)?
+        .console()
+        .lines()
+        .cloned()
+        .collect())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn device_log_clear(id: Uuid, state: State<'_, Arc<Mutex<XapClient>>>) -> Result<(), Error> {
+    state
+        .lock()
+        .unwrap()
+        .get_device_mut(&id)?
+        .console_mut()
+        .clear();
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn device_log_export(
+    id: Uuid,
+    path: String,
+    state: State<'_, Arc<Mutex<XapClient>>>,
+) -> Result<(), Error> {
+    Ok(state
+        .lock()
+        .unwrap()
+        .get_device(&id)?
+        .console()
+        .export(path)?)
+}
+
+/// Invokes a route by its name, e.g. `lighting.rgbmatrix.get_config`, for routes that have no
+/// generated command.
+#[tauri::command]
+#[specta::specta]
+pub fn route_invoke(
+    id: Uuid,
+    route: String,
+    args: Value,
+    state: State<'_, Arc<Mutex<XapClient>>>,
+    routes: State<'_, Arc<RouteTable>>,
+) -> Result<Value, Error> {
+    state
+        .lock()
+        .unwrap()
+        .query_dynamic(id, &routes, &route, &args)
+}
+
+/// Metadata of all routes the generated code knows, e.g. to show help texts or gray out routes
+/// a device doesn't support.
+#[tauri::command]
+#[specta::specta]
+pub fn routes_metadata_get() -> Vec<RouteMetadata> {
+    ROUTES.to_vec()
+}
+
 #[tauri::command]
 #[specta::specta]
 pub fn devices_get(state: State<'_, Arc<Mutex<XapClient>>>) -> Vec<XapDeviceState> {
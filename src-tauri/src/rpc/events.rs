@@ -1,24 +1,10 @@
 use serde::Serialize;
 use specta::Type;
 use tauri_specta::Event;
-use uuid::Uuid;
-use xap_specs::XapSecureStatus;
+use xap_client::XapClientEvent;
 
-#[derive(Clone, Serialize, Type, Event)]
-#[serde(tag = "kind", content = "data")]
-pub enum XapEvent {
-    LogReceived {
-        id: Uuid,
-        log: String,
-    },
-    SecureStatusChanged {
-        id: Uuid,
-        secure_status: XapSecureStatus,
-    },
-    NewDevice {
-        id: Uuid,
-    },
-    RemovedDevice {
-        id: Uuid,
-    },
-}
+/// Events of the XAP client as emitted to the frontend.
+#[derive(Debug, Clone, Serialize, Type, Event)]
+#[serde(transparent)]
+#[specta(transparent)]
+pub struct XapEvent(pub XapClientEvent);
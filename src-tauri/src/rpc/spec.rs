@@ -1,14 +1,7 @@
 // This file was generated by xap-specs, do not edit manually
 
 pub mod error {
-    #[derive(Debug, Clone, serde::Deserialize, serde::Serialize, specta::Type)]
-    pub struct Error(pub String);
-
-    impl From<anyhow::Error> for Error {
-        fn from(err: anyhow::Error) -> Self {
-            Self(err.to_string())
-        }
-    }
+    pub use xap_specs::error::XapError as Error;
 }
 
 #[allow(dead_code)]
@@ -20,10 +13,11 @@ pub mod xap {
     use tauri::State;
     use uuid::Uuid;
 
+    use xap_client::spec::types::*;
+    use xap_client::spec::xap::*;
+    use xap_client::XapClient;
+
     use crate::rpc::spec::error::Error;
-    use crate::xap::client::XapClient;
-    use crate::xap::spec::types::*;
-    use crate::xap::spec::xap::*;
 
     #[tauri::command]
     #[specta::specta]
@@ -31,11 +25,7 @@ pub mod xap {
         id: Uuid,
         state: State<'_, Arc<Mutex<XapClient>>>,
     ) -> Result<XapVersionResponse, Error> {
-        state
-            .lock()
-            .unwrap()
-            .query(id, XapVersionRequest(()))
-            .map_err(Into::into)
+        state.lock().unwrap().query(id, XapVersionRequest(()))
     }
 
     #[tauri::command]
@@ -44,11 +34,7 @@ pub mod xap {
         id: Uuid,
         state: State<'_, Arc<Mutex<XapClient>>>,
     ) -> Result<XapCapabilitiesFlags, Error> {
-        state
-            .lock()
-            .unwrap()
-            .query(id, XapCapabilitiesRequest(()))
-            .map_err(Into::into)
+        state.lock().unwrap().query(id, XapCapabilitiesRequest(()))
     }
 
     #[tauri::command]
@@ -61,7 +47,6 @@ pub mod xap {
             .lock()
             .unwrap()
             .query(id, XapEnabledSubsystemCapabilitiesRequest(()))
-            .map_err(Into::into)
     }
 
     #[tauri::command]
@@ -70,11 +55,7 @@ pub mod xap {
         id: Uuid,
         state: State<'_, Arc<Mutex<XapClient>>>,
     ) -> Result<XapSecureStatusResponse, Error> {
-        state
-            .lock()
-            .unwrap()
-            .query(id, XapSecureStatusRequest(()))
-            .map_err(Into::into)
+        state.lock().unwrap().query(id, XapSecureStatusRequest(()))
     }
 
     #[tauri::command]
@@ -83,21 +64,13 @@ pub mod xap {
         id: Uuid,
         state: State<'_, Arc<Mutex<XapClient>>>,
     ) -> Result<(), Error> {
-        state
-            .lock()
-            .unwrap()
-            .query(id, XapSecureUnlockRequest(()))
-            .map_err(Into::into)
+        state.lock().unwrap().query(id, XapSecureUnlockRequest(()))
     }
 
     #[tauri::command]
     #[specta::specta]
     pub fn xap_secure_lock(id: Uuid, state: State<'_, Arc<Mutex<XapClient>>>) -> Result<(), Error> {
-        state
-            .lock()
-            .unwrap()
-            .query(id, XapSecureLockRequest(()))
-            .map_err(Into::into)
+        state.lock().unwrap().query(id, XapSecureLockRequest(()))
     }
 }
 
@@ -110,10 +83,11 @@ pub mod qmk {
     use tauri::State;
     use uuid::Uuid;
 
+    use xap_client::spec::qmk::*;
+    use xap_client::spec::types::*;
+    use xap_client::XapClient;
+
     use crate::rpc::spec::error::Error;
-    use crate::xap::client::XapClient;
-    use crate::xap::spec::qmk::*;
-    use crate::xap::spec::types::*;
 
     #[tauri::command]
     #[specta::specta]
@@ -121,11 +95,7 @@ pub mod qmk {
         id: Uuid,
         state: State<'_, Arc<Mutex<XapClient>>>,
     ) -> Result<QmkVersionResponse, Error> {
-        state
-            .lock()
-            .unwrap()
-            .query(id, QmkVersionRequest(()))
-            .map_err(Into::into)
+        state.lock().unwrap().query(id, QmkVersionRequest(()))
     }
 
     #[tauri::command]
@@ -134,11 +104,7 @@ pub mod qmk {
         id: Uuid,
         state: State<'_, Arc<Mutex<XapClient>>>,
     ) -> Result<QmkCapabilitiesFlags, Error> {
-        state
-            .lock()
-            .unwrap()
-            .query(id, QmkCapabilitiesRequest(()))
-            .map_err(Into::into)
+        state.lock().unwrap().query(id, QmkCapabilitiesRequest(()))
     }
 
     #[tauri::command]
@@ -151,7 +117,6 @@ pub mod qmk {
             .lock()
             .unwrap()
             .query(id, QmkBoardIdentifiersRequest(()))
-            .map_err(Into::into)
     }
 
     #[tauri::command]
@@ -164,7 +129,6 @@ pub mod qmk {
             .lock()
             .unwrap()
             .query(id, QmkBoardManufacturerRequest(()))
-            .map_err(Into::into)
     }
 
     #[tauri::command]
@@ -173,11 +137,7 @@ pub mod qmk {
         id: Uuid,
         state: State<'_, Arc<Mutex<XapClient>>>,
     ) -> Result<QmkProductNameResponse, Error> {
-        state
-            .lock()
-            .unwrap()
-            .query(id, QmkProductNameRequest(()))
-            .map_err(Into::into)
+        state.lock().unwrap().query(id, QmkProductNameRequest(()))
     }
 
     #[tauri::command]
@@ -190,7 +150,6 @@ pub mod qmk {
             .lock()
             .unwrap()
             .query(id, QmkConfigBlobLengthRequest(()))
-            .map_err(Into::into)
     }
 
     #[tauri::command]
@@ -204,7 +163,6 @@ pub mod qmk {
             .lock()
             .unwrap()
             .query(id, QmkConfigBlobChunkRequest(arg))
-            .map_err(Into::into)
     }
 
     #[tauri::command]
@@ -217,7 +175,6 @@ pub mod qmk {
             .lock()
             .unwrap()
             .query(id, QmkJumpToBootloaderRequest(()))
-            .map_err(Into::into)
     }
 
     #[tauri::command]
@@ -230,7 +187,6 @@ pub mod qmk {
             .lock()
             .unwrap()
             .query(id, QmkHardwareIdentifierRequest(()))
-            .map_err(Into::into)
     }
 
     #[tauri::command]
@@ -243,7 +199,6 @@ pub mod qmk {
             .lock()
             .unwrap()
             .query(id, QmkReinitializeEepromRequest(()))
-            .map_err(Into::into)
     }
 }
 
@@ -256,10 +211,11 @@ pub mod keyboard {
     use tauri::State;
     use uuid::Uuid;
 
+    use xap_client::spec::keyboard::*;
+    use xap_client::spec::types::*;
+    use xap_client::XapClient;
+
     use crate::rpc::spec::error::Error;
-    use crate::xap::client::XapClient;
-    use crate::xap::spec::keyboard::*;
-    use crate::xap::spec::types::*;
 }
 
 #[allow(dead_code)]
@@ -271,10 +227,11 @@ pub mod user {
     use tauri::State;
     use uuid::Uuid;
 
+    use xap_client::spec::types::*;
+    use xap_client::spec::user::*;
+    use xap_client::XapClient;
+
     use crate::rpc::spec::error::Error;
-    use crate::xap::client::XapClient;
-    use crate::xap::spec::types::*;
-    use crate::xap::spec::user::*;
 }
 
 #[allow(dead_code)]
@@ -286,10 +243,11 @@ pub mod keymap {
     use tauri::State;
     use uuid::Uuid;
 
+    use xap_client::spec::keymap::*;
+    use xap_client::spec::types::*;
+    use xap_client::XapClient;
+
     use crate::rpc::spec::error::Error;
-    use crate::xap::client::XapClient;
-    use crate::xap::spec::keymap::*;
-    use crate::xap::spec::types::*;
 
     #[tauri::command]
     #[specta::specta]
@@ -301,7 +259,6 @@ pub mod keymap {
             .lock()
             .unwrap()
             .query(id, KeymapCapabilitiesRequest(()))
-            .map_err(Into::into)
     }
 
     #[tauri::command]
@@ -314,7 +271,6 @@ pub mod keymap {
             .lock()
             .unwrap()
             .query(id, KeymapGetLayerCountRequest(()))
-            .map_err(Into::into)
     }
 
     #[tauri::command]
@@ -328,7 +284,6 @@ pub mod keymap {
             .lock()
             .unwrap()
             .query(id, KeymapGetKeycodeRequest(arg))
-            .map_err(Into::into)
     }
 
     #[tauri::command]
@@ -342,7 +297,6 @@ pub mod keymap {
             .lock()
             .unwrap()
             .query(id, KeymapGetEncoderKeycodeRequest(arg))
-            .map_err(Into::into)
     }
 }
 
@@ -355,10 +309,11 @@ pub mod remapping {
     use tauri::State;
     use uuid::Uuid;
 
+    use xap_client::spec::remapping::*;
+    use xap_client::spec::types::*;
+    use xap_client::XapClient;
+
     use crate::rpc::spec::error::Error;
-    use crate::xap::client::XapClient;
-    use crate::xap::spec::remapping::*;
-    use crate::xap::spec::types::*;
 
     #[tauri::command]
     #[specta::specta]
@@ -370,7 +325,6 @@ pub mod remapping {
             .lock()
             .unwrap()
             .query(id, RemappingCapabilitiesRequest(()))
-            .map_err(Into::into)
     }
 
     #[tauri::command]
@@ -383,7 +337,6 @@ pub mod remapping {
             .lock()
             .unwrap()
             .query(id, RemappingGetLayerCountRequest(()))
-            .map_err(Into::into)
     }
 
     #[tauri::command]
@@ -397,7 +350,6 @@ pub mod remapping {
             .lock()
             .unwrap()
             .query(id, RemappingSetKeycodeRequest(arg))
-            .map_err(Into::into)
     }
 
     #[tauri::command]
@@ -411,7 +363,6 @@ pub mod remapping {
             .lock()
             .unwrap()
             .query(id, RemappingSetEncoderKeycodeRequest(arg))
-            .map_err(Into::into)
     }
 }
 
@@ -424,10 +375,11 @@ pub mod lighting {
     use tauri::State;
     use uuid::Uuid;
 
+    use xap_client::spec::lighting::*;
+    use xap_client::spec::types::*;
+    use xap_client::XapClient;
+
     use crate::rpc::spec::error::Error;
-    use crate::xap::client::XapClient;
-    use crate::xap::spec::lighting::*;
-    use crate::xap::spec::types::*;
 
     #[tauri::command]
     #[specta::specta]
@@ -439,7 +391,6 @@ pub mod lighting {
             .lock()
             .unwrap()
             .query(id, LightingCapabilitiesRequest(()))
-            .map_err(Into::into)
     }
 
     #[allow(dead_code)]
@@ -451,10 +402,11 @@ pub mod lighting {
         use tauri::State;
         use uuid::Uuid;
 
+        use xap_client::spec::lighting::backlight::*;
+        use xap_client::spec::types::*;
+        use xap_client::XapClient;
+
         use crate::rpc::spec::error::Error;
-        use crate::xap::client::XapClient;
-        use crate::xap::spec::lighting::backlight::*;
-        use crate::xap::spec::types::*;
 
         #[tauri::command]
         #[specta::specta]
@@ -466,7 +418,6 @@ pub mod lighting {
                 .lock()
                 .unwrap()
                 .query(id, BacklightCapabilitiesRequest(()))
-                .map_err(Into::into)
         }
 
         #[tauri::command]
@@ -479,7 +430,6 @@ pub mod lighting {
                 .lock()
                 .unwrap()
                 .query(id, BacklightGetEnabledEffectsRequest(()))
-                .map_err(Into::into)
         }
 
         #[tauri::command]
@@ -492,7 +442,6 @@ pub mod lighting {
                 .lock()
                 .unwrap()
                 .query(id, BacklightGetConfigRequest(()))
-                .map_err(Into::into)
         }
 
         #[tauri::command]
@@ -506,7 +455,6 @@ pub mod lighting {
                 .lock()
                 .unwrap()
                 .query(id, BacklightSetConfigRequest(arg))
-                .map_err(Into::into)
         }
 
         #[tauri::command]
@@ -519,7 +467,6 @@ pub mod lighting {
                 .lock()
                 .unwrap()
                 .query(id, BacklightSaveConfigRequest(()))
-                .map_err(Into::into)
         }
     }
 
@@ -532,10 +479,11 @@ pub mod lighting {
         use tauri::State;
         use uuid::Uuid;
 
+        use xap_client::spec::lighting::rgblight::*;
+        use xap_client::spec::types::*;
+        use xap_client::XapClient;
+
         use crate::rpc::spec::error::Error;
-        use crate::xap::client::XapClient;
-        use crate::xap::spec::lighting::rgblight::*;
-        use crate::xap::spec::types::*;
 
         #[tauri::command]
         #[specta::specta]
@@ -547,7 +495,6 @@ pub mod lighting {
                 .lock()
                 .unwrap()
                 .query(id, RgblightCapabilitiesRequest(()))
-                .map_err(Into::into)
         }
 
         #[tauri::command]
@@ -560,7 +507,6 @@ pub mod lighting {
                 .lock()
                 .unwrap()
                 .query(id, RgblightGetEnabledEffectsRequest(()))
-                .map_err(Into::into)
         }
 
         #[tauri::command]
@@ -573,7 +519,6 @@ pub mod lighting {
                 .lock()
                 .unwrap()
                 .query(id, RgblightGetConfigRequest(()))
-                .map_err(Into::into)
         }
 
         #[tauri::command]
@@ -587,7 +532,6 @@ pub mod lighting {
                 .lock()
                 .unwrap()
                 .query(id, RgblightSetConfigRequest(arg))
-                .map_err(Into::into)
         }
 
         #[tauri::command]
@@ -600,7 +544,6 @@ pub mod lighting {
                 .lock()
                 .unwrap()
                 .query(id, RgblightSaveConfigRequest(()))
-                .map_err(Into::into)
         }
     }
 
@@ -613,10 +556,11 @@ pub mod lighting {
         use tauri::State;
         use uuid::Uuid;
 
+        use xap_client::spec::lighting::rgbmatrix::*;
+        use xap_client::spec::types::*;
+        use xap_client::XapClient;
+
         use crate::rpc::spec::error::Error;
-        use crate::xap::client::XapClient;
-        use crate::xap::spec::lighting::rgbmatrix::*;
-        use crate::xap::spec::types::*;
 
         #[tauri::command]
         #[specta::specta]
@@ -628,7 +572,6 @@ pub mod lighting {
                 .lock()
                 .unwrap()
                 .query(id, RgbmatrixCapabilitiesRequest(()))
-                .map_err(Into::into)
         }
 
         #[tauri::command]
@@ -641,7 +584,6 @@ pub mod lighting {
                 .lock()
                 .unwrap()
                 .query(id, RgbmatrixGetEnabledEffectsRequest(()))
-                .map_err(Into::into)
         }
 
         #[tauri::command]
@@ -654,7 +596,6 @@ pub mod lighting {
                 .lock()
                 .unwrap()
                 .query(id, RgbmatrixGetConfigRequest(()))
-                .map_err(Into::into)
         }
 
         #[tauri::command]
@@ -668,7 +609,6 @@ pub mod lighting {
                 .lock()
                 .unwrap()
                 .query(id, RgbmatrixSetConfigRequest(arg))
-                .map_err(Into::into)
         }
 
         #[tauri::command]
@@ -681,7 +621,6 @@ pub mod lighting {
                 .lock()
                 .unwrap()
                 .query(id, RgbmatrixSaveConfigRequest(()))
-                .map_err(Into::into)
         }
     }
 }
@@ -695,10 +634,11 @@ pub mod audio {
     use tauri::State;
     use uuid::Uuid;
 
+    use xap_client::spec::audio::*;
+    use xap_client::spec::types::*;
+    use xap_client::XapClient;
+
     use crate::rpc::spec::error::Error;
-    use crate::xap::client::XapClient;
-    use crate::xap::spec::audio::*;
-    use crate::xap::spec::types::*;
 
     #[tauri::command]
     #[specta::specta]
@@ -710,7 +650,6 @@ pub mod audio {
             .lock()
             .unwrap()
             .query(id, AudioCapabilitiesRequest(()))
-            .map_err(Into::into)
     }
 
     #[tauri::command]
@@ -719,11 +658,7 @@ pub mod audio {
         id: Uuid,
         state: State<'_, Arc<Mutex<XapClient>>>,
     ) -> Result<AudioConfig, Error> {
-        state
-            .lock()
-            .unwrap()
-            .query(id, AudioGetConfigRequest(()))
-            .map_err(Into::into)
+        state.lock().unwrap().query(id, AudioGetConfigRequest(()))
     }
 
     #[tauri::command]
@@ -733,11 +668,7 @@ pub mod audio {
         arg: AudioConfig,
         state: State<'_, Arc<Mutex<XapClient>>>,
     ) -> Result<(), Error> {
-        state
-            .lock()
-            .unwrap()
-            .query(id, AudioSetConfigRequest(arg))
-            .map_err(Into::into)
+        state.lock().unwrap().query(id, AudioSetConfigRequest(arg))
     }
 
     #[tauri::command]
@@ -746,11 +677,7 @@ pub mod audio {
         id: Uuid,
         state: State<'_, Arc<Mutex<XapClient>>>,
     ) -> Result<(), Error> {
-        state
-            .lock()
-            .unwrap()
-            .query(id, AudioSaveConfigRequest(()))
-            .map_err(Into::into)
+        state.lock().unwrap().query(id, AudioSaveConfigRequest(()))
     }
 }
 
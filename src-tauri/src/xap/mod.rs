@@ -1,5 +0,0 @@
-// Module which contains everything needed to communicate to a XAP-enabled device
-
-pub mod client;
-pub mod device;
-pub mod spec;
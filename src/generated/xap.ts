@@ -1,7 +1,7 @@
 // This file was generated by [tauri-specta](https://github.com/oscartbeaumont/tauri-specta). Do not edit this file manually.
 
 export const commands = {
-    async xapVersion(id: string): Promise<Result<XapVersionResponse, Error>> {
+    async xapVersion(id: string): Promise<Result<XapVersionResponse, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('xap_version', { id }) }
         } catch (e) {
@@ -9,7 +9,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async xapCapabilities(id: string): Promise<Result<XapCapabilitiesFlags, Error>> {
+    async xapCapabilities(id: string): Promise<Result<XapCapabilitiesFlags, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('xap_capabilities', { id }) }
         } catch (e) {
@@ -19,7 +19,7 @@ export const commands = {
     },
     async xapEnabledSubsystemCapabilities(
         id: string,
-    ): Promise<Result<XapEnabledSubsystemCapabilitiesFlags, Error>> {
+    ): Promise<Result<XapEnabledSubsystemCapabilitiesFlags, XapError>> {
         try {
             return {
                 status: 'ok',
@@ -30,7 +30,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async xapSecureStatus(id: string): Promise<Result<XapSecureStatusResponse, Error>> {
+    async xapSecureStatus(id: string): Promise<Result<XapSecureStatusResponse, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('xap_secure_status', { id }) }
         } catch (e) {
@@ -38,7 +38,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async xapSecureUnlock(id: string): Promise<Result<null, Error>> {
+    async xapSecureUnlock(id: string): Promise<Result<null, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('xap_secure_unlock', { id }) }
         } catch (e) {
@@ -46,7 +46,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async xapSecureLock(id: string): Promise<Result<null, Error>> {
+    async xapSecureLock(id: string): Promise<Result<null, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('xap_secure_lock', { id }) }
         } catch (e) {
@@ -54,7 +54,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async qmkVersion(id: string): Promise<Result<QmkVersionResponse, Error>> {
+    async qmkVersion(id: string): Promise<Result<QmkVersionResponse, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('qmk_version', { id }) }
         } catch (e) {
@@ -62,7 +62,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async qmkCapabilities(id: string): Promise<Result<QmkCapabilitiesFlags, Error>> {
+    async qmkCapabilities(id: string): Promise<Result<QmkCapabilitiesFlags, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('qmk_capabilities', { id }) }
         } catch (e) {
@@ -70,7 +70,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async qmkBoardIdentifiers(id: string): Promise<Result<QmkBoardIdentifiersResponse, Error>> {
+    async qmkBoardIdentifiers(id: string): Promise<Result<QmkBoardIdentifiersResponse, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('qmk_board_identifiers', { id }) }
         } catch (e) {
@@ -78,7 +78,9 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async qmkBoardManufacturer(id: string): Promise<Result<QmkBoardManufacturerResponse, Error>> {
+    async qmkBoardManufacturer(
+        id: string,
+    ): Promise<Result<QmkBoardManufacturerResponse, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('qmk_board_manufacturer', { id }) }
         } catch (e) {
@@ -86,7 +88,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async qmkProductName(id: string): Promise<Result<QmkProductNameResponse, Error>> {
+    async qmkProductName(id: string): Promise<Result<QmkProductNameResponse, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('qmk_product_name', { id }) }
         } catch (e) {
@@ -94,7 +96,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async qmkConfigBlobLength(id: string): Promise<Result<QmkConfigBlobLengthResponse, Error>> {
+    async qmkConfigBlobLength(id: string): Promise<Result<QmkConfigBlobLengthResponse, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('qmk_config_blob_length', { id }) }
         } catch (e) {
@@ -105,7 +107,7 @@ export const commands = {
     async qmkConfigBlobChunk(
         id: string,
         arg: number,
-    ): Promise<Result<QmkConfigBlobChunkResponse, Error>> {
+    ): Promise<Result<QmkConfigBlobChunkResponse, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('qmk_config_blob_chunk', { id, arg }) }
         } catch (e) {
@@ -113,7 +115,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async qmkJumpToBootloader(id: string): Promise<Result<QmkJumpToBootloaderResponse, Error>> {
+    async qmkJumpToBootloader(id: string): Promise<Result<QmkJumpToBootloaderResponse, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('qmk_jump_to_bootloader', { id }) }
         } catch (e) {
@@ -121,7 +123,9 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async qmkHardwareIdentifier(id: string): Promise<Result<QmkHardwareIdentifierResponse, Error>> {
+    async qmkHardwareIdentifier(
+        id: string,
+    ): Promise<Result<QmkHardwareIdentifierResponse, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('qmk_hardware_identifier', { id }) }
         } catch (e) {
@@ -129,7 +133,9 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async qmkReinitializeEeprom(id: string): Promise<Result<QmkReinitializeEepromResponse, Error>> {
+    async qmkReinitializeEeprom(
+        id: string,
+    ): Promise<Result<QmkReinitializeEepromResponse, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('qmk_reinitialize_eeprom', { id }) }
         } catch (e) {
@@ -137,7 +143,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async keymapCapabilities(id: string): Promise<Result<KeymapCapabilitiesFlags, Error>> {
+    async keymapCapabilities(id: string): Promise<Result<KeymapCapabilitiesFlags, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('keymap_capabilities', { id }) }
         } catch (e) {
@@ -145,7 +151,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async keymapGetLayerCount(id: string): Promise<Result<KeymapGetLayerCountResponse, Error>> {
+    async keymapGetLayerCount(id: string): Promise<Result<KeymapGetLayerCountResponse, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('keymap_get_layer_count', { id }) }
         } catch (e) {
@@ -156,7 +162,7 @@ export const commands = {
     async keymapGetKeycode(
         id: string,
         arg: KeymapGetKeycodeArg,
-    ): Promise<Result<KeymapGetKeycodeResponse, Error>> {
+    ): Promise<Result<KeymapGetKeycodeResponse, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('keymap_get_keycode', { id, arg }) }
         } catch (e) {
@@ -167,7 +173,7 @@ export const commands = {
     async keymapGetEncoderKeycode(
         id: string,
         arg: KeymapGetEncoderKeycodeArg,
-    ): Promise<Result<KeymapGetEncoderKeycodeResponse, Error>> {
+    ): Promise<Result<KeymapGetEncoderKeycodeResponse, XapError>> {
         try {
             return {
                 status: 'ok',
@@ -178,7 +184,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async remappingCapabilities(id: string): Promise<Result<RemappingCapabilitiesFlags, Error>> {
+    async remappingCapabilities(id: string): Promise<Result<RemappingCapabilitiesFlags, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('remapping_capabilities', { id }) }
         } catch (e) {
@@ -188,7 +194,7 @@ export const commands = {
     },
     async remappingGetLayerCount(
         id: string,
-    ): Promise<Result<RemappingGetLayerCountResponse, Error>> {
+    ): Promise<Result<RemappingGetLayerCountResponse, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('remapping_get_layer_count', { id }) }
         } catch (e) {
@@ -199,7 +205,7 @@ export const commands = {
     async remappingSetKeycode(
         id: string,
         arg: RemappingSetKeycodeArg,
-    ): Promise<Result<null, Error>> {
+    ): Promise<Result<null, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('remapping_set_keycode', { id, arg }) }
         } catch (e) {
@@ -210,7 +216,7 @@ export const commands = {
     async remappingSetEncoderKeycode(
         id: string,
         arg: RemappingSetEncoderKeycodeArg,
-    ): Promise<Result<null, Error>> {
+    ): Promise<Result<null, XapError>> {
         try {
             return {
                 status: 'ok',
@@ -221,7 +227,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async lightingCapabilities(id: string): Promise<Result<LightingCapabilitiesFlags, Error>> {
+    async lightingCapabilities(id: string): Promise<Result<LightingCapabilitiesFlags, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('lighting_capabilities', { id }) }
         } catch (e) {
@@ -229,7 +235,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async backlightCapabilities(id: string): Promise<Result<BacklightCapabilitiesFlags, Error>> {
+    async backlightCapabilities(id: string): Promise<Result<BacklightCapabilitiesFlags, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('backlight_capabilities', { id }) }
         } catch (e) {
@@ -239,7 +245,7 @@ export const commands = {
     },
     async backlightGetEnabledEffects(
         id: string,
-    ): Promise<Result<BacklightGetEnabledEffectsResponse, Error>> {
+    ): Promise<Result<BacklightGetEnabledEffectsResponse, XapError>> {
         try {
             return {
                 status: 'ok',
@@ -250,7 +256,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async backlightGetConfig(id: string): Promise<Result<BacklightConfig, Error>> {
+    async backlightGetConfig(id: string): Promise<Result<BacklightConfig, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('backlight_get_config', { id }) }
         } catch (e) {
@@ -258,7 +264,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async backlightSetConfig(id: string, arg: BacklightConfig): Promise<Result<null, Error>> {
+    async backlightSetConfig(id: string, arg: BacklightConfig): Promise<Result<null, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('backlight_set_config', { id, arg }) }
         } catch (e) {
@@ -266,7 +272,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async backlightSaveConfig(id: string): Promise<Result<null, Error>> {
+    async backlightSaveConfig(id: string): Promise<Result<null, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('backlight_save_config', { id }) }
         } catch (e) {
@@ -274,7 +280,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async rgblightCapabilities(id: string): Promise<Result<RgblightCapabilitiesFlags, Error>> {
+    async rgblightCapabilities(id: string): Promise<Result<RgblightCapabilitiesFlags, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('rgblight_capabilities', { id }) }
         } catch (e) {
@@ -284,7 +290,7 @@ export const commands = {
     },
     async rgblightGetEnabledEffects(
         id: string,
-    ): Promise<Result<RgblightGetEnabledEffectsResponse, Error>> {
+    ): Promise<Result<RgblightGetEnabledEffectsResponse, XapError>> {
         try {
             return {
                 status: 'ok',
@@ -295,7 +301,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async rgblightGetConfig(id: string): Promise<Result<RgbLightConfig, Error>> {
+    async rgblightGetConfig(id: string): Promise<Result<RgbLightConfig, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('rgblight_get_config', { id }) }
         } catch (e) {
@@ -303,7 +309,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async rgblightSetConfig(id: string, arg: RgbLightConfig): Promise<Result<null, Error>> {
+    async rgblightSetConfig(id: string, arg: RgbLightConfig): Promise<Result<null, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('rgblight_set_config', { id, arg }) }
         } catch (e) {
@@ -311,7 +317,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async rgblightSaveConfig(id: string): Promise<Result<null, Error>> {
+    async rgblightSaveConfig(id: string): Promise<Result<null, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('rgblight_save_config', { id }) }
         } catch (e) {
@@ -319,7 +325,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async rgbmatrixCapabilities(id: string): Promise<Result<RgbmatrixCapabilitiesFlags, Error>> {
+    async rgbmatrixCapabilities(id: string): Promise<Result<RgbmatrixCapabilitiesFlags, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('rgbmatrix_capabilities', { id }) }
         } catch (e) {
@@ -329,7 +335,7 @@ export const commands = {
     },
     async rgbmatrixGetEnabledEffects(
         id: string,
-    ): Promise<Result<RgbmatrixGetEnabledEffectsResponse, Error>> {
+    ): Promise<Result<RgbmatrixGetEnabledEffectsResponse, XapError>> {
         try {
             return {
                 status: 'ok',
@@ -340,7 +346,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async rgbmatrixGetConfig(id: string): Promise<Result<RgbMatrixConfig, Error>> {
+    async rgbmatrixGetConfig(id: string): Promise<Result<RgbMatrixConfig, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('rgbmatrix_get_config', { id }) }
         } catch (e) {
@@ -348,7 +354,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async rgbmatrixSetConfig(id: string, arg: RgbMatrixConfig): Promise<Result<null, Error>> {
+    async rgbmatrixSetConfig(id: string, arg: RgbMatrixConfig): Promise<Result<null, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('rgbmatrix_set_config', { id, arg }) }
         } catch (e) {
@@ -356,7 +362,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async rgbmatrixSaveConfig(id: string): Promise<Result<null, Error>> {
+    async rgbmatrixSaveConfig(id: string): Promise<Result<null, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('rgbmatrix_save_config', { id }) }
         } catch (e) {
@@ -364,7 +370,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async audioCapabilities(id: string): Promise<Result<AudioCapabilitiesFlags, Error>> {
+    async audioCapabilities(id: string): Promise<Result<AudioCapabilitiesFlags, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('audio_capabilities', { id }) }
         } catch (e) {
@@ -372,7 +378,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async audioGetConfig(id: string): Promise<Result<AudioConfig, Error>> {
+    async audioGetConfig(id: string): Promise<Result<AudioConfig, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('audio_get_config', { id }) }
         } catch (e) {
@@ -380,7 +386,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async audioSetConfig(id: string, arg: AudioConfig): Promise<Result<null, Error>> {
+    async audioSetConfig(id: string, arg: AudioConfig): Promise<Result<null, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('audio_set_config', { id, arg }) }
         } catch (e) {
@@ -388,7 +394,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async audioSaveConfig(id: string): Promise<Result<null, Error>> {
+    async audioSaveConfig(id: string): Promise<Result<null, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('audio_save_config', { id }) }
         } catch (e) {
@@ -399,7 +405,48 @@ export const commands = {
     async xapConstantsGet(): Promise<XapConstants> {
         return await TAURI_INVOKE('xap_constants_get')
     },
-    async remapKey(id: string, arg: RemappingSetKeycodeArg): Promise<Result<null, Error>> {
+    /**
+     * Parses a keycode as written in a QMK keymap, e.g. `LT(2, KC_SPC)`. The custom keycodes of the
+     * device with the given id are accepted as well, the keycode is labeled for the given host layout
+     * or the one of the device.
+     */
+    async keycodeParse(
+        expression: string,
+        id: string | null,
+        layout: string | null,
+    ): Promise<Result<KeyCode, XapError>> {
+        try {
+            return {
+                status: 'ok',
+                data: await TAURI_INVOKE('keycode_parse', { expression, id, layout }),
+            }
+        } catch (e) {
+            if (e instanceof Error) throw e
+            else return { status: 'error', error: e as any }
+        }
+    },
+    /**
+     * Fuzzy search over the names, aliases and labels of all keycodes for the keycode picker,
+     * including the custom keycodes of the device with the given id. The keycodes are localized to
+     * the given host layout or the one of the device, e.g. `DE_ODIA` finds `KC_SCLN`.
+     */
+    async keycodesSearch(
+        query: string,
+        limit: number,
+        id: string | null,
+        layout: string | null,
+    ): Promise<Result<KeyCode[], XapError>> {
+        try {
+            return {
+                status: 'ok',
+                data: await TAURI_INVOKE('keycodes_search', { query, limit, id, layout }),
+            }
+        } catch (e) {
+            if (e instanceof Error) throw e
+            else return { status: 'error', error: e as any }
+        }
+    },
+    async remapKey(id: string, arg: RemappingSetKeycodeArg): Promise<Result<null, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('remap_key', { id, arg }) }
         } catch (e) {
@@ -407,7 +454,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async keymapGet(id: string, layout: string): Promise<Result<MappedKeymap, Error>> {
+    async keymapGet(id: string, layout: string): Promise<Result<MappedKeymap, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('keymap_get', { id, layout }) }
         } catch (e) {
@@ -415,7 +462,7 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
-    async deviceGet(id: string): Promise<Result<XapDeviceState, Error>> {
+    async deviceGet(id: string): Promise<Result<XapDeviceState, XapError>> {
         try {
             return { status: 'ok', data: await TAURI_INVOKE('device_get', { id }) }
         } catch (e) {
@@ -423,9 +470,81 @@ export const commands = {
             else return { status: 'error', error: e as any }
         }
     },
+    async deviceAutoUnlockSet(id: string, enabled: boolean): Promise<Result<null, XapError>> {
+        try {
+            return {
+                status: 'ok',
+                data: await TAURI_INVOKE('device_auto_unlock_set', { id, enabled }),
+            }
+        } catch (e) {
+            if (e instanceof Error) throw e
+            else return { status: 'error', error: e as any }
+        }
+    },
+    /**
+     * Localizes the keycode labels of the device to a host keyboard layout, e.g. `german`.
+     */
+    async deviceHostLayoutSet(id: string, layout: string | null): Promise<Result<null, XapError>> {
+        try {
+            return {
+                status: 'ok',
+                data: await TAURI_INVOKE('device_host_layout_set', { id, layout }),
+            }
+        } catch (e) {
+            if (e instanceof Error) throw e
+            else return { status: 'error', error: e as any }
+        }
+    },
+    async deviceLogGet(id: string): Promise<Result<ConsoleLine[], XapError>> {
+        try {
+            return { status: 'ok', data: await TAURI_INVOKE('device_log_get', { id }) }
+        } catch (e) {
+            if (e instanceof Error) throw e
+            else return { status: 'error', error: e as any }
+        }
+    },
+    async deviceLogClear(id: string): Promise<Result<null, XapError>> {
+        try {
+            return { status: 'ok', data: await TAURI_INVOKE('device_log_clear', { id }) }
+        } catch (e) {
+            if (e instanceof Error) throw e
+            else return { status: 'error', error: e as any }
+        }
+    },
+    async deviceLogExport(id: string, path: string): Promise<Result<null, XapError>> {
+        try {
+            return { status: 'ok', data: await TAURI_INVOKE('device_log_export', { id, path }) }
+        } catch (e) {
+            if (e instanceof Error) throw e
+            else return { status: 'error', error: e as any }
+        }
+    },
     async devicesGet(): Promise<XapDeviceState[]> {
         return await TAURI_INVOKE('devices_get')
     },
+    /**
+     * Invokes a route by its name, e.g. `lighting.rgbmatrix.get_config`, for routes that have no
+     * generated command.
+     */
+    async routeInvoke(
+        id: string,
+        route: string,
+        args: JsonValue,
+    ): Promise<Result<JsonValue, XapError>> {
+        try {
+            return { status: 'ok', data: await TAURI_INVOKE('route_invoke', { id, route, args }) }
+        } catch (e) {
+            if (e instanceof Error) throw e
+            else return { status: 'error', error: e as any }
+        }
+    },
+    /**
+     * Metadata of all routes the generated code knows, e.g. to show help texts or gray out routes
+     * a device doesn't support.
+     */
+    async routesMetadataGet(): Promise<RouteMetadata[]> {
+        return await TAURI_INVOKE('routes_metadata_get')
+    },
 }
 
 export const events = __makeEvents__<{
@@ -447,14 +566,91 @@ export type BacklightCapabilitiesFlags = number
  */
 export type BacklightConfig = { enable: number; mode: number; val: number }
 export type BacklightGetEnabledEffectsResponse = number
-export type Config = { layouts: { [key in string]: Layout }; matrix_size: Point2D }
-export type Error = string
+/**
+ * Semantic version encoded as BCD in the `0xXXYYZZZZ` format, which XAP uses for its own
+ * protocol version and the QMK firmware version. E.g. `0x00240008` is version "0.24.8".
+ */
+export type BcdVersion = string
+/**
+ * Keycode that is composed of a basic keycode, modifiers or a layer, e.g. `LT(1, KC_A)`.
+ * Modifiers are in the 5-bit format of QMK, bit 4 selects the right hand modifiers.
+ */
+export type CompoundKeyCode =
+    | { kind: 'mods'; mods: number; keycode: KeyCode }
+    | { kind: 'mod_tap'; mods: number; keycode: KeyCode }
+    | { kind: 'layer_tap'; layer: number; keycode: KeyCode }
+    | { kind: 'layer_mod'; layer: number; mods: number }
+    | { kind: 'to'; layer: number }
+    | { kind: 'momentary'; layer: number }
+    | { kind: 'default_layer'; layer: number }
+    | { kind: 'toggle_layer'; layer: number }
+    | { kind: 'one_shot_layer'; layer: number }
+    | { kind: 'one_shot_mod'; mods: number }
+    | { kind: 'layer_tap_toggle'; layer: number }
+    | { kind: 'swap_hands_tap'; keycode: KeyCode }
+    | { kind: 'tap_dance'; index: number }
+export type Config = {
+    layouts: { [key in string]: Layout }
+    matrix_size: Point2D
+    /**
+     * Custom keycodes of the keyboard, QMK numbers them from `QK_KB_0` in the declared order.
+     */
+    keycodes?: KeyCode[]
+}
+export type ConsoleLine = {
+    /**
+     * Arrival of the first part of the line in milliseconds since the unix epoch.
+     */
+    timestamp: number
+    text: string
+}
+/**
+ * Vendor or user specific broadcast, the payload is only known to the firmware that sent it.
+ */
+export type CustomBroadcast = {
+    payload: number[]
+    /**
+     * Payload as decoded by a registered decoder, `None` if no decoder is registered.
+     */
+    decoded: JsonValue | null
+}
+/**
+ * Labels and aliases of the keycodes on a host keyboard layout, e.g. German QWERTZ. The
+ * tables mirror QMK's `keymap_extras` and are read from the `extras` directory of the specs.
+ */
+export type HostLayout = { name: string; keys: { [key in number]: HostLayoutKey } }
+export type HostLayoutKey = {
+    /**
+     * Alias of the keycode on this layout, e.g. `DE_ODIA`
+     */
+    key: string
+    label?: string | null
+}
+export type JsonValue =
+    | null
+    | boolean
+    | number
+    | string
+    | JsonValue[]
+    | { [key in string]: JsonValue }
 export type KeyCode = {
     code?: number
     key: string
     group?: string | null
     label?: string | null
     aliases?: string[]
+    compound?: CompoundKeyCode
+}
+/**
+ * Range of keycodes from the `ranges` table of the keycode specs, e.g. `QK_LAYER_TAP`.
+ */
+export type KeyCodeRange = {
+    start?: number
+    /**
+     * Last keycode of the range, inclusive
+     */
+    end?: number
+    define: string
 }
 export type KeymapCapabilitiesFlags = number
 export type KeymapGetEncoderKeycodeArg = { layer: number; encoder: number; clockwise: number }
@@ -550,7 +746,7 @@ export type QmkConfigBlobChunkResponse = [
 export type QmkConfigBlobLengthResponse = number
 export type QmkHardwareIdentifierResponse = [number, number, number, number]
 export type QmkInfo = {
-    version: string
+    version: BcdVersion
     board_ids: QmkBoardIdentifiersResponse
     manufacturer: string
     product_name: string
@@ -561,7 +757,7 @@ export type QmkInfo = {
 export type QmkJumpToBootloaderResponse = number
 export type QmkProductNameResponse = UTF8String
 export type QmkReinitializeEepromResponse = number
-export type QmkVersionResponse = number
+export type QmkVersionResponse = BcdVersion
 export type RemapInfo = {
     layer_count: number | null
     set_keycode_enabled: boolean
@@ -603,10 +799,60 @@ export type RgblightCapabilitiesFlags = number
 export type RgblightGetEnabledEffectsResponse = bigint
 export type RgbmatrixCapabilitiesFlags = number
 export type RgbmatrixGetEnabledEffectsResponse = bigint
+/**
+ * Metadata of a command route, as generated from the specs by `xap-specs-codegen`.
+ */
+export type RouteMetadata = {
+    /**
+     * Full id of the route, starting with the ids of the routers it belongs to.
+     */
+    id: number
+    /**
+     * Module of the generated request in `xap_client::spec`, e.g. `lighting::rgblight`.
+     */
+    module: string
+    /**
+     * Path of the route as used by the dynamic route table, e.g. `lighting.rgblight.get_config`.
+     */
+    path: string
+    name: string
+    description: string
+    /**
+     * First XAP version that supports the route.
+     */
+    xap_version: BcdVersion
+    secure: boolean
+    /**
+     * Name of the generated type of the request payload.
+     */
+    request_type: string
+    /**
+     * Name of the generated type of the response payload.
+     */
+    response_type: string
+    /**
+     * Capability bits the device has to report for the route to be usable.
+     */
+    capabilities: XapCapability
+}
 export type UTF8String = string
 export type XapCapabilitiesFlags = number
+/**
+ * A bit in the response of the capabilities query at `route`, it is set if the firmware was
+ * compiled with `condition` being true.
+ */
+export type XapCapability = { route: number; bit: number; condition: string }
+export type XapClientEvent =
+    | { kind: 'LogReceived'; data: { id: string; line: ConsoleLine } }
+    | { kind: 'SecureStatusChanged'; data: { id: string; secure_status: XapSecureStatus } }
+    | { kind: 'KeyboardBroadcast'; data: { id: string; broadcast: CustomBroadcast } }
+    | { kind: 'UserBroadcast'; data: { id: string; broadcast: CustomBroadcast } }
+    | { kind: 'NewDevice'; data: { id: string } }
+    | { kind: 'RemovedDevice'; data: { id: string } }
 export type XapConstants = {
     keycodes: XapKeyCodeCategory[]
+    keycode_ranges: KeyCodeRange[]
+    host_layouts: HostLayout[]
     rgblight_modes: LightingEffects
     rgb_matrix_modes: LightingEffects
     led_matrix_modes: LightingEffects
@@ -623,18 +869,44 @@ export type XapDeviceState = {
     info: XapDeviceInfo | null
     config: Config
     secure_status: XapSecureStatus
+    /**
+     * Host keyboard layout the keycode labels are localized to, e.g. `german`
+     */
+    host_layout: string | null
 }
 export type XapEnabledSubsystemCapabilitiesFlags = number
-export type XapEvent =
-    | { kind: 'LogReceived'; data: { id: string; log: string } }
-    | { kind: 'SecureStatusChanged'; data: { id: string; secure_status: XapSecureStatus } }
-    | { kind: 'NewDevice'; data: { id: string } }
-    | { kind: 'RemovedDevice'; data: { id: string } }
-export type XapInfo = { version: number }
+/**
+ * Structured failure of a XAP operation, serialized with a `kind` tag so the frontend can react
+ * on specific failures.
+ */
+export type XapError =
+    | { kind: 'Timeout'; data: { route: number[] } }
+    | { kind: 'DeviceLocked' }
+    | { kind: 'SecureRouteLocked'; data: { route: number[] } }
+    | { kind: 'UnlockFailed' }
+    | { kind: 'SecureActionFailed' }
+    | { kind: 'RequestFailed' }
+    | { kind: 'UnknownResponseFlags'; data: { flags: number } }
+    | {
+          kind: 'UnsupportedByVersion'
+          data: { route: number[]; required: BcdVersion; device: BcdVersion }
+      }
+    | { kind: 'UnsupportedByCapability'; data: { route: number[]; condition: string } }
+    | { kind: 'UnknownRoute'; data: { route: string } }
+    | { kind: 'InvalidArguments'; data: { route: string; reason: string } }
+    | { kind: 'Decode'; data: string }
+    | { kind: 'Transport'; data: string }
+    | { kind: 'UnknownDevice'; data: string }
+    | { kind: 'Other'; data: string }
+/**
+ * Events of the XAP client as emitted to the frontend.
+ */
+export type XapEvent = XapClientEvent
+export type XapInfo = { version: BcdVersion }
 export type XapKeyCodeCategory = { name: string; codes: KeyCode[] }
 export type XapSecureStatus = 'Locked' | 'Unlocking' | 'Unlocked'
 export type XapSecureStatusResponse = number
-export type XapVersionResponse = number
+export type XapVersionResponse = BcdVersion
 
 /** tauri-specta globals **/
 
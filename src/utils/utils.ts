@@ -3,6 +3,14 @@ import { Notify } from 'quasar'
 export function notifyError(err: unknown) {
     Notify.create({
         type: 'negative',
-        message: 'Error: ' + err,
+        message: 'Error: ' + formatError(err),
     })
 }
+
+// XAP errors are serialized as `{ kind, data }` objects
+export function formatError(err: unknown): string {
+    if (err !== null && typeof err === 'object' && 'kind' in err) {
+        return 'data' in err ? `${err.kind} ${JSON.stringify(err.data)}` : String(err.kind)
+    }
+    return String(err)
+}
@@ -1,17 +1,22 @@
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
-use specta::Type;
+use xap_specs::constants::keycode::KeyCode;
 
 use crate::aggregation::Point2D;
 
-#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct Config {
     pub layouts: HashMap<String, Layout>,
     pub matrix_size: Point2D,
+    /// Custom keycodes of the keyboard, QMK numbers them from `QK_KB_0` in the declared order.
+    #[serde(default)]
+    pub keycodes: Vec<KeyCode>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct Layout {
     #[serde(skip)]
     pub name: String,
@@ -24,7 +29,8 @@ impl Layout {
     }
 }
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize, Type)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct LayoutEntry {
     pub matrix: Point2D,
     pub x: f64,
@@ -590,4 +596,27 @@ mod test {
 
         let _layout: Config = serde_json::from_str(input).unwrap();
     }
+
+    #[test]
+    fn deserialize_custom_keycodes() {
+        let input = r#"{
+"layouts": {},
+"matrix_size": {"cols": 1, "rows": 1},
+"keycodes": [
+    {"key": "CUSTOM_MACRO", "label": "Macro", "aliases": ["CM"]},
+    {"key": "CUSTOM_RESET"}
+]
+}"#;
+
+        let config: Config = serde_json::from_str(input).unwrap();
+        let keys: Vec<&str> = config
+            .keycodes
+            .iter()
+            .map(|keycode| keycode.key.as_str())
+            .collect();
+        assert_eq!(keys, vec!["CUSTOM_MACRO", "CUSTOM_RESET"]);
+        assert_eq!(config.keycodes[0].label.as_deref(), Some("Macro"));
+        assert_eq!(config.keycodes[0].aliases, vec!["CM"]);
+        assert_eq!(config.keycodes[1].label, None);
+    }
 }
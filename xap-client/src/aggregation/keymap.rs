@@ -1,18 +1,19 @@
 use serde::Serialize;
-use specta::Type;
 
 use crate::aggregation::config::LayoutEntry;
-use crate::xap::device::KeymapKey;
+use crate::device::KeymapKey;
 
 use super::{Point2D, Point3D};
 
-#[derive(Clone, Debug, Default, Serialize, Type)]
+#[derive(Clone, Debug, Default, Serialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct MappedKeymapKey {
     pub key: KeymapKey,
     pub layout: LayoutEntry,
 }
 
-#[derive(Clone, Debug, Serialize, Type)]
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct MappedKeymap {
     pub keys: Vec<Vec<Vec<Option<MappedKeymapKey>>>>,
     pub dimensions: Point3D,
@@ -2,14 +2,12 @@ pub mod config;
 pub mod keymap;
 
 use serde::{Deserialize, Serialize};
-use specta::Type;
-use xap_specs::constants::lighting::LightingEffect;
+use xap_specs::{constants::lighting::LightingEffect, version::BcdVersion};
 
-use crate::xap::spec::{keymap::KeymapGetKeycodeArg, qmk::QmkBoardIdentifiersResponse};
+use crate::spec::{keymap::KeymapGetKeycodeArg, qmk::QmkBoardIdentifiersResponse};
 
-#[derive(
-    Default, Debug, Clone, Copy, Serialize, Deserialize, Type, PartialEq, PartialOrd, Hash,
-)]
+#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, PartialOrd, Hash)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct Point2D {
     #[serde(alias = "row")]
     #[serde(alias = "rows")]
@@ -19,9 +17,8 @@ pub struct Point2D {
     pub x: u64,
 }
 
-#[derive(
-    Default, Debug, Clone, Copy, Serialize, Deserialize, Type, PartialEq, PartialOrd, Hash,
-)]
+#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, PartialOrd, Hash)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct Point3D {
     #[serde(alias = "col")]
     #[serde(alias = "cols")]
@@ -44,7 +41,8 @@ impl Into<KeymapGetKeycodeArg> for Point3D {
     }
 }
 
-#[derive(Debug, Serialize, Clone, Type)]
+#[derive(Debug, Serialize, Clone)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct XapDeviceInfo {
     pub xap: XapInfo,
     pub qmk: QmkInfo,
@@ -53,14 +51,16 @@ pub struct XapDeviceInfo {
     pub lighting: Option<LightingInfo>,
 }
 
-#[derive(Debug, Serialize, Clone, Type)]
+#[derive(Debug, Serialize, Clone)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct XapInfo {
-    pub version: u32,
+    pub version: BcdVersion,
 }
 
-#[derive(Debug, Serialize, Clone, Type)]
+#[derive(Debug, Serialize, Clone)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct QmkInfo {
-    pub version: String,
+    pub version: BcdVersion,
     pub board_ids: QmkBoardIdentifiersResponse,
     pub manufacturer: String,
     pub product_name: String,
@@ -69,28 +69,32 @@ pub struct QmkInfo {
     pub eeprom_reset_enabled: bool,
 }
 
-#[derive(Debug, Serialize, Clone, Type)]
+#[derive(Debug, Serialize, Clone)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct KeymapInfo {
     pub layer_count: Option<u8>,
     pub get_keycode_enabled: bool,
     pub get_encoder_keycode_enabled: bool,
 }
 
-#[derive(Debug, Serialize, Clone, Type)]
+#[derive(Debug, Serialize, Clone)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct RemapInfo {
     pub layer_count: Option<u8>,
     pub set_keycode_enabled: bool,
     pub set_encoder_keycode_enabled: bool,
 }
 
-#[derive(Debug, Serialize, Clone, Type)]
+#[derive(Debug, Serialize, Clone)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct LightingInfo {
     pub backlight: Option<LightingCapabilities>,
     pub rgblight: Option<LightingCapabilities>,
     pub rgbmatrix: Option<LightingCapabilities>,
 }
 
-#[derive(Debug, Serialize, Clone, Type)]
+#[derive(Debug, Serialize, Clone)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct LightingCapabilities {
     pub effects: Vec<LightingEffect>,
     pub get_config_enabled: bool,
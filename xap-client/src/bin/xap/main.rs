@@ -0,0 +1,385 @@
+// Command line interface to script XAP devices, all output is printed as JSON
+
+mod repl;
+
+use std::{
+    io::{self, Read},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, Subcommand};
+use env_logger::Env;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use xap_client::{
+    aggregation::{config::Config, Point3D},
+    spec::{
+        audio::{AudioGetConfigRequest, AudioSaveConfigRequest, AudioSetConfigRequest},
+        lighting::{
+            backlight::{
+                BacklightGetConfigRequest, BacklightSaveConfigRequest, BacklightSetConfigRequest,
+            },
+            rgblight::{
+                RgblightGetConfigRequest, RgblightSaveConfigRequest, RgblightSetConfigRequest,
+            },
+            rgbmatrix::{
+                RgbmatrixGetConfigRequest, RgbmatrixSaveConfigRequest, RgbmatrixSetConfigRequest,
+            },
+        },
+        qmk::QmkJumpToBootloaderRequest,
+        remapping::RemappingSetKeycodeArg,
+        xap::XapSecureLockRequest,
+    },
+    XapClient, XapDevice, XapError,
+};
+use xap_specs::{constants::XapConstants, dynamic::RouteTable, request::XapRequest};
+
+fn get_default_spec_dir() -> &'static str {
+    concat!(env!("CARGO_MANIFEST_DIR"), "/../xap-specs/assets")
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Device to talk to, either its HID path or `VID:PID` in hex. Can be omitted if only one
+    /// device is attached.
+    #[arg(long, short)]
+    device: Option<String>,
+    /// Run the unlock sequence when a secure route is requested on a locked device.
+    #[arg(long)]
+    auto_unlock: bool,
+    /// Host keyboard layout to label keycodes with, e.g. `german`
+    #[arg(long)]
+    host_layout: Option<String>,
+    #[arg(long, default_value = get_default_spec_dir())]
+    spec_dir: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List all attached XAP devices
+    List,
+    /// Print the information the device reports about itself
+    Info,
+    /// Dump the keymap mapped onto a layout of the device
+    Keymap {
+        /// Name of the layout, defaults to `LAYOUT` or the first layout of the device.
+        #[arg(long)]
+        layout: Option<String>,
+    },
+    /// Assign a keycode to a key, given as number or as written in a keymap e.g. `0x04`, `KC_A`
+    /// or `LT(1, KC_SPC)`
+    Remap {
+        layer: u8,
+        row: u8,
+        column: u8,
+        keycode: String,
+    },
+    /// Configuration of the backlight subsystem
+    Backlight {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Configuration of the rgblight subsystem
+    Rgblight {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Configuration of the rgbmatrix subsystem
+    Rgbmatrix {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Configuration of the audio subsystem
+    Audio {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Print the code of a keycode as written in a keymap, e.g. `MT(MOD_LSFT, KC_A)`
+    Keycode { keycode: String },
+    /// List the routes of the specs that can be invoked with `call`
+    Routes,
+    /// Invoke a route by its name, e.g. `lighting.rgbmatrix.get_config`
+    Call {
+        route: String,
+        /// JSON arguments of the route or `-` to read them from stdin
+        args: Option<String>,
+    },
+    /// Interactive shell to invoke routes with tab completion, prints broadcasts as they arrive
+    Repl,
+    /// Lock the secure routes of the device
+    Lock,
+    /// Unlock the secure routes, the unlock sequence has to be completed on the device
+    Unlock,
+    /// Jump to the bootloader of the device
+    Bootloader,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    /// Print the current configuration
+    Get,
+    /// Update the configuration from a JSON object, omitted fields keep their current value
+    Set {
+        /// JSON object with the new values or `-` to read it from stdin
+        config: String,
+    },
+    /// Persist the current configuration to EEPROM
+    Save,
+}
+
+fn main() -> ExitCode {
+    env_logger::Builder::from_env(Env::default().default_filter_or("warn")).init();
+
+    let args = Args::parse();
+
+    match run(args) {
+        Ok(Some(output)) => {
+            println!("{output:#}");
+            ExitCode::SUCCESS
+        }
+        Ok(None) => ExitCode::SUCCESS,
+        Err(err) => {
+            // Errors keep their XAP kind so scripts can react on them
+            let err = XapError::from(err);
+            eprintln!(
+                "{}",
+                serde_json::to_string(&err).unwrap_or_else(|_| err.to_string())
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Args) -> Result<Option<Value>> {
+    if let Command::Routes = args.command {
+        let routes = RouteTable::from_dir(&args.spec_dir)?
+            .routes()
+            .map(|route| {
+                serde_json::json!({
+                    "route": route.path,
+                    "id": route.id,
+                    "xap_version": route.xap_version,
+                    "secure": route.secure,
+                    "description": route.description(),
+                })
+            })
+            .collect();
+        return Ok(Some(Value::Array(routes)));
+    }
+
+    let constants = XapConstants::new(args.spec_dir.clone())?;
+
+    if let Command::Keycode { keycode } = args.command {
+        let code = constants.parse_keycode(&keycode)?;
+        return Ok(Some(serde_json::to_value(
+            constants.get_localized_keycode(code, args.host_layout.as_deref()),
+        )?));
+    }
+
+    let mut client = XapClient::new(constants.clone())?;
+    client.enumerate_xap_devices()?;
+
+    if let Command::List = args.command {
+        let devices = client
+            .get_devices()
+            .into_iter()
+            .map(|device| {
+                let identity = device.identity();
+                let info = device.xap_info();
+                serde_json::json!({
+                    "path": identity.path,
+                    "vendor_id": format!("{:04x}", identity.vendor_id),
+                    "product_id": format!("{:04x}", identity.product_id),
+                    "manufacturer": info.qmk.manufacturer,
+                    "product_name": info.qmk.product_name,
+                })
+            })
+            .collect();
+        return Ok(Some(Value::Array(devices)));
+    }
+
+    let id = select_device(&client, args.device.as_deref())?;
+
+    if let Command::Repl = args.command {
+        client
+            .get_device_mut(&id)?
+            .set_auto_unlock(args.auto_unlock);
+        repl::run(client, id, &RouteTable::from_dir(&args.spec_dir)?)?;
+        return Ok(None);
+    }
+
+    let device = client.get_device_mut(&id)?;
+    device.set_auto_unlock(args.auto_unlock);
+    device.set_host_layout(args.host_layout)?;
+
+    Ok(match args.command {
+        Command::List | Command::Keycode { .. } | Command::Routes | Command::Repl => {
+            unreachable!("handled before a device is borrowed")
+        }
+        Command::Info => Some(serde_json::to_value(device.xap_info())?),
+        Command::Keymap { layout } => {
+            let layout = match layout {
+                Some(layout) => layout,
+                None => default_layout(&device.state().config)?,
+            };
+            Some(serde_json::to_value(device.keymap_with_layout(layout)?)?)
+        }
+        Command::Remap {
+            layer,
+            row,
+            column,
+            keycode,
+        } => {
+            device.remap_key(RemappingSetKeycodeArg {
+                layer,
+                row,
+                column,
+                keycode: device.constants().parse_keycode(&keycode)?,
+            })?;
+            Some(serde_json::to_value(device.query_key(Point3D {
+                x: column.into(),
+                y: row.into(),
+                z: layer.into(),
+            })?)?)
+        }
+        Command::Backlight { action } => run_config::<
+            BacklightGetConfigRequest,
+            BacklightSetConfigRequest,
+            BacklightSaveConfigRequest,
+        >(device, action)?,
+        Command::Rgblight { action } => run_config::<
+            RgblightGetConfigRequest,
+            RgblightSetConfigRequest,
+            RgblightSaveConfigRequest,
+        >(device, action)?,
+        Command::Rgbmatrix { action } => run_config::<
+            RgbmatrixGetConfigRequest,
+            RgbmatrixSetConfigRequest,
+            RgbmatrixSaveConfigRequest,
+        >(device, action)?,
+        Command::Audio { action } => {
+            run_config::<AudioGetConfigRequest, AudioSetConfigRequest, AudioSaveConfigRequest>(
+                device, action,
+            )?
+        }
+        Command::Call {
+            route,
+            args: route_args,
+        } => {
+            let routes = RouteTable::from_dir(&args.spec_dir)?;
+            let route_args = match route_args {
+                Some(route_args) => read_json(&route_args)?,
+                None => Value::Null,
+            };
+            Some(device.query_dynamic(&routes, &route, &route_args)?)
+        }
+        Command::Lock => {
+            device.query(XapSecureLockRequest(()))?;
+            None
+        }
+        Command::Unlock => {
+            eprintln!("complete the unlock sequence on the device");
+            device.unlock()?;
+            None
+        }
+        Command::Bootloader => {
+            device.query(QmkJumpToBootloaderRequest(()))?;
+            None
+        }
+    })
+}
+
+fn select_device(client: &XapClient, selector: Option<&str>) -> Result<uuid::Uuid> {
+    let devices = client.get_devices();
+
+    let matches: Vec<&&XapDevice> = match selector {
+        Some(selector) => devices
+            .iter()
+            .filter(|device| {
+                let identity = device.identity();
+                identity.path == selector
+                    || format!("{:04x}:{:04x}", identity.vendor_id, identity.product_id)
+                        == selector.to_lowercase()
+            })
+            .collect(),
+        None => devices.iter().collect(),
+    };
+
+    match matches[..] {
+        [device] => Ok(device.id()),
+        [] => bail!("no matching XAP device found"),
+        _ => bail!(
+            "{} XAP devices match, select one with --device",
+            matches.len()
+        ),
+    }
+}
+
+fn default_layout(config: &Config) -> Result<String> {
+    if config.layouts.contains_key("LAYOUT") {
+        return Ok("LAYOUT".to_owned());
+    }
+    let mut layouts: Vec<&String> = config.layouts.keys().collect();
+    layouts.sort();
+    layouts
+        .first()
+        .map(|layout| layout.to_string())
+        .ok_or_else(|| anyhow!("device has no layouts"))
+}
+
+/// Parses a JSON argument, `-` reads it from stdin instead.
+fn read_json(arg: &str) -> Result<Value> {
+    if arg == "-" {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+        Ok(serde_json::from_str(&input)?)
+    } else {
+        Ok(serde_json::from_str(arg)?)
+    }
+}
+
+fn run_config<Get, Set, Save>(device: &mut XapDevice, action: ConfigAction) -> Result<Option<Value>>
+where
+    Get: XapRequest + From<()>,
+    Get::Response: Serialize + DeserializeOwned,
+    Set: XapRequest + From<Get::Response>,
+    Save: XapRequest + From<()>,
+{
+    match action {
+        ConfigAction::Get => Ok(Some(serde_json::to_value(device.query(Get::from(()))?)?)),
+        ConfigAction::Set { config } => {
+            let Value::Object(update) = read_json(&config)? else {
+                bail!("configuration has to be a JSON object");
+            };
+
+            let mut current = serde_json::to_value(device.query(Get::from(()))?)?;
+            let Value::Object(fields) = &mut current else {
+                bail!("configuration of the device is not a JSON object");
+            };
+            // Unknown fields would be dropped silently when the config is deserialized
+            for (key, value) in update {
+                let Some(field) = fields.get_mut(&key) else {
+                    let known: Vec<&str> = fields.keys().map(String::as_str).collect();
+                    bail!(
+                        "unknown configuration field {key}, expected one of: {}",
+                        known.join(", ")
+                    );
+                };
+                *field = value;
+            }
+
+            device.query(Set::from(serde_json::from_value(current)?))?;
+            Ok(Some(serde_json::to_value(device.query(Get::from(()))?)?))
+        }
+        ConfigAction::Save => {
+            device.query(Save::from(()))?;
+            Ok(None)
+        }
+    }
+}
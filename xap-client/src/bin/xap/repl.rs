@@ -0,0 +1,273 @@
+// Interactive shell to invoke routes of a device by name, meant for firmware development
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use anyhow::Result;
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    validate::Validator, Context, Editor, ExternalPrinter, Helper,
+};
+use serde_json::Value;
+use uuid::Uuid;
+
+use xap_client::{broadcast::CustomBroadcast, XapClient, XapClientEvent};
+use xap_specs::dynamic::{DynamicRoute, RouteTable};
+
+const COMMANDS: [&str; 6] = ["describe", "exit", "help", "quit", "routes", "unlock"];
+const BROADCAST_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Completes route paths one segment at a time and hints the arguments of a complete route.
+struct RouteHelper<'a> {
+    routes: &'a RouteTable,
+}
+
+impl Completer for RouteHelper<'_> {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+        // Routes are either the command itself or the argument of `describe`
+        let (start, prefix) = match line.split_once(' ') {
+            None => (0, line),
+            Some(("describe", route)) if !route.contains(' ') => (line.len() - route.len(), route),
+            _ => return Ok((pos, Vec::new())),
+        };
+
+        let mut candidates: Vec<String> = self
+            .routes
+            .routes()
+            .filter_map(|route| {
+                let rest = route.path.strip_prefix(prefix)?;
+                Some(match rest.find('.') {
+                    Some(end) => format!("{prefix}{}", &rest[..=end]),
+                    None => route.path.clone(),
+                })
+            })
+            .collect();
+        candidates.dedup();
+
+        if start == 0 {
+            candidates.extend(
+                COMMANDS
+                    .iter()
+                    .filter(|command| command.starts_with(prefix))
+                    .map(|command| command.to_string()),
+            );
+        }
+
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for RouteHelper<'_> {
+    type Hint = String;
+
+    fn hint(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Option<String> {
+        if pos < line.len() {
+            return None;
+        }
+        let route = self.routes.get(line.trim_end()).ok()?;
+        match self.routes.request_template(route) {
+            Value::Null => None,
+            template if line.ends_with(' ') => Some(template.to_string()),
+            template => Some(format!(" {template}")),
+        }
+    }
+}
+
+impl Highlighter for RouteHelper<'_> {}
+
+impl Validator for RouteHelper<'_> {}
+
+impl Helper for RouteHelper<'_> {}
+
+/// Reads commands until the user exits, broadcasts of the device are printed as they arrive.
+pub fn run(client: XapClient, id: Uuid, routes: &RouteTable) -> Result<()> {
+    let client = Arc::new(Mutex::new(client));
+    let running = Arc::new(AtomicBool::new(true));
+
+    let mut editor = Editor::new()?;
+    editor.set_helper(Some(RouteHelper { routes }));
+    let printer = print_broadcasts(
+        Arc::clone(&client),
+        id,
+        editor.create_external_printer()?,
+        Arc::clone(&running),
+    );
+
+    println!("connected to device {id}, `help` lists the available commands");
+
+    loop {
+        match editor.readline("xap> ") {
+            Ok(line) => {
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                editor.add_history_entry(line)?;
+
+                match execute(&client, id, routes, line) {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(err) => eprintln!("error: {err}"),
+                }
+            }
+            // Ctrl-C only discards the current line
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    running.store(false, Ordering::SeqCst);
+    printer.join().expect("broadcast printer panicked");
+
+    Ok(())
+}
+
+/// Runs a single command, returns false if the shell should exit.
+fn execute(client: &Mutex<XapClient>, id: Uuid, routes: &RouteTable, line: &str) -> Result<bool> {
+    let (command, args) = match line.split_once(' ') {
+        Some((command, args)) => (command, args.trim()),
+        None => (line, ""),
+    };
+
+    match command {
+        "exit" | "quit" => return Ok(false),
+        "help" => {
+            println!("<route> [json]    invoke a route, e.g. `lighting.rgbmatrix.get_config`");
+            println!("describe <route>  show the description and arguments of a route");
+            println!("routes            list all routes");
+            println!("unlock            unlock the secure routes of the device");
+            println!("exit              leave the shell");
+        }
+        "routes" => {
+            for route in routes.routes() {
+                let summary = route
+                    .description()
+                    .and_then(|description| description.lines().next())
+                    .unwrap_or_default();
+                println!("{:<40} {summary}", route.path);
+            }
+        }
+        "describe" => describe(routes, routes.get(args)?),
+        "unlock" => {
+            println!("complete the unlock sequence on the device");
+            client.lock().unwrap().get_device_mut(&id)?.unlock()?;
+        }
+        path => {
+            let route = routes.get(path)?;
+            let args = if args.is_empty() {
+                Value::Null
+            } else {
+                serde_json::from_str(args)?
+            };
+            let request = routes.encode_request(route, &args)?;
+            let response = client
+                .lock()
+                .unwrap()
+                .get_device_mut(&id)?
+                .query_raw(route, &request)?;
+
+            println!("request:  {}", hex(&[&route.id[..], &request].concat()));
+            println!("response: {}", hex(&response));
+            println!("{:#}", routes.decode_response(route, &response)?);
+        }
+    }
+
+    Ok(true)
+}
+
+fn describe(routes: &RouteTable, route: &DynamicRoute) {
+    println!("{} [{}]", route.path, hex(&route.id));
+    if let Some(description) = route.description() {
+        println!("{}", description.trim_end());
+    }
+    println!("since XAP {}", route.xap_version);
+    if route.secure {
+        println!("secure, the device has to be unlocked");
+    }
+    for capability in &route.capabilities {
+        println!("requires {}", capability.condition);
+    }
+    match routes.request_template(route) {
+        Value::Null => {}
+        template => println!("arguments: {template}"),
+    }
+}
+
+fn print_broadcasts(
+    client: Arc<Mutex<XapClient>>,
+    id: Uuid,
+    mut printer: impl ExternalPrinter + Send + 'static,
+    running: Arc<AtomicBool>,
+) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        while running.load(Ordering::SeqCst) {
+            let events = client.lock().unwrap().poll_devices();
+            match events {
+                Ok(events) => {
+                    for message in events
+                        .into_iter()
+                        .filter_map(|event| format_event(id, event))
+                    {
+                        // The shell is not worth crashing over a lost message
+                        let _ = printer.print(message);
+                    }
+                }
+                Err(err) => {
+                    let _ = printer.print(format!(
+                        "failed to poll device, stopped printing broadcasts: {err}"
+                    ));
+                    return;
+                }
+            }
+            std::thread::sleep(BROADCAST_POLL_INTERVAL);
+        }
+    })
+}
+
+fn format_event(device: Uuid, event: XapClientEvent) -> Option<String> {
+    match event {
+        XapClientEvent::LogReceived { id, line } if id == device => {
+            Some(format!("[log] {}", line.text))
+        }
+        XapClientEvent::SecureStatusChanged { id, secure_status } if id == device => {
+            Some(format!("[secure status] {secure_status}"))
+        }
+        XapClientEvent::KeyboardBroadcast { id, broadcast } if id == device => {
+            Some(format_custom_broadcast("keyboard", &broadcast))
+        }
+        XapClientEvent::UserBroadcast { id, broadcast } if id == device => {
+            Some(format_custom_broadcast("user", &broadcast))
+        }
+        _ => None,
+    }
+}
+
+fn format_custom_broadcast(kind: &str, broadcast: &CustomBroadcast) -> String {
+    match &broadcast.decoded {
+        Some(decoded) => format!("[{kind} broadcast] {} {decoded}", hex(&broadcast.payload)),
+        None => format!("[{kind} broadcast] {}", hex(&broadcast.payload)),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
@@ -0,0 +1,117 @@
+// Decodes the broadcasts of a device into the messages defined in the spec, and the keyboard and
+// user broadcasts with decoders registered by the application
+
+pub use xap_specs::broadcast::{
+    BroadcastDecoder, BroadcastDecoders, CustomBroadcast, XapBroadcastMessage,
+};
+use xap_specs::{
+    broadcast::BroadcastRaw,
+    error::{XapError, XapResult},
+    XapSecureStatus,
+};
+
+use crate::spec::broadcast::{BroadcastType, LogMessageBroadcast, SecureStatusBroadcast};
+
+/// Decodes a broadcast by the broadcast messages of the spec, the payloads of Keyboard and User
+/// broadcasts are handed to the decoders registered for the device.
+pub fn decode_broadcast(
+    decoders: &BroadcastDecoders,
+    vendor_id: u16,
+    product_id: u16,
+    broadcast: BroadcastRaw,
+) -> XapResult<XapBroadcastMessage> {
+    let Some(broadcast_type) = BroadcastType::from_id(broadcast.broadcast_type()) else {
+        return Err(XapError::Decode(format!(
+            "unknown broadcast type {:#04x}",
+            broadcast.broadcast_type()
+        )));
+    };
+
+    Ok(match broadcast_type {
+        BroadcastType::LogMessage => {
+            // The broadcast length already bounds the text, the whole payload is the message
+            let mut text = broadcast.into_xap_broadcast::<LogMessageBroadcast>()?.0;
+            while text.last() == Some(&0) {
+                text.pop();
+            }
+            XapBroadcastMessage::Log(text)
+        }
+        BroadcastType::SecureStatus => XapBroadcastMessage::SecureStatus(XapSecureStatus::from(
+            broadcast.into_xap_broadcast::<SecureStatusBroadcast>()?.0,
+        )),
+        BroadcastType::Keyboard => XapBroadcastMessage::Keyboard(decoders.decode_keyboard(
+            vendor_id,
+            product_id,
+            broadcast.into_payload(),
+        )?),
+        BroadcastType::User => XapBroadcastMessage::User(decoders.decode_user(
+            vendor_id,
+            product_id,
+            broadcast.into_payload(),
+        )?),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use similar_asserts::assert_eq;
+
+    use super::*;
+
+    fn broadcast(broadcast_type: u8, payload: &[u8]) -> BroadcastRaw {
+        let mut report = vec![0xFF, 0xFF, broadcast_type, payload.len() as u8];
+        report.extend_from_slice(payload);
+        BroadcastRaw::from_raw_report(&report).unwrap()
+    }
+
+    #[test]
+    fn decode_custom_broadcasts() {
+        let mut decoders = BroadcastDecoders::default();
+        decoders.register_keyboard(0xFEED, 0x0001, |payload| Ok(json!({ "layer": payload[0] })));
+        decoders.register_user(None, |payload| Ok(json!(payload.len())));
+
+        let XapBroadcastMessage::Keyboard(keyboard) =
+            decode_broadcast(&decoders, 0xFEED, 0x0001, broadcast(2, &[3])).unwrap()
+        else {
+            panic!("expected a keyboard broadcast");
+        };
+        assert_eq!(keyboard.decoded, Some(json!({ "layer": 3 })));
+
+        let XapBroadcastMessage::User(user) =
+            decode_broadcast(&decoders, 0xFEED, 0x0001, broadcast(3, &[1, 2])).unwrap()
+        else {
+            panic!("expected a user broadcast");
+        };
+        assert_eq!(user.decoded, Some(json!(2)));
+    }
+
+    #[test]
+    fn decode_log() {
+        let decoders = BroadcastDecoders::default();
+        let XapBroadcastMessage::Log(log) =
+            decode_broadcast(&decoders, 0, 0, broadcast(0, b"Hello QMK!")).unwrap()
+        else {
+            panic!("expected a log broadcast");
+        };
+        assert_eq!(log, b"Hello QMK!");
+    }
+
+    #[test]
+    fn decode_unknown_broadcast_type() {
+        let decoders = BroadcastDecoders::default();
+        assert_eq!(
+            decode_broadcast(&decoders, 0, 0, broadcast(0x42, &[])).unwrap_err(),
+            XapError::Decode("unknown broadcast type 0x42".to_owned())
+        );
+    }
+
+    #[test]
+    fn decode_secure_status() {
+        let decoders = BroadcastDecoders::default();
+        assert!(matches!(
+            decode_broadcast(&decoders, 0, 0, broadcast(1, &[2])).unwrap(),
+            XapBroadcastMessage::SecureStatus(XapSecureStatus::Unlocked)
+        ));
+    }
+}
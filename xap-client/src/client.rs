@@ -0,0 +1,218 @@
+use std::{collections::HashMap, fmt::Debug, path::PathBuf, sync::Arc};
+
+use anyhow::Result;
+use log::error;
+use serde_json::Value;
+use uuid::Uuid;
+
+use xap_specs::{
+    constants::XapConstants,
+    dynamic::RouteTable,
+    error::{XapError, XapResult},
+    request::XapRequest,
+};
+
+use crate::{
+    broadcast::{decode_broadcast, BroadcastDecoders, XapBroadcastMessage},
+    console,
+    device::XapDevice,
+    event::XapClientEvent,
+    hid::HidDiscovery,
+    recording::RecordingTransport,
+    transport::XapDiscovery,
+};
+
+/// Upper bound of reports read from a single device per polling cycle, so a device that floods
+/// broadcasts can't starve the others.
+pub(crate) const MAX_REPORTS_PER_POLL: usize = 256;
+
+pub struct XapClient {
+    discovery: Box<dyn XapDiscovery>,
+    devices: HashMap<Uuid, XapDevice>,
+    constants: Arc<XapConstants>,
+    recording_directory: Option<PathBuf>,
+    broadcast_decoders: BroadcastDecoders,
+}
+
+impl Debug for XapClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AppState")
+            .field("device", &self.devices)
+            .finish()
+    }
+}
+
+impl XapClient {
+    pub fn new(xap_constants: XapConstants) -> Result<Self> {
+        Ok(Self::with_discovery(
+            xap_constants,
+            Box::new(HidDiscovery::new()?),
+        ))
+    }
+
+    pub fn with_discovery(xap_constants: XapConstants, discovery: Box<dyn XapDiscovery>) -> Self {
+        Self {
+            devices: HashMap::new(),
+            discovery,
+            constants: Arc::new(xap_constants),
+            recording_directory: None,
+            broadcast_decoders: BroadcastDecoders::default(),
+        }
+    }
+
+    /// Records the traffic of all devices that are opened from now on into `directory`.
+    pub fn record_to(&mut self, directory: PathBuf) {
+        self.recording_directory = Some(directory);
+    }
+
+    /// Decoders for the Keyboard and User broadcasts, which are forwarded undecoded otherwise.
+    pub fn broadcast_decoders_mut(&mut self) -> &mut BroadcastDecoders {
+        &mut self.broadcast_decoders
+    }
+
+    pub fn poll_devices(&mut self) -> Result<Vec<XapClientEvent>> {
+        // TODO: implement as callback functions?
+        let mut events = Vec::new();
+        for device in self.devices.values_mut() {
+            // Read everything the device queued since the last cycle, the HID input queue of
+            // the OS is small and drops reports once it is full
+            for _ in 0..MAX_REPORTS_PER_POLL {
+                if device.poll()?.is_none() {
+                    break;
+                }
+            }
+
+            let identity = device.identity().clone();
+            while let Some(broadcast) = device.broadcast_queue.pop_front() {
+                let message = match decode_broadcast(
+                    &self.broadcast_decoders,
+                    identity.vendor_id,
+                    identity.product_id,
+                    broadcast,
+                ) {
+                    Ok(message) => message,
+                    Err(err) => {
+                        // A single malformed broadcast must not stop polling the device
+                        error!("failed to decode broadcast of device {identity}: {err}");
+                        continue;
+                    }
+                };
+
+                let id = device.id();
+                let event = match message {
+                    XapBroadcastMessage::Log(log) => {
+                        // Only completed lines are forwarded, partial lines stay in the console
+                        let lines = device.console_mut().push(&log, console::now());
+                        events.extend(
+                            lines
+                                .into_iter()
+                                .map(|line| XapClientEvent::LogReceived { id, line }),
+                        );
+                        continue;
+                    }
+                    // The device already tracked the new status while polling
+                    XapBroadcastMessage::SecureStatus(_) => XapClientEvent::SecureStatusChanged {
+                        id,
+                        secure_status: *device.secure_status(),
+                    },
+                    XapBroadcastMessage::Keyboard(broadcast) => {
+                        XapClientEvent::KeyboardBroadcast { id, broadcast }
+                    }
+                    XapBroadcastMessage::User(broadcast) => {
+                        XapClientEvent::UserBroadcast { id, broadcast }
+                    }
+                };
+                events.push(event);
+            }
+
+            if let Some(line) = device.console_mut().flush_stale(console::now()) {
+                events.push(XapClientEvent::LogReceived {
+                    id: device.id(),
+                    line,
+                });
+            }
+        }
+
+        Ok(events)
+    }
+
+    pub fn query<T>(&mut self, id: Uuid, request: T) -> XapResult<T::Response>
+    where
+        T: XapRequest,
+    {
+        match self.devices.get_mut(&id) {
+            Some(device) => device.query(request),
+            None => Err(XapError::UnknownDevice(id)),
+        }
+    }
+
+    /// Invokes a route by its name, see [`XapDevice::query_dynamic`].
+    pub fn query_dynamic(
+        &mut self,
+        id: Uuid,
+        routes: &RouteTable,
+        path: &str,
+        args: &Value,
+    ) -> XapResult<Value> {
+        self.get_device_mut(&id)?.query_dynamic(routes, path, args)
+    }
+
+    pub fn xap_constants(&self) -> Arc<XapConstants> {
+        Arc::clone(&self.constants)
+    }
+
+    pub fn enumerate_xap_devices(&mut self) -> Result<Vec<XapClientEvent>> {
+        // TODO: implement as callback functions?
+        let mut events = Vec::new();
+        // 1. Device already enumerated - don't start new capturing thread (announce nothing)
+        // 2. Device already enumerated but error occured - remove old device and restart device (announce removal + announce new device)
+        // 3. Device not enumerated - add device and start capturing (announce new device)
+        let xap_devices = self.discovery.enumerate()?;
+
+        self.devices.retain(|id, known_device| {
+            if xap_devices
+                .iter()
+                .any(|candidate| known_device.is_device(candidate))
+            {
+                true
+            } else {
+                events.push(XapClientEvent::RemovedDevice { id: *id });
+                false
+            }
+        });
+
+        for device in xap_devices {
+            if self
+                .devices
+                .iter()
+                .any(|(_, known_device)| known_device.is_device(&device))
+            {
+                continue;
+            }
+
+            let mut transport = self.discovery.open(&device)?;
+            if let Some(directory) = &self.recording_directory {
+                transport = Box::new(RecordingTransport::create(transport, directory)?);
+            }
+
+            let new_device = XapDevice::new(Arc::clone(&self.constants), transport)?;
+            let id = new_device.id();
+            self.devices.insert(id, new_device);
+            events.push(XapClientEvent::NewDevice { id });
+        }
+
+        Ok(events)
+    }
+
+    pub fn get_device(&self, id: &Uuid) -> XapResult<&XapDevice> {
+        self.devices.get(id).ok_or(XapError::UnknownDevice(*id))
+    }
+
+    pub fn get_device_mut(&mut self, id: &Uuid) -> XapResult<&mut XapDevice> {
+        self.devices.get_mut(id).ok_or(XapError::UnknownDevice(*id))
+    }
+
+    pub fn get_devices(&self) -> Vec<&XapDevice> {
+        self.devices.values().collect()
+    }
+}
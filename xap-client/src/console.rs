@@ -0,0 +1,223 @@
+// Reassembles the text of log broadcasts into lines, replacing `hid_listen` for XAP devices
+
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use serde::Serialize;
+
+/// Number of lines kept per device, older lines are dropped first.
+pub const CONSOLE_CAPACITY: usize = 5000;
+/// Text without a trailing newline is emitted as a line after this time without new output.
+pub const CONSOLE_FLUSH_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub struct ConsoleLine {
+    /// Arrival of the first part of the line in milliseconds since the unix epoch.
+    #[cfg_attr(feature = "specta", specta(type = f64))]
+    pub timestamp: u64,
+    pub text: String,
+}
+
+/// Bounded console output of a single device.
+#[derive(Debug)]
+pub struct DeviceConsole {
+    lines: VecDeque<ConsoleLine>,
+    /// Raw output of the current line and the arrival of its first part, it is only decoded once
+    /// the line is complete as multibyte characters may be split across broadcasts.
+    partial: Option<(u64, Vec<u8>)>,
+    last_output: u64,
+    capacity: usize,
+}
+
+impl Default for DeviceConsole {
+    fn default() -> Self {
+        Self::with_capacity(CONSOLE_CAPACITY)
+    }
+}
+
+impl DeviceConsole {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::with_capacity(capacity),
+            partial: None,
+            last_output: 0,
+            capacity,
+        }
+    }
+
+    /// Appends the payload of a log broadcast and returns all lines it completed.
+    pub fn push(&mut self, output: &[u8], timestamp: u64) -> Vec<ConsoleLine> {
+        let mut completed = Vec::new();
+        self.last_output = timestamp;
+
+        for chunk in output.split_inclusive(|byte| *byte == b'\n') {
+            let (_, partial) = self.partial.get_or_insert_with(|| (timestamp, Vec::new()));
+            partial.extend_from_slice(chunk);
+
+            if chunk.ends_with(b"\n") {
+                if let Some(line) = self.take_partial() {
+                    completed.push(self.store(line));
+                }
+            }
+        }
+
+        completed
+    }
+
+    /// Completes pending text that didn't receive a newline within the flush timeout, e.g. a
+    /// prompt printed by the firmware.
+    pub fn flush_stale(&mut self, now: u64) -> Option<ConsoleLine> {
+        if now.saturating_sub(self.last_output) < CONSOLE_FLUSH_TIMEOUT.as_millis() as u64 {
+            return None;
+        }
+        let line = self.take_partial()?;
+        Some(self.store(line))
+    }
+
+    fn take_partial(&mut self) -> Option<ConsoleLine> {
+        let (timestamp, output) = self.partial.take()?;
+        Some(ConsoleLine {
+            timestamp,
+            text: String::from_utf8_lossy(&output)
+                .trim_end_matches(['\n', '\r'])
+                .to_owned(),
+        })
+    }
+
+    fn store(&mut self, line: ConsoleLine) -> ConsoleLine {
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line.clone());
+        line
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &ConsoleLine> {
+        self.lines.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.partial = None;
+    }
+
+    /// Writes all completed lines prefixed with their UTC time of day to `path`.
+    pub fn export(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        for line in &self.lines {
+            writeln!(
+                file,
+                "[{}] {}",
+                format_time_of_day(line.timestamp),
+                line.text
+            )?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/// Current time in milliseconds since the unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+fn format_time_of_day(timestamp: u64) -> String {
+    let millis = timestamp % 1000;
+    let seconds = timestamp / 1000 % 86400;
+    format!(
+        "{:02}:{:02}:{:02}.{millis:03}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use similar_asserts::assert_eq;
+
+    use super::*;
+
+    fn texts(lines: &[ConsoleLine]) -> Vec<&str> {
+        lines.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn reassemble_lines_across_broadcasts() {
+        let mut console = DeviceConsole::default();
+
+        assert!(console.push(b"Hello ", 10).is_empty());
+        let lines = console.push(b"QMK!\r\nsecond\nthi", 20);
+        assert_eq!(texts(&lines), ["Hello QMK!", "second"]);
+        // Lines are stamped with the arrival of their first part
+        assert_eq!(lines[0].timestamp, 10);
+        assert_eq!(lines[1].timestamp, 20);
+
+        assert_eq!(texts(&console.push(b"rd\n", 30)), ["third"]);
+        assert_eq!(console.lines().count(), 3);
+    }
+
+    #[test]
+    fn decode_characters_split_across_broadcasts() {
+        let mut console = DeviceConsole::default();
+        let output = "Grüße\n".as_bytes();
+
+        // The two bytes of `ü` arrive in separate broadcasts
+        assert!(console.push(&output[..3], 10).is_empty());
+        assert_eq!(texts(&console.push(&output[3..], 20)), ["Grüße"]);
+    }
+
+    #[test]
+    fn flush_stale_partial_line() {
+        let mut console = DeviceConsole::default();
+        console.push(b"prompt> ", 1000);
+
+        assert_eq!(console.flush_stale(1100), None);
+        assert_eq!(
+            console.flush_stale(1500),
+            Some(ConsoleLine {
+                timestamp: 1000,
+                text: "prompt> ".to_owned()
+            })
+        );
+        assert_eq!(console.flush_stale(5000), None);
+    }
+
+    #[test]
+    fn drop_oldest_lines() {
+        let mut console = DeviceConsole::with_capacity(2);
+        console.push(b"one\ntwo\nthree\n", 0);
+
+        let lines: Vec<ConsoleLine> = console.lines().cloned().collect();
+        assert_eq!(texts(&lines), ["two", "three"]);
+    }
+
+    #[test]
+    fn export_with_time_of_day() {
+        let mut console = DeviceConsole::default();
+        // 2024-01-01 13:14:15.016 UTC
+        console.push(b"booted\n", 1_704_114_855_016);
+
+        let path = std::env::temp_dir().join(format!(
+            "qmk-xap-console-{}-{}.txt",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        console.export(&path).unwrap();
+        let exported = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(exported, "[13:14:15.016] booted\n");
+    }
+}
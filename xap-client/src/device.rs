@@ -0,0 +1,956 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    fmt::Debug,
+    io::{Cursor, Read},
+    sync::Arc,
+    time::{Duration, Instant},
+    vec,
+};
+
+use anyhow::{anyhow, Result};
+use binrw::{BinRead, BinWrite, BinWriterExt};
+use bitflags::Flags;
+use flate2::read::GzDecoder;
+use log::{error, info, trace};
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use xap_specs::{
+    broadcast::{BroadcastRaw, XapBroadcast},
+    constants::{keycode::KeyCode, XapConstants},
+    dynamic::{DynamicRoute, RouteTable},
+    error::{XapError, XapResult},
+    request::{DynamicRequest, RawRequest, XapRequest},
+    response::RawResponse,
+    token::{Token, TokenAllocator},
+    version::BcdVersion,
+    XapSecureStatus,
+};
+
+use crate::{
+    aggregation::{
+        config::Config, keymap::MappedKeymap, KeymapInfo, LightingCapabilities, LightingInfo,
+        Point2D, Point3D, QmkInfo, RemapInfo, XapDeviceInfo, XapInfo,
+    },
+    console::DeviceConsole,
+    spec::{
+        audio::AudioCapabilitiesRequest,
+        broadcast::SecureStatusBroadcast,
+        keymap::{
+            KeymapCapabilitiesFlags, KeymapCapabilitiesRequest, KeymapGetKeycodeRequest,
+            KeymapGetLayerCountRequest,
+        },
+        lighting::{
+            backlight::{
+                BacklightCapabilitiesFlags, BacklightCapabilitiesRequest,
+                BacklightGetEnabledEffectsRequest,
+            },
+            rgblight::{
+                RgblightCapabilitiesFlags, RgblightCapabilitiesRequest,
+                RgblightGetEnabledEffectsRequest,
+            },
+            rgbmatrix::{
+                RgbmatrixCapabilitiesFlags, RgbmatrixCapabilitiesRequest,
+                RgbmatrixGetEnabledEffectsRequest,
+            },
+            LightingCapabilitiesFlags, LightingCapabilitiesRequest,
+        },
+        qmk::{
+            QmkBoardIdentifiersRequest, QmkBoardManufacturerRequest, QmkCapabilitiesFlags,
+            QmkCapabilitiesRequest, QmkConfigBlobChunkRequest, QmkConfigBlobChunkResponse,
+            QmkConfigBlobLengthRequest, QmkHardwareIdentifierRequest, QmkProductNameRequest,
+            QmkVersionRequest,
+        },
+        remapping::{
+            RemappingCapabilitiesFlags, RemappingCapabilitiesRequest,
+            RemappingGetLayerCountRequest, RemappingSetKeycodeArg, RemappingSetKeycodeRequest,
+        },
+        xap::{
+            XapCapabilitiesRequest, XapEnabledSubsystemCapabilitiesFlags,
+            XapEnabledSubsystemCapabilitiesRequest, XapSecureStatusRequest, XapSecureUnlockRequest,
+            XapVersionRequest,
+        },
+    },
+    transport::{XapDeviceIdentity, XapTransport},
+};
+
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub struct Keymap {
+    keys: Vec<Vec<Vec<KeymapKey>>>,
+    dimensions: Point3D,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub struct KeymapKey {
+    pub code: KeyCode,
+    pub position: Point3D,
+}
+
+impl Keymap {
+    pub fn new(layers: u64, rows: u64, columns: u64) -> Self {
+        Self {
+            keys: vec![
+                vec![vec![KeymapKey::default(); columns as usize]; rows as usize];
+                layers as usize
+            ],
+            dimensions: Point3D {
+                z: layers,
+                y: rows,
+                x: columns,
+            },
+        }
+    }
+
+    pub fn remap_key(&mut self, key: &KeymapKey) -> Result<()> {
+        if key.position.z >= self.dimensions.z
+            || key.position.y >= self.dimensions.y
+            || key.position.x >= self.dimensions.x
+        {
+            anyhow::bail!(
+                "key position {:?} out of bounds for keymap with dimensions {:?}",
+                key.position,
+                self.dimensions
+            )
+        }
+
+        self.keys[key.position.z as usize][key.position.y as usize][key.position.x as usize] =
+            key.clone();
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub struct XapDeviceState {
+    pub id: Uuid,
+    pub info: Option<XapDeviceInfo>,
+    #[serde(skip)]
+    pub keymap: Keymap,
+    pub config: Config,
+    pub secure_status: XapSecureStatus,
+    /// Host keyboard layout the keycode labels are localized to, e.g. `german`
+    pub host_layout: Option<String>,
+}
+
+/// Number of requests that are sent to the device before waiting for the first response.
+const MAX_IN_FLIGHT_REQUESTS: usize = 16;
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Time the user has to complete the unlock sequence on the device.
+const UNLOCK_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub struct XapDevice {
+    id: Uuid,
+    transport: Box<dyn XapTransport>,
+    constants: Arc<XapConstants>,
+    state: XapDeviceState,
+    pub(crate) broadcast_queue: VecDeque<BroadcastRaw>,
+    responses: HashMap<Token, Option<XapResult<RawResponse>>>,
+    tokens: TokenAllocator,
+    auto_unlock: bool,
+    /// Set whenever the device reports the `Unlocking` status, so an unlock sequence that
+    /// already ended while its request was answered isn't missed.
+    unlocking_seen: bool,
+    /// Responses of all capabilities queries by route id.
+    capabilities: HashMap<&'static [u8], u32>,
+    console: DeviceConsole,
+}
+
+impl XapDevice {
+    pub fn new(constants: Arc<XapConstants>, transport: Box<dyn XapTransport>) -> Result<Self> {
+        Self::with_token_allocator(constants, transport, TokenAllocator::new())
+    }
+
+    /// Creates a device that draws its request tokens from `tokens`, a seeded allocator yields
+    /// reproducible traffic.
+    pub fn with_token_allocator(
+        constants: Arc<XapConstants>,
+        transport: Box<dyn XapTransport>,
+        tokens: TokenAllocator,
+    ) -> Result<Self> {
+        let id = Uuid::new_v4();
+        let state = XapDeviceState {
+            id,
+            info: None,
+            keymap: Keymap::new(0, 0, 0),
+            config: Config {
+                layouts: HashMap::new(),
+                matrix_size: Point2D { x: 0, y: 0 },
+                keycodes: Vec::new(),
+            },
+            secure_status: XapSecureStatus::Locked,
+            host_layout: None,
+        };
+
+        let mut device = Self {
+            id,
+            transport,
+            state,
+            constants,
+            responses: HashMap::new(),
+            broadcast_queue: VecDeque::new(),
+            tokens,
+            auto_unlock: false,
+            unlocking_seen: false,
+            capabilities: HashMap::new(),
+            console: DeviceConsole::default(),
+        };
+        device.query_device_info()?;
+        device.query_keymap()?;
+        device.query_secure_status()?;
+        Ok(device)
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn state(&self) -> &XapDeviceState {
+        &self.state
+    }
+
+    pub fn xap_info(&self) -> XapDeviceInfo {
+        self.state
+            .info
+            .clone()
+            .expect("XAP device wasn't properly initialized")
+    }
+
+    pub fn keymap(&self) -> &Keymap {
+        &self.state.keymap
+    }
+
+    pub fn keymap_with_layout(&self, layout: String) -> Result<MappedKeymap> {
+        let layout = self
+            .state
+            .config
+            .layouts
+            .get(&layout)
+            .ok_or_else(|| anyhow!("layout {layout} not found in device {}", self.id))?;
+
+        let mut keymap = MappedKeymap::new(
+            self.state.keymap.dimensions.z,
+            self.state.keymap.dimensions.y,
+            self.state.keymap.dimensions.x,
+        );
+
+        for (_layer, keys) in self.keymap().keys.iter().enumerate() {
+            for (row, keys) in keys.iter().enumerate() {
+                for (column, key) in keys.iter().enumerate() {
+                    if let Some(entry) = layout.find(Point2D {
+                        x: column as u64,
+                        y: row as u64,
+                    }) {
+                        keymap.insert(key.clone(), entry.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(keymap)
+    }
+
+    /// Constants of the device, they include the custom keycodes of the keyboard.
+    pub fn constants(&self) -> &Arc<XapConstants> {
+        &self.constants
+    }
+
+    pub fn console(&self) -> &DeviceConsole {
+        &self.console
+    }
+
+    pub fn console_mut(&mut self) -> &mut DeviceConsole {
+        &mut self.console
+    }
+
+    pub fn identity(&self) -> &XapDeviceIdentity {
+        self.transport.identity()
+    }
+
+    pub fn is_device(&self, candidate: &XapDeviceIdentity) -> bool {
+        self.transport.identity() == candidate
+    }
+
+    pub fn remap_key(&mut self, key: RemappingSetKeycodeArg) -> Result<()> {
+        self.query(RemappingSetKeycodeRequest(key.clone()))?;
+
+        let keycode = self.query_key(Point3D {
+            z: key.layer as u64,
+            y: key.row as u64,
+            x: key.column as u64,
+        })?;
+
+        self.state.keymap.remap_key(&keycode)?;
+
+        Ok(())
+    }
+
+    pub fn query_key(&mut self, position: Point3D) -> Result<KeymapKey> {
+        let code_raw = self.query(KeymapGetKeycodeRequest(position.into()))?;
+
+        let key = KeymapKey {
+            code: self.get_keycode(code_raw.0),
+            position,
+        };
+
+        self.state.keymap.remap_key(&key)?;
+
+        Ok(key)
+    }
+
+    pub fn query<T: XapRequest>(&mut self, request: T) -> XapResult<T::Response> {
+        self.query_pipelined([request])?
+            .pop()
+            .expect("one response for one request")
+    }
+
+    /// Sends all requests without waiting for the response of the previous one, keeping up to
+    /// `MAX_IN_FLIGHT_REQUESTS` requests outstanding. The responses are returned in the same
+    /// order as the requests, failures of individual requests don't abort the whole query.
+    pub fn query_pipelined<T: XapRequest>(
+        &mut self,
+        requests: impl IntoIterator<Item = T>,
+    ) -> XapResult<Vec<XapResult<T::Response>>> {
+        let mut requests = requests.into_iter().peekable();
+        if requests.peek().is_none() {
+            return Ok(Vec::new());
+        }
+
+        self.check_request::<T>()?;
+        let mut responses: Vec<Option<XapResult<T::Response>>> = Vec::new();
+        let mut in_flight: VecDeque<(usize, Token, Instant)> = VecDeque::new();
+
+        loop {
+            while in_flight.len() < MAX_IN_FLIGHT_REQUESTS {
+                let Some(request) = requests.next() else {
+                    break;
+                };
+                let token = self
+                    .tokens
+                    .allocate(|token| self.responses.contains_key(token));
+                in_flight.push_back((responses.len(), token.clone(), Instant::now()));
+                responses.push(None);
+                if let Err(err) = self.send(&token, &RawRequest::new(token.clone(), request)) {
+                    self.forget_in_flight(&in_flight);
+                    return Err(err);
+                }
+            }
+
+            if in_flight.is_empty() {
+                break;
+            }
+
+            let report = match self.poll() {
+                Ok(report) => report,
+                Err(err) => {
+                    self.forget_in_flight(&in_flight);
+                    return Err(err);
+                }
+            };
+
+            in_flight.retain(|(index, token, sent)| {
+                if let Entry::Occupied(response) = self.responses.entry(token.clone()) {
+                    if response.get().is_some() {
+                        let (_, response) = response.remove_entry();
+                        responses[*index] = Some(
+                            response
+                                .expect("response was just checked for None")
+                                .and_then(|response| response.into_xap_response::<T>()),
+                        );
+                        return false;
+                    }
+
+                    if sent.elapsed() > RESPONSE_TIMEOUT {
+                        response.remove_entry();
+                        responses[*index] = Some(Err(XapError::Timeout {
+                            route: T::id().to_vec(),
+                        }));
+                        return false;
+                    }
+                }
+                true
+            });
+
+            if report.is_none() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        Ok(responses
+            .into_iter()
+            .map(|response| response.expect("all requests were answered or timed out"))
+            .collect())
+    }
+
+    /// Drops the tokens of an aborted pipelined query, late responses to them are discarded.
+    fn forget_in_flight(&mut self, in_flight: &VecDeque<(usize, Token, Instant)>) {
+        for (_, token, _) in in_flight {
+            self.responses.remove(token);
+        }
+    }
+
+    /// Sends a request with the reserved "fire and forget" token, the device won't answer it.
+    pub fn send_without_response<T: XapRequest>(&mut self, request: T) -> XapResult<()> {
+        self.check_request::<T>()?;
+        self.send(
+            &Token::WithoutResponse,
+            &RawRequest::without_response(request),
+        )
+    }
+
+    /// Invokes a route of `routes` by its name, e.g. `lighting.rgbmatrix.get_config`, with the
+    /// request encoded from and the response decoded into JSON. This reaches routes of newer
+    /// specs, which the generated requests don't cover yet.
+    pub fn query_dynamic(
+        &mut self,
+        routes: &RouteTable,
+        path: &str,
+        args: &Value,
+    ) -> XapResult<Value> {
+        let route = routes.get(path)?;
+        let payload = routes.encode_request(route, args)?;
+        let response = self.query_raw(route, &payload)?;
+
+        routes.decode_response(route, &response)
+    }
+
+    /// Sends an already encoded request to `route` and returns the undecoded response payload.
+    pub fn query_raw(&mut self, route: &DynamicRoute, payload: &[u8]) -> XapResult<Vec<u8>> {
+        self.check_version(&route.id, route.xap_version)?;
+        self.check_capabilities(
+            &route.id,
+            route.capabilities.iter().map(|capability| {
+                (
+                    capability.route.as_slice(),
+                    capability.bit,
+                    capability.condition.as_str(),
+                )
+            }),
+        )?;
+        self.check_secure(&route.id, route.secure)?;
+
+        let token = self
+            .tokens
+            .allocate(|token| self.responses.contains_key(token));
+        self.send(
+            &token,
+            &DynamicRequest::new(token.clone(), &route.id, payload),
+        )?;
+        let response = self.wait_for_response(&token, &route.id)?;
+
+        Ok(response.payload().to_vec())
+    }
+
+    fn wait_for_response(&mut self, token: &Token, route: &[u8]) -> XapResult<RawResponse> {
+        let sent = Instant::now();
+
+        loop {
+            let report = self.poll()?;
+
+            if let Some(Some(_)) = self.responses.get(token) {
+                return self
+                    .responses
+                    .remove(token)
+                    .flatten()
+                    .expect("response was just checked for None");
+            }
+
+            if sent.elapsed() > RESPONSE_TIMEOUT {
+                self.responses.remove(token);
+                return Err(XapError::Timeout {
+                    route: route.to_vec(),
+                });
+            }
+
+            if report.is_none() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+
+    fn check_request<T: XapRequest>(&mut self) -> XapResult<()> {
+        self.check_version(T::id(), T::xap_version())?;
+        self.check_capabilities(
+            T::id(),
+            T::capabilities()
+                .iter()
+                .map(|capability| (capability.route, capability.bit, capability.condition)),
+        )?;
+        self.check_secure(T::id(), T::is_secure())
+    }
+
+    fn check_version(&self, route: &[u8], required: BcdVersion) -> XapResult<()> {
+        if let Some(xap_info) = &self.state.info {
+            if required > xap_info.xap.version {
+                return Err(XapError::UnsupportedByVersion {
+                    route: route.to_vec(),
+                    required,
+                    device: xap_info.xap.version,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// If enabled, secure requests to a locked device first run the unlock sequence instead of
+    /// failing right away.
+    pub fn set_auto_unlock(&mut self, enabled: bool) {
+        self.auto_unlock = enabled;
+    }
+
+    /// Localizes the labels of all keycodes to a host keyboard layout of the constants, e.g.
+    /// `german`. `None` restores the US ANSI labels of the specs.
+    pub fn set_host_layout(&mut self, layout: Option<String>) -> Result<()> {
+        if let Some(name) = &layout {
+            if self.constants.host_layout(name).is_none() {
+                anyhow::bail!("unknown host layout {name}");
+            }
+        }
+        self.state.host_layout = layout;
+
+        let layout = self.state.host_layout.as_deref();
+        for key in self.state.keymap.keys.iter_mut().flatten().flatten() {
+            key.code = self.constants.get_localized_keycode(key.code.code, layout);
+        }
+
+        Ok(())
+    }
+
+    fn get_keycode(&self, code: u16) -> KeyCode {
+        self.constants
+            .get_localized_keycode(code, self.state.host_layout.as_deref())
+    }
+
+    /// Fails if the device reported any capability the request depends on as missing. Requests
+    /// are let through as long as the capabilities are unknown, e.g. during initialization.
+    fn check_capabilities<'a>(
+        &self,
+        route: &[u8],
+        capabilities: impl IntoIterator<Item = (&'a [u8], u8, &'a str)>,
+    ) -> XapResult<()> {
+        for (capabilities_route, bit, condition) in capabilities {
+            if let Some(mask) = self.capabilities.get(capabilities_route) {
+                if mask & (1 << bit) == 0 {
+                    return Err(XapError::UnsupportedByCapability {
+                        route: route.to_vec(),
+                        condition: condition.to_owned(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_secure(&mut self, route: &[u8], secure: bool) -> XapResult<()> {
+        if !secure {
+            return Ok(());
+        }
+
+        // Process pending secure status broadcasts, our view of the status might be outdated
+        while self.poll()?.is_some() {}
+
+        match self.state.secure_status {
+            XapSecureStatus::Unlocked => Ok(()),
+            _ if self.auto_unlock => self.unlock(),
+            _ => Err(XapError::SecureRouteLocked {
+                route: route.to_vec(),
+            }),
+        }
+    }
+
+    /// Starts the unlock sequence and waits until the user completed it on the device.
+    pub fn unlock(&mut self) -> XapResult<()> {
+        self.unlocking_seen = false;
+        self.query(XapSecureUnlockRequest(()))?;
+
+        let started = Instant::now();
+
+        loop {
+            let report = self.poll()?;
+
+            match self.state.secure_status {
+                XapSecureStatus::Unlocked => return Ok(()),
+                XapSecureStatus::Locked if self.unlocking_seen => {
+                    return Err(XapError::UnlockFailed)
+                }
+                XapSecureStatus::Locked | XapSecureStatus::Unlocking => {}
+            }
+
+            if started.elapsed() > UNLOCK_TIMEOUT {
+                return Err(XapError::Timeout {
+                    route: XapSecureUnlockRequest::id().to_vec(),
+                });
+            }
+
+            if report.is_none() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+
+    fn send<R>(&mut self, token: &Token, request: &R) -> XapResult<()>
+    where
+        R: for<'a> BinWrite<Args<'a> = ()>,
+    {
+        let mut report = vec![0; self.transport.report_size()];
+
+        let mut writer = Cursor::new(&mut report[..]);
+        writer.write_le(request)?;
+
+        trace!("send XAP report with payload {:?}", &report);
+
+        if let Token::WithResponse(_) = token {
+            self.responses.insert(token.clone(), None);
+        }
+        self.transport
+            .write_report(&report)
+            .map_err(|err| XapError::Transport(err.to_string()))
+    }
+
+    pub fn query_secure_status(&mut self) -> Result<XapSecureStatus> {
+        let status = self.query(XapSecureStatusRequest(()))?.0.into();
+        self.state.secure_status = status;
+        Ok(status)
+    }
+
+    /// Queries a capabilities route and caches the response for gating the requests that depend
+    /// on it.
+    fn query_capabilities<T>(&mut self, request: T) -> XapResult<T::Response>
+    where
+        T: XapRequest,
+        T::Response: Flags<Bits = u32>,
+    {
+        let capabilities = self.query(request)?;
+        self.capabilities.insert(T::id(), capabilities.bits());
+        Ok(capabilities)
+    }
+
+    fn query_device_info(&mut self) -> Result<()> {
+        let subsystems = self.query_capabilities(XapEnabledSubsystemCapabilitiesRequest(()))?;
+        self.query_capabilities(XapCapabilitiesRequest(()))?;
+
+        let xap_info = XapInfo {
+            version: self.query(XapVersionRequest(()))?.0,
+        };
+
+        let qmk_caps = self.query_capabilities(QmkCapabilitiesRequest(()))?;
+        let board_ids = self.query(QmkBoardIdentifiersRequest(()))?;
+        // TODO: why do these strings have leading and trailing " characters -
+        // should be removed in QMK
+        let manufacturer = self
+            .query(QmkBoardManufacturerRequest(()))?
+            .0
+             .0
+            .trim_matches('"')
+            .to_owned();
+        let product_name = self
+            .query(QmkProductNameRequest(()))?
+            .0
+             .0
+            .trim_matches('"')
+            .to_owned();
+
+        self.query_config()?;
+
+        let hardware_id = self.query(QmkHardwareIdentifierRequest(()))?.0;
+
+        let qmk_info = QmkInfo {
+            version: self.query(QmkVersionRequest(()))?.0,
+            board_ids,
+            manufacturer,
+            product_name,
+            hardware_id: format!(
+                "{}{}{}{}",
+                hardware_id[0], hardware_id[1], hardware_id[2], hardware_id[3]
+            ),
+            jump_to_bootloader_enabled: qmk_caps.contains(QmkCapabilitiesFlags::JumpToBootloader),
+            eeprom_reset_enabled: qmk_caps.contains(QmkCapabilitiesFlags::ReinitializeEeprom),
+        };
+
+        let keymap_info = if subsystems.contains(XapEnabledSubsystemCapabilitiesFlags::Keymap) {
+            let keymap_caps = self.query_capabilities(KeymapCapabilitiesRequest(()))?;
+
+            let layer_count = if keymap_caps.contains(KeymapCapabilitiesFlags::GetLayerCount) {
+                Some(self.query(KeymapGetLayerCountRequest(()))?.0)
+            } else {
+                None
+            };
+
+            Some(KeymapInfo {
+                layer_count,
+                get_keycode_enabled: keymap_caps.contains(KeymapCapabilitiesFlags::GetKeycode),
+                get_encoder_keycode_enabled: keymap_caps
+                    .contains(KeymapCapabilitiesFlags::GetEncoderKeycode),
+            })
+        } else {
+            info!("keymap subsystem not active!");
+            None
+        };
+
+        let remap_info = if subsystems.contains(XapEnabledSubsystemCapabilitiesFlags::Remapping) {
+            let keymap_caps = self.query_capabilities(RemappingCapabilitiesRequest(()))?;
+
+            let layer_count = if keymap_caps.contains(RemappingCapabilitiesFlags::GetLayerCount) {
+                Some(self.query(RemappingGetLayerCountRequest(()))?.0)
+            } else {
+                None
+            };
+
+            Some(RemapInfo {
+                layer_count,
+                set_keycode_enabled: keymap_caps.contains(RemappingCapabilitiesFlags::SetKeycode),
+                set_encoder_keycode_enabled: keymap_caps
+                    .contains(RemappingCapabilitiesFlags::SetEncoderKeycode),
+            })
+        } else {
+            None
+        };
+
+        let lighting_info = if subsystems.contains(XapEnabledSubsystemCapabilitiesFlags::Lighting) {
+            let lighting_caps = self.query_capabilities(LightingCapabilitiesRequest(()))?;
+
+            let backlight_info = if lighting_caps.contains(LightingCapabilitiesFlags::Backlight) {
+                let backlight_caps = self.query_capabilities(BacklightCapabilitiesRequest(()))?;
+
+                let effects =
+                    if backlight_caps.contains(BacklightCapabilitiesFlags::GetEnabledEffects) {
+                        self.query(BacklightGetEnabledEffectsRequest(()))?.0
+                    } else {
+                        0
+                    };
+
+                Some(LightingCapabilities::new(
+                    // Todo: implement backlight effects
+                    self.constants
+                        .led_matrix_modes
+                        .get_effect_map(effects as u64),
+                    backlight_caps.contains(BacklightCapabilitiesFlags::GetConfig),
+                    backlight_caps.contains(BacklightCapabilitiesFlags::SetConfig),
+                    backlight_caps.contains(BacklightCapabilitiesFlags::SaveConfig),
+                ))
+            } else {
+                None
+            };
+
+            let rgblight_info = if lighting_caps.contains(LightingCapabilitiesFlags::Rgblight) {
+                let rgblight_caps = self.query_capabilities(RgblightCapabilitiesRequest(()))?;
+
+                let effects =
+                    if rgblight_caps.contains(RgblightCapabilitiesFlags::GetEnabledEffects) {
+                        self.query(RgblightGetEnabledEffectsRequest(()))?.0
+                    } else {
+                        0
+                    };
+
+                Some(LightingCapabilities::new(
+                    self.constants.rgblight_modes.get_effect_map(effects),
+                    rgblight_caps.contains(RgblightCapabilitiesFlags::GetConfig),
+                    rgblight_caps.contains(RgblightCapabilitiesFlags::SetConfig),
+                    rgblight_caps.contains(RgblightCapabilitiesFlags::SaveConfig),
+                ))
+            } else {
+                None
+            };
+
+            let rgbmatrix_info = if lighting_caps.contains(LightingCapabilitiesFlags::Rgbmatrix) {
+                let rgbmatrix_caps = self.query_capabilities(RgbmatrixCapabilitiesRequest(()))?;
+
+                let effects =
+                    if rgbmatrix_caps.contains(RgbmatrixCapabilitiesFlags::GetEnabledEffects) {
+                        self.query(RgbmatrixGetEnabledEffectsRequest(()))?.0
+                    } else {
+                        0
+                    };
+
+                Some(LightingCapabilities::new(
+                    self.constants.rgb_matrix_modes.get_effect_map(effects),
+                    rgbmatrix_caps.contains(RgbmatrixCapabilitiesFlags::GetConfig),
+                    rgbmatrix_caps.contains(RgbmatrixCapabilitiesFlags::SetConfig),
+                    rgbmatrix_caps.contains(RgbmatrixCapabilitiesFlags::SaveConfig),
+                ))
+            } else {
+                None
+            };
+
+            Some(LightingInfo {
+                backlight: backlight_info,
+                rgblight: rgblight_info,
+                rgbmatrix: rgbmatrix_info,
+            })
+        } else {
+            None
+        };
+
+        if subsystems.contains(XapEnabledSubsystemCapabilitiesFlags::Audio) {
+            self.query_capabilities(AudioCapabilitiesRequest(()))?;
+        }
+
+        self.state.info = Some(XapDeviceInfo {
+            xap: xap_info,
+            qmk: qmk_info,
+            keymap: keymap_info,
+            remap: remap_info,
+            lighting: lighting_info,
+        });
+
+        Ok(())
+    }
+
+    fn query_config(&mut self) -> Result<()> {
+        //  data size
+        let size = self.query(QmkConfigBlobLengthRequest(()))?.0;
+        let chunk_size = QmkConfigBlobChunkResponse::default().0.len() as u16;
+
+        //  all chunks and merge them in a Vec
+        let mut data: Vec<u8> = Vec::with_capacity(size as usize);
+        for chunk in self.query_pipelined(
+            (0..size)
+                .step_by(chunk_size as usize)
+                .map(QmkConfigBlobChunkRequest),
+        )? {
+            data.extend(chunk?.0);
+        }
+
+        // Trim trailing zeroes and convert Vec into array
+        let data = &data[..(size as usize)];
+
+        // Decompress data
+        let mut decoder = GzDecoder::new(data);
+        let mut decompressed = String::new();
+
+        decoder.read_to_string(&mut decompressed)?;
+
+        self.state.config = serde_json::from_str(&decompressed)?;
+
+        if !self.state.config.keycodes.is_empty() {
+            // Custom keycodes only add labels, the device stays usable with the keycodes of the
+            // specs if they can't be merged
+            match self
+                .constants
+                .with_custom_keycodes(&self.state.config.keycodes)
+            {
+                Ok(constants) => self.constants = Arc::new(constants),
+                Err(err) => error!("ignoring custom keycodes of device {}: {err}", self.id),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn query_keymap(&mut self) -> Result<()> {
+        let layers: u64 = if let Some(keymap) = &self.xap_info().keymap {
+            keymap.layer_count.unwrap_or_default() as u64
+        } else {
+            0
+        };
+
+        let Point2D {
+            x: columns,
+            y: rows,
+        } = self.state.config.matrix_size;
+
+        self.state.keymap = Keymap::new(layers, rows, columns);
+
+        let positions: Vec<Point3D> = (0..layers)
+            .flat_map(|layer| {
+                (0..rows).flat_map(move |row| {
+                    (0..columns).map(move |column| Point3D {
+                        z: layer,
+                        y: row,
+                        x: column,
+                    })
+                })
+            })
+            .collect();
+
+        let codes = self.query_pipelined(
+            positions
+                .iter()
+                .map(|position| KeymapGetKeycodeRequest((*position).into())),
+        )?;
+
+        for (position, code_raw) in positions.into_iter().zip(codes) {
+            let key = KeymapKey {
+                code: self.get_keycode(code_raw?.0),
+                position,
+            };
+            self.state.keymap.remap_key(&key)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a single report from the device. Returns `None` if no report was pending, reports
+    /// that are discarded, e.g. duplicate responses, are still returned with their length.
+    pub fn poll(&mut self) -> XapResult<Option<usize>> {
+        let mut report = vec![0_u8; self.transport.report_size()];
+
+        let length = self
+            .transport
+            .read_report(&mut report)
+            .map_err(|err| XapError::Transport(err.to_string()))?;
+
+        if length == 0 {
+            return Ok(None);
+        }
+
+        let mut reader = Cursor::new(&report);
+        let token = Token::read_le(&mut reader)?;
+
+        if let Token::Broadcast = token {
+            let broadcast = BroadcastRaw::from_raw_report(&report)?;
+            trace!("received XAP broadcast {:?}", broadcast);
+
+            // TODO nicer way to handle this without clone?
+            if broadcast.broadcast_type() == SecureStatusBroadcast::id() {
+                broadcast
+                    .clone()
+                    .into_xap_broadcast::<SecureStatusBroadcast>()
+                    .map(|broadcast| {
+                        self.state.secure_status = XapSecureStatus::from(broadcast.0);
+                        if let XapSecureStatus::Unlocking = self.state.secure_status {
+                            self.unlocking_seen = true;
+                        }
+                    })?;
+            }
+
+            self.broadcast_queue.push_back(broadcast);
+        } else {
+            let response = RawResponse::from_raw_report(&report);
+
+            if let Err(XapError::DeviceLocked) = response {
+                self.state.secure_status = XapSecureStatus::Locked;
+            }
+
+            match self.responses.entry(token) {
+                Entry::Occupied(mut request) => {
+                    if request.get().is_some() {
+                        trace!(
+                            "received duplicate response with token {:?}, discarding",
+                            request.key()
+                        );
+                        return Ok(Some(length));
+                    }
+                    request.insert(Some(response));
+                }
+                Entry::Vacant(request) => {
+                    trace!(
+                        "received unsolicited response with token {:?}, discarding",
+                        request.key()
+                    );
+                    return Ok(Some(length));
+                }
+            }
+        }
+
+        Ok(Some(length))
+    }
+
+    pub fn secure_status(&self) -> &XapSecureStatus {
+        &self.state.secure_status
+    }
+}
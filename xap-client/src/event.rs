@@ -0,0 +1,35 @@
+// Events the client reports while enumerating and polling devices
+
+use serde::Serialize;
+use uuid::Uuid;
+use xap_specs::XapSecureStatus;
+
+use crate::{broadcast::CustomBroadcast, console::ConsoleLine};
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[serde(tag = "kind", content = "data")]
+pub enum XapClientEvent {
+    LogReceived {
+        id: Uuid,
+        line: ConsoleLine,
+    },
+    SecureStatusChanged {
+        id: Uuid,
+        secure_status: XapSecureStatus,
+    },
+    KeyboardBroadcast {
+        id: Uuid,
+        broadcast: CustomBroadcast,
+    },
+    UserBroadcast {
+        id: Uuid,
+        broadcast: CustomBroadcast,
+    },
+    NewDevice {
+        id: Uuid,
+    },
+    RemovedDevice {
+        id: Uuid,
+    },
+}
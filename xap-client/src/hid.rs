@@ -0,0 +1,100 @@
+// hidapi backed XAP transport and device discovery
+
+use anyhow::{anyhow, Result};
+use hidapi::{DeviceInfo, HidApi, HidDevice};
+
+use crate::transport::{XapDeviceIdentity, XapDiscovery, XapTransport};
+
+const XAP_USAGE_PAGE: u16 = 0xFF51;
+const XAP_USAGE: u16 = 0x0058;
+const XAP_REPORT_SIZE: usize = 64;
+
+impl From<&DeviceInfo> for XapDeviceIdentity {
+    fn from(info: &DeviceInfo) -> Self {
+        Self {
+            path: info.path().to_string_lossy().into_owned(),
+            vendor_id: info.vendor_id(),
+            product_id: info.product_id(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct HidTransport {
+    device: HidDevice,
+    identity: XapDeviceIdentity,
+}
+
+impl HidTransport {
+    pub fn new(device: HidDevice, identity: XapDeviceIdentity) -> Result<Self> {
+        // We are polling for reports, so we need to set the device to non-blocking mode otherwise
+        // we will block forever in case that there is no report to read
+        device.set_blocking_mode(false)?;
+
+        Ok(Self { device, identity })
+    }
+}
+
+impl XapTransport for HidTransport {
+    fn write_report(&mut self, report: &[u8]) -> Result<()> {
+        let mut hid_report = [0_u8; XAP_REPORT_SIZE + 1];
+        // Leave the first byte as zero, it is the HID report id
+        hid_report[1..=report.len()].copy_from_slice(report);
+        self.device.write(&hid_report)?;
+        Ok(())
+    }
+
+    fn read_report(&mut self, report: &mut [u8]) -> Result<usize> {
+        Ok(self.device.read(report)?)
+    }
+
+    fn report_size(&self) -> usize {
+        XAP_REPORT_SIZE
+    }
+
+    fn identity(&self) -> &XapDeviceIdentity {
+        &self.identity
+    }
+}
+
+pub struct HidDiscovery {
+    hid: HidApi,
+    devices: Vec<DeviceInfo>,
+}
+
+impl HidDiscovery {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            hid: HidApi::new_without_enumerate()?,
+            devices: Vec::new(),
+        })
+    }
+}
+
+impl XapDiscovery for HidDiscovery {
+    fn enumerate(&mut self) -> Result<Vec<XapDeviceIdentity>> {
+        self.hid.refresh_devices()?;
+
+        self.devices = self
+            .hid
+            .device_list()
+            .filter(|info| info.usage_page() == XAP_USAGE_PAGE && info.usage() == XAP_USAGE)
+            .cloned()
+            .collect();
+
+        Ok(self.devices.iter().map(Into::into).collect())
+    }
+
+    fn open(&mut self, identity: &XapDeviceIdentity) -> Result<Box<dyn XapTransport>> {
+        let info = self
+            .devices
+            .iter()
+            .find(|info| XapDeviceIdentity::from(*info) == *identity)
+            .ok_or_else(|| anyhow!("HID device {identity} is not enumerated"))?;
+
+        Ok(Box::new(HidTransport::new(
+            info.open_device(&self.hid)?,
+            identity.clone(),
+        )?))
+    }
+}
@@ -0,0 +1,20 @@
+// Client library to communicate with XAP-enabled devices, independent of any GUI
+
+pub mod aggregation;
+pub mod broadcast;
+pub mod client;
+pub mod console;
+pub mod device;
+pub mod event;
+pub mod hid;
+pub mod recording;
+#[cfg(test)]
+mod simulator;
+pub mod spec;
+pub mod transport;
+
+pub use client::XapClient;
+pub use device::{XapDevice, XapDeviceState};
+pub use event::XapClientEvent;
+pub use transport::{XapDeviceIdentity, XapDiscovery, XapTransport};
+pub use xap_specs::error::{XapError, XapResult};
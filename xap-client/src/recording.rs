@@ -0,0 +1,434 @@
+// Records the XAP traffic of a device to a file and replays it without the hardware
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail, Result};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::transport::{XapDeviceIdentity, XapDiscovery, XapTransport};
+
+/// Version of the recording file format, bumped on incompatible changes.
+pub const RECORDING_VERSION: u32 = 1;
+const RECORDING_FORMAT: &str = "qmk-xap-recording";
+const BROADCAST_TOKEN: [u8; 2] = [0xFF, 0xFF];
+const WITHOUT_RESPONSE_TOKEN: [u8; 2] = [0xFE, 0xFF];
+
+/// First line of a recording file, every following line holds one `RecordedReport`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub format: String,
+    pub version: u32,
+    pub device: XapDeviceIdentity,
+    pub report_size: usize,
+    /// Start of the recording in seconds since the unix epoch.
+    pub started: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "direction", rename_all = "lowercase")]
+pub enum RecordedReport {
+    /// Request written to the device.
+    Sent { timestamp_us: u64, data: Vec<u8> },
+    /// Response or broadcast read from the device.
+    Received { timestamp_us: u64, data: Vec<u8> },
+}
+
+/// A complete recording of a XAP session with a single device.
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub header: RecordingHeader,
+    pub reports: Vec<RecordedReport>,
+}
+
+impl Recording {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut lines = BufReader::new(File::open(path)?).lines();
+
+        let header: RecordingHeader = serde_json::from_str(
+            &lines
+                .next()
+                .ok_or_else(|| anyhow!("recording {path:?} is empty"))??,
+        )?;
+
+        if header.format != RECORDING_FORMAT {
+            bail!("{path:?} is not a XAP recording");
+        }
+        if header.version != RECORDING_VERSION {
+            bail!(
+                "recording {path:?} has version {}, only version {RECORDING_VERSION} is supported",
+                header.version
+            );
+        }
+
+        let reports = lines
+            .filter(|line| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+            .map(|line| Ok(serde_json::from_str(&line?)?))
+            .collect::<Result<Vec<RecordedReport>>>()?;
+
+        Ok(Self { header, reports })
+    }
+}
+
+/// Wraps a transport and appends all reports passing through it to a recording file.
+#[derive(Debug)]
+pub struct RecordingTransport {
+    inner: Box<dyn XapTransport>,
+    file: File,
+    started: Instant,
+}
+
+impl RecordingTransport {
+    /// Starts a new recording in `directory`, the file is named after the device and the start
+    /// time of the recording in milliseconds. Recordings started within the same millisecond get
+    /// a counter appended, existing files are never overwritten.
+    pub fn create(inner: Box<dyn XapTransport>, directory: impl AsRef<Path>) -> Result<Self> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        let started = now.as_secs();
+        let identity = inner.identity();
+        let name = format!(
+            "{:04x}_{:04x}_{}",
+            identity.vendor_id,
+            identity.product_id,
+            now.as_millis()
+        );
+
+        let mut attempt = 0;
+        let (path, mut file) = loop {
+            let path: PathBuf = directory.as_ref().join(match attempt {
+                0 => format!("{name}.jsonl"),
+                attempt => format!("{name}_{attempt}.jsonl"),
+            });
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => break (path, file),
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => attempt += 1,
+                Err(err) => return Err(err.into()),
+            }
+        };
+
+        info!("recording XAP traffic of {identity} to {path:?}");
+
+        let header = RecordingHeader {
+            format: RECORDING_FORMAT.to_owned(),
+            version: RECORDING_VERSION,
+            device: identity.clone(),
+            report_size: inner.report_size(),
+            started,
+        };
+
+        writeln!(file, "{}", serde_json::to_string(&header)?)?;
+
+        Ok(Self {
+            inner,
+            file,
+            started: Instant::now(),
+        })
+    }
+
+    fn record(&mut self, report: impl FnOnce(u64) -> RecordedReport) -> Result<()> {
+        let report = report(self.started.elapsed().as_micros() as u64);
+        // Every report is written out right away so a crash doesn't lose the interesting part
+        writeln!(self.file, "{}", serde_json::to_string(&report)?)?;
+        Ok(())
+    }
+}
+
+impl XapTransport for RecordingTransport {
+    fn write_report(&mut self, report: &[u8]) -> Result<()> {
+        self.record(|timestamp_us| RecordedReport::Sent {
+            timestamp_us,
+            data: report.to_vec(),
+        })?;
+        self.inner.write_report(report)
+    }
+
+    fn read_report(&mut self, report: &mut [u8]) -> Result<usize> {
+        let length = self.inner.read_report(report)?;
+        if length > 0 {
+            self.record(|timestamp_us| RecordedReport::Received {
+                timestamp_us,
+                data: report[..length].to_vec(),
+            })?;
+        }
+        Ok(length)
+    }
+
+    fn report_size(&self) -> usize {
+        self.inner.report_size()
+    }
+
+    fn identity(&self) -> &XapDeviceIdentity {
+        self.inner.identity()
+    }
+}
+
+/// Plays the device side of a recording. Requests have to arrive in the recorded order, as the
+/// client draws random tokens the recorded tokens are translated to the ones of the replay.
+#[derive(Debug)]
+pub struct ReplayTransport {
+    identity: XapDeviceIdentity,
+    report_size: usize,
+    reports: VecDeque<RecordedReport>,
+    pending: VecDeque<Vec<u8>>,
+    tokens: HashMap<[u8; 2], [u8; 2]>,
+}
+
+impl ReplayTransport {
+    pub fn new(recording: Recording) -> Self {
+        let mut transport = Self {
+            identity: recording.header.device,
+            report_size: recording.header.report_size,
+            reports: recording.reports.into(),
+            pending: VecDeque::new(),
+            tokens: HashMap::new(),
+        };
+        transport.queue_received();
+        transport
+    }
+
+    /// Queues all reports the device sent until the next recorded request.
+    fn queue_received(&mut self) {
+        while let Some(RecordedReport::Received { .. }) = self.reports.front() {
+            let Some(RecordedReport::Received { mut data, .. }) = self.reports.pop_front() else {
+                unreachable!("front was checked to be a received report");
+            };
+            if let Some(token) = data.get(..2).and_then(|token| self.tokens.get(token)) {
+                data[..2].copy_from_slice(token);
+            }
+            self.pending.push_back(data);
+        }
+    }
+}
+
+impl XapTransport for ReplayTransport {
+    fn write_report(&mut self, report: &[u8]) -> Result<()> {
+        let Some(RecordedReport::Sent { data, .. }) = self.reports.pop_front() else {
+            bail!(
+                "replay of {} diverged: no further request recorded, got {report:02X?}",
+                self.identity
+            );
+        };
+
+        if report.len() < 2 || data.len() < 2 || report[2..] != data[2..] {
+            bail!(
+                "replay of {} diverged: expected request {data:02X?} but got {report:02X?}",
+                self.identity
+            );
+        }
+
+        let recorded = [data[0], data[1]];
+        if recorded != BROADCAST_TOKEN && recorded != WITHOUT_RESPONSE_TOKEN {
+            self.tokens.insert(recorded, [report[0], report[1]]);
+        }
+
+        self.queue_received();
+        Ok(())
+    }
+
+    fn read_report(&mut self, report: &mut [u8]) -> Result<usize> {
+        let Some(pending) = self.pending.pop_front() else {
+            return Ok(0);
+        };
+        let length = pending.len().min(report.len());
+        report[..length].copy_from_slice(&pending[..length]);
+        Ok(length)
+    }
+
+    fn report_size(&self) -> usize {
+        self.report_size
+    }
+
+    fn identity(&self) -> &XapDeviceIdentity {
+        &self.identity
+    }
+}
+
+/// Presents recorded sessions as attached devices, each recording can be opened once.
+pub struct ReplayDiscovery {
+    devices: Vec<XapDeviceIdentity>,
+    recordings: Vec<Recording>,
+}
+
+impl ReplayDiscovery {
+    pub fn new(recordings: Vec<Recording>) -> Self {
+        Self {
+            devices: recordings
+                .iter()
+                .map(|recording| recording.header.device.clone())
+                .collect(),
+            recordings,
+        }
+    }
+
+    pub fn from_files(paths: impl IntoIterator<Item = impl AsRef<Path>>) -> Result<Self> {
+        Ok(Self::new(
+            paths
+                .into_iter()
+                .map(Recording::from_file)
+                .collect::<Result<Vec<Recording>>>()?,
+        ))
+    }
+}
+
+impl XapDiscovery for ReplayDiscovery {
+    fn enumerate(&mut self) -> Result<Vec<XapDeviceIdentity>> {
+        // Replayed devices stay attached after they were opened
+        Ok(self.devices.clone())
+    }
+
+    fn open(&mut self, identity: &XapDeviceIdentity) -> Result<Box<dyn XapTransport>> {
+        let index = self
+            .recordings
+            .iter()
+            .position(|recording| recording.header.device == *identity)
+            .ok_or_else(|| anyhow!("no unplayed recording for device {identity}"))?;
+
+        Ok(Box::new(ReplayTransport::new(
+            self.recordings.remove(index),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, sync::Arc};
+
+    use xap_specs::constants::XapConstants;
+
+    use super::*;
+    use crate::{
+        device::XapDevice,
+        simulator::{SimulatedKeyboard, SimulatorConfig},
+        spec::{lighting::rgblight::RgblightGetConfigRequest, qmk::QmkJumpToBootloaderRequest},
+    };
+
+    fn constants() -> Arc<XapConstants> {
+        Arc::new(
+            XapConstants::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../xap-specs/assets").into())
+                .expect("failed to load XAP constants"),
+        )
+    }
+
+    fn record_session(name: &str) -> (SimulatedKeyboard, XapDevice, Recording) {
+        let directory = std::env::temp_dir().join(format!(
+            "qmk-xap-{name}-{}-{}",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        fs::create_dir_all(&directory).unwrap();
+
+        let keyboard = SimulatedKeyboard::new(SimulatorConfig::default()).unwrap();
+        let mut device = XapDevice::new(
+            constants(),
+            Box::new(
+                RecordingTransport::create(Box::new(keyboard.transport()), &directory).unwrap(),
+            ),
+        )
+        .unwrap();
+        device.query(RgblightGetConfigRequest(())).unwrap();
+
+        let file = fs::read_dir(&directory)
+            .unwrap()
+            .next()
+            .expect("no recording was written")
+            .unwrap()
+            .path();
+        let recording = Recording::from_file(&file).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        (keyboard, device, recording)
+    }
+
+    #[test]
+    fn recordings_are_not_overwritten() {
+        let directory = std::env::temp_dir().join(format!(
+            "qmk-xap-overwrite-{}-{}",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        fs::create_dir_all(&directory).unwrap();
+
+        let keyboard = SimulatedKeyboard::new(SimulatorConfig::default()).unwrap();
+        for _ in 0..3 {
+            RecordingTransport::create(Box::new(keyboard.transport()), &directory).unwrap();
+        }
+
+        let recordings = fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| Recording::from_file(entry.unwrap().path()))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(recordings.len(), 3);
+    }
+
+    #[test]
+    fn replay_recorded_session() {
+        let (keyboard, recorded, recording) = record_session("replay");
+        assert_eq!(recording.header.device, *keyboard.transport().identity());
+
+        let mut replayed =
+            XapDevice::new(constants(), Box::new(ReplayTransport::new(recording))).unwrap();
+        let config = replayed.query(RgblightGetConfigRequest(())).unwrap();
+        assert_eq!(
+            [
+                config.enable,
+                config.mode,
+                config.hue,
+                config.sat,
+                config.val,
+                config.speed
+            ],
+            keyboard.rgblight_config()
+        );
+
+        assert_eq!(
+            serde_json::to_value(replayed.xap_info()).unwrap(),
+            serde_json::to_value(recorded.xap_info()).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(replayed.keymap_with_layout("LAYOUT".to_owned()).unwrap())
+                .unwrap(),
+            serde_json::to_value(recorded.keymap_with_layout("LAYOUT".to_owned()).unwrap())
+                .unwrap()
+        );
+
+        // The recording ends here, any further request diverges from it
+        assert!(replayed.query(RgblightGetConfigRequest(())).is_err());
+    }
+
+    #[test]
+    fn replay_rejects_diverging_requests() {
+        let (_, _, recording) = record_session("diverge");
+
+        let mut replayed =
+            XapDevice::new(constants(), Box::new(ReplayTransport::new(recording))).unwrap();
+        assert!(replayed.query(QmkJumpToBootloaderRequest(())).is_err());
+    }
+
+    #[test]
+    fn reject_unknown_recording_version() {
+        let file = std::env::temp_dir().join(format!(
+            "qmk-xap-version-{}-{}.jsonl",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        fs::write(
+            &file,
+            r#"{"format":"qmk-xap-recording","version":99,"device":{"vendor_id":1,"product_id":2,"path":"sim"},"report_size":64,"started":0}"#,
+        )
+        .unwrap();
+
+        let err = Recording::from_file(&file).unwrap_err();
+        fs::remove_file(&file).unwrap();
+        assert!(err.to_string().contains("version 99"));
+    }
+}
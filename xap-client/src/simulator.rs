@@ -0,0 +1,1263 @@
+// In-process simulation of the firmware side of the XAP protocol, used to exercise the client
+// without a physical keyboard
+
+use std::{
+    collections::VecDeque,
+    io::Write,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use anyhow::{bail, Result};
+use flate2::{write::GzEncoder, Compression};
+use serde_json::json;
+use xap_specs::{response::ResponseFlags, version::BcdVersion, XapSecureStatus};
+
+use crate::{
+    spec::broadcast::BroadcastType,
+    transport::{XapDeviceIdentity, XapDiscovery, XapTransport},
+};
+
+const SIMULATOR_REPORT_SIZE: usize = 64;
+const CONFIG_BLOB_CHUNK_SIZE: usize = 32;
+
+const BROADCAST_TOKEN: u16 = 0xFFFF;
+const WITHOUT_RESPONSE_TOKEN: u16 = 0xFFFE;
+
+pub const XAP_VERSION_0_0_1: BcdVersion = BcdVersion::new(0, 0, 1);
+pub const XAP_VERSION_0_1_0: BcdVersion = BcdVersion::new(0, 1, 0);
+pub const XAP_VERSION_0_2_0: BcdVersion = BcdVersion::new(0, 2, 0);
+pub const XAP_VERSION_0_3_0: BcdVersion = BcdVersion::new(0, 3, 0);
+
+#[derive(Debug, Clone)]
+pub struct SimulatorConfig {
+    /// XAP version of the firmware, routes introduced in later versions are rejected.
+    pub xap_version: BcdVersion,
+    pub qmk_version: BcdVersion,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub product_version: u16,
+    pub qmk_identifier: u32,
+    pub hardware_id: [u32; 4],
+    pub manufacturer: String,
+    pub product_name: String,
+    pub layers: u8,
+    pub rows: u8,
+    pub columns: u8,
+    pub encoders: u8,
+    pub keymap_enabled: bool,
+    pub remapping_enabled: bool,
+    pub backlight_enabled: bool,
+    pub rgblight_enabled: bool,
+    pub rgbmatrix_enabled: bool,
+    pub audio_enabled: bool,
+    pub bootloader_jump_enabled: bool,
+    pub eeprom_reset_enabled: bool,
+    /// Complete the unlock sequence right away instead of waiting for `complete_unlock`.
+    pub auto_unlock: bool,
+    /// Abort the unlock sequence right away, as if the user pressed a wrong key. Has no effect
+    /// together with `auto_unlock`.
+    pub abort_unlock: bool,
+    /// JSON config blob served through the config blob routes, a single layout matching the
+    /// matrix is generated if this is `None`.
+    pub config: Option<String>,
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        Self {
+            xap_version: XAP_VERSION_0_3_0,
+            qmk_version: BcdVersion::new(0, 24, 8),
+            vendor_id: 0xFEED,
+            product_id: 0x0001,
+            product_version: 0x0001,
+            qmk_identifier: 0xDEADBEEF,
+            hardware_id: [0x11111111, 0x22222222, 0x33333333, 0x44444444],
+            manufacturer: "QMK".to_owned(),
+            product_name: "XAP Simulator".to_owned(),
+            layers: 4,
+            rows: 4,
+            columns: 12,
+            encoders: 0,
+            keymap_enabled: true,
+            remapping_enabled: true,
+            backlight_enabled: false,
+            rgblight_enabled: true,
+            rgbmatrix_enabled: true,
+            audio_enabled: false,
+            bootloader_jump_enabled: true,
+            eeprom_reset_enabled: true,
+            auto_unlock: true,
+            abort_unlock: false,
+            config: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SimulatorState {
+    config: SimulatorConfig,
+    config_blob: Vec<u8>,
+    connected: bool,
+    secure_status: XapSecureStatus,
+    keymap: Vec<u16>,
+    encoder_keymap: Vec<u16>,
+    backlight: [u8; 3],
+    rgblight: [u8; 6],
+    rgbmatrix: [u8; 7],
+    audio: [u8; 2],
+    saved_configs: usize,
+    bootloader_jumps: usize,
+    eeprom_resets: usize,
+    reports: VecDeque<Vec<u8>>,
+}
+
+/// Handle to a simulated XAP keyboard, clones share the same firmware state.
+#[derive(Debug, Clone)]
+pub struct SimulatedKeyboard {
+    state: Arc<Mutex<SimulatorState>>,
+    identity: XapDeviceIdentity,
+}
+
+impl SimulatedKeyboard {
+    pub fn new(config: SimulatorConfig) -> Result<Self> {
+        let config_json = match &config.config {
+            Some(config) => config.clone(),
+            None => default_config_json(&config),
+        };
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(config_json.as_bytes())?;
+        let config_blob = encoder.finish()?;
+
+        let layer_size = config.rows as usize * config.columns as usize;
+        let mut keymap = vec![0x0001; config.layers as usize * layer_size];
+        // Fill the base layer with KC_A..KC_Z repeating, all other layers are KC_TRANSPARENT
+        for (i, keycode) in keymap.iter_mut().take(layer_size).enumerate() {
+            *keycode = 0x0004 + (i % 26) as u16;
+        }
+
+        let identity = XapDeviceIdentity {
+            path: format!("simulator://{}", uuid::Uuid::new_v4()),
+            vendor_id: config.vendor_id,
+            product_id: config.product_id,
+        };
+
+        Ok(Self {
+            state: Arc::new(Mutex::new(SimulatorState {
+                config_blob,
+                connected: true,
+                secure_status: XapSecureStatus::Locked,
+                encoder_keymap: vec![0x0000; config.layers as usize * config.encoders as usize * 2],
+                keymap,
+                backlight: [1, 0, 128],
+                rgblight: [1, 1, 0, 255, 128, 64],
+                rgbmatrix: [1, 1, 0, 255, 128, 64, 0xFF],
+                audio: [1, 0],
+                saved_configs: 0,
+                bootloader_jumps: 0,
+                eeprom_resets: 0,
+                reports: VecDeque::new(),
+                config,
+            })),
+            identity,
+        })
+    }
+
+    pub fn transport(&self) -> SimulatedTransport {
+        SimulatedTransport {
+            keyboard: self.clone(),
+        }
+    }
+
+    /// Simulates plugging or unplugging the keyboard, only connected keyboards are enumerated.
+    pub fn set_connected(&self, connected: bool) {
+        self.lock().connected = connected;
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.lock().connected
+    }
+
+    pub fn keycode(&self, layer: u8, row: u8, column: u8) -> Option<u16> {
+        let state = self.lock();
+        state
+            .keymap_index(layer, row, column)
+            .map(|index| state.keymap[index])
+    }
+
+    pub fn secure_status(&self) -> XapSecureStatus {
+        self.lock().secure_status
+    }
+
+    /// Finishes a pending unlock sequence, as if the user pressed the unlock keys.
+    pub fn complete_unlock(&self) {
+        let mut state = self.lock();
+        if matches!(state.secure_status, XapSecureStatus::Unlocking) {
+            state.set_secure_status(XapSecureStatus::Unlocked);
+        }
+    }
+
+    pub fn rgblight_config(&self) -> [u8; 6] {
+        self.lock().rgblight
+    }
+
+    pub fn saved_configs(&self) -> usize {
+        self.lock().saved_configs
+    }
+
+    pub fn bootloader_jumps(&self) -> usize {
+        self.lock().bootloader_jumps
+    }
+
+    pub fn eeprom_resets(&self) -> usize {
+        self.lock().eeprom_resets
+    }
+
+    pub fn emit_secure_status(&self, status: XapSecureStatus) {
+        self.lock().set_secure_status(status);
+    }
+
+    pub fn emit_broadcast(&self, broadcast_type: BroadcastType, payload: &[u8]) {
+        self.lock().push_broadcast(broadcast_type, payload);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SimulatorState> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl SimulatorState {
+    fn keymap_index(&self, layer: u8, row: u8, column: u8) -> Option<usize> {
+        let config = &self.config;
+        if layer >= config.layers || row >= config.rows || column >= config.columns {
+            return None;
+        }
+        Some(
+            (layer as usize * config.rows as usize + row as usize) * config.columns as usize
+                + column as usize,
+        )
+    }
+
+    fn encoder_index(&self, layer: u8, encoder: u8, clockwise: u8) -> Option<usize> {
+        let config = &self.config;
+        if layer >= config.layers || encoder >= config.encoders || clockwise > 1 {
+            return None;
+        }
+        Some(
+            (layer as usize * config.encoders as usize + encoder as usize) * 2 + clockwise as usize,
+        )
+    }
+
+    fn set_secure_status(&mut self, status: XapSecureStatus) {
+        self.secure_status = status;
+        self.push_broadcast(BroadcastType::SecureStatus, &[secure_status_byte(status)]);
+    }
+
+    fn push_broadcast(&mut self, broadcast_type: BroadcastType, payload: &[u8]) {
+        let broadcast_type = broadcast_type as u8;
+        // Long payloads are split over multiple reports, just like the firmware does
+        for chunk in payload.chunks(SIMULATOR_REPORT_SIZE - 4) {
+            let mut report = Vec::with_capacity(SIMULATOR_REPORT_SIZE);
+            report.extend_from_slice(&BROADCAST_TOKEN.to_le_bytes());
+            report.push(broadcast_type);
+            report.push(chunk.len() as u8);
+            report.extend_from_slice(chunk);
+            self.reports.push_back(report);
+        }
+    }
+
+    fn push_response(&mut self, token: u16, flags: ResponseFlags, payload: &[u8]) {
+        if token == WITHOUT_RESPONSE_TOKEN {
+            return;
+        }
+        let mut report = Vec::with_capacity(SIMULATOR_REPORT_SIZE);
+        report.extend_from_slice(&token.to_le_bytes());
+        report.push(flags.bits());
+        report.push(payload.len() as u8);
+        report.extend_from_slice(payload);
+        self.reports.push_back(report);
+    }
+
+    fn handle_request(&mut self, report: &[u8]) -> Result<()> {
+        let [token_lo, token_hi, length, ..] = *report else {
+            bail!("XAP request is too short: {report:?}");
+        };
+        let token = u16::from_le_bytes([token_lo, token_hi]);
+        let Some(request) = report.get(3..3 + length as usize) else {
+            bail!("XAP request length {length} exceeds report");
+        };
+
+        if self.route_version(request) > self.config.xap_version {
+            self.push_response(token, ResponseFlags::empty(), &[]);
+            return Ok(());
+        }
+
+        if is_secure_route(request) && !matches!(self.secure_status, XapSecureStatus::Unlocked) {
+            self.push_response(token, ResponseFlags::SECURE_FAILURE, &[]);
+            return Ok(());
+        }
+
+        match self.execute(request) {
+            Some(payload) => self.push_response(token, ResponseFlags::SUCCESS, &payload),
+            None => self.push_response(token, ResponseFlags::empty(), &[]),
+        }
+
+        Ok(())
+    }
+
+    /// XAP version that introduced the route addressed by the request.
+    fn route_version(&self, request: &[u8]) -> BcdVersion {
+        match request {
+            [0x00, 0x00, ..] => XAP_VERSION_0_0_1,
+            [0x00, ..] | [0x01, ..] | [0x02, ..] | [0x03, ..] => XAP_VERSION_0_1_0,
+            [0x04, ..] | [0x05, ..] | [0x06, 0x01, ..] => XAP_VERSION_0_2_0,
+            _ => XAP_VERSION_0_3_0,
+        }
+    }
+
+    /// Bitmask of the subsystems that are compiled into the simulated firmware.
+    fn subsystems(&self) -> u32 {
+        let config = &self.config;
+        let mut subsystems = 0b1111;
+        if config.keymap_enabled {
+            subsystems |= 1 << 0x04;
+        }
+        if config.remapping_enabled {
+            subsystems |= 1 << 0x05;
+        }
+        if self.lighting_capabilities() != 1 << 0x01 {
+            subsystems |= 1 << 0x06;
+        }
+        if config.audio_enabled {
+            subsystems |= 1 << 0x07;
+        }
+        // Subsystems of newer XAP versions are unknown to the firmware
+        subsystems
+            & (0..8)
+                .filter(|subsystem| self.route_version(&[*subsystem, 0x01]) <= config.xap_version)
+                .fold(0, |mask, subsystem| mask | 1 << subsystem)
+    }
+
+    fn lighting_capabilities(&self) -> u32 {
+        let config = &self.config;
+        let mut capabilities = 1 << 0x01;
+        if config.backlight_enabled {
+            capabilities |= 1 << 0x02;
+        }
+        if config.rgblight_enabled {
+            capabilities |= 1 << 0x03;
+        }
+        if config.rgbmatrix_enabled {
+            capabilities |= 1 << 0x04;
+        }
+        capabilities
+    }
+
+    fn is_enabled(&self, subsystem: u8) -> bool {
+        self.subsystems() & (1 << subsystem) != 0
+    }
+
+    fn execute(&mut self, request: &[u8]) -> Option<Vec<u8>> {
+        let (subsystem, route) = (*request.first()?, &request[1..]);
+
+        if !self.is_enabled(subsystem) {
+            return None;
+        }
+
+        match subsystem {
+            0x00 => self.execute_xap(route),
+            0x01 => self.execute_qmk(route),
+            0x04 => self.execute_keymap(route),
+            0x05 => self.execute_remapping(route),
+            0x06 => self.execute_lighting(route),
+            0x07 => self.execute_audio(route),
+            _ => None,
+        }
+    }
+
+    fn execute_xap(&mut self, route: &[u8]) -> Option<Vec<u8>> {
+        match route {
+            [0x00] => Some(self.config.xap_version.bcd().to_le_bytes().to_vec()),
+            [0x01] => Some(0b11_1111_u32.to_le_bytes().to_vec()),
+            [0x02] => Some(self.subsystems().to_le_bytes().to_vec()),
+            [0x03] => Some(vec![secure_status_byte(self.secure_status)]),
+            [0x04] => {
+                if !matches!(self.secure_status, XapSecureStatus::Unlocked) {
+                    self.set_secure_status(XapSecureStatus::Unlocking);
+                    if self.config.auto_unlock {
+                        self.set_secure_status(XapSecureStatus::Unlocked);
+                    } else if self.config.abort_unlock {
+                        self.set_secure_status(XapSecureStatus::Locked);
+                    }
+                }
+                Some(vec![])
+            }
+            [0x05] => {
+                self.set_secure_status(XapSecureStatus::Locked);
+                Some(vec![])
+            }
+            _ => None,
+        }
+    }
+
+    fn execute_qmk(&mut self, route: &[u8]) -> Option<Vec<u8>> {
+        let config = &self.config;
+        match route {
+            [0x00] => Some(config.qmk_version.bcd().to_le_bytes().to_vec()),
+            [0x01] => {
+                let mut capabilities: u32 = 0b1_0111_1111;
+                if config.bootloader_jump_enabled {
+                    capabilities |= 1 << 0x07;
+                }
+                if config.eeprom_reset_enabled {
+                    capabilities |= 1 << 0x09;
+                }
+                Some(capabilities.to_le_bytes().to_vec())
+            }
+            [0x02] => {
+                let mut payload = Vec::new();
+                payload.extend_from_slice(&config.vendor_id.to_le_bytes());
+                payload.extend_from_slice(&config.product_id.to_le_bytes());
+                payload.extend_from_slice(&config.product_version.to_le_bytes());
+                payload.extend_from_slice(&config.qmk_identifier.to_le_bytes());
+                Some(payload)
+            }
+            [0x03] => Some(config.manufacturer.as_bytes().to_vec()),
+            [0x04] => Some(config.product_name.as_bytes().to_vec()),
+            [0x05] => Some((self.config_blob.len() as u16).to_le_bytes().to_vec()),
+            [0x06, lo, hi] => {
+                let offset = u16::from_le_bytes([*lo, *hi]) as usize;
+                let mut chunk = [0_u8; CONFIG_BLOB_CHUNK_SIZE];
+                if offset < self.config_blob.len() {
+                    let data = &self.config_blob[offset..];
+                    let length = data.len().min(CONFIG_BLOB_CHUNK_SIZE);
+                    chunk[..length].copy_from_slice(&data[..length]);
+                }
+                Some(chunk.to_vec())
+            }
+            [0x07] if config.bootloader_jump_enabled => {
+                self.bootloader_jumps += 1;
+                Some(vec![1])
+            }
+            [0x08] => Some(
+                config
+                    .hardware_id
+                    .iter()
+                    .flat_map(|id| id.to_le_bytes())
+                    .collect(),
+            ),
+            [0x09] if config.eeprom_reset_enabled => {
+                self.eeprom_resets += 1;
+                Some(vec![1])
+            }
+            _ => None,
+        }
+    }
+
+    fn execute_keymap(&mut self, route: &[u8]) -> Option<Vec<u8>> {
+        match route {
+            [0x01] => {
+                let mut capabilities: u32 = 0b1110;
+                if self.config.encoders > 0 {
+                    capabilities |= 1 << 0x04;
+                }
+                Some(capabilities.to_le_bytes().to_vec())
+            }
+            [0x02] => Some(vec![self.config.layers]),
+            [0x03, layer, row, column] => {
+                let index = self.keymap_index(*layer, *row, *column)?;
+                Some(self.keymap[index].to_le_bytes().to_vec())
+            }
+            [0x04, layer, encoder, clockwise] => {
+                let index = self.encoder_index(*layer, *encoder, *clockwise)?;
+                Some(self.encoder_keymap[index].to_le_bytes().to_vec())
+            }
+            _ => None,
+        }
+    }
+
+    fn execute_remapping(&mut self, route: &[u8]) -> Option<Vec<u8>> {
+        match route {
+            [0x01] => {
+                let mut capabilities: u32 = 0b1110;
+                if self.config.encoders > 0 {
+                    capabilities |= 1 << 0x04;
+                }
+                Some(capabilities.to_le_bytes().to_vec())
+            }
+            [0x02] => Some(vec![self.config.layers]),
+            [0x03, layer, row, column, lo, hi] => {
+                let index = self.keymap_index(*layer, *row, *column)?;
+                self.keymap[index] = u16::from_le_bytes([*lo, *hi]);
+                Some(vec![])
+            }
+            [0x04, layer, encoder, clockwise, lo, hi] => {
+                let index = self.encoder_index(*layer, *encoder, *clockwise)?;
+                self.encoder_keymap[index] = u16::from_le_bytes([*lo, *hi]);
+                Some(vec![])
+            }
+            _ => None,
+        }
+    }
+
+    fn execute_lighting(&mut self, route: &[u8]) -> Option<Vec<u8>> {
+        let capabilities = self.lighting_capabilities();
+        let is_enabled = |subsystem: u8| capabilities & (1 << subsystem) != 0;
+
+        match route {
+            [0x01] => Some(capabilities.to_le_bytes().to_vec()),
+            [0x02, route @ ..] if is_enabled(0x02) => {
+                let effects: u8 = 0b11;
+                execute_config_routes(route, &effects.to_le_bytes(), &mut self.backlight)
+                    .inspect(|_| self.saved_configs += usize::from(route == [0x05]))
+            }
+            [0x03, route @ ..] if is_enabled(0x03) => {
+                let effects: u64 = 0xFFFF_FFFF;
+                execute_config_routes(route, &effects.to_le_bytes(), &mut self.rgblight)
+                    .inspect(|_| self.saved_configs += usize::from(route == [0x05]))
+            }
+            [0x04, route @ ..] if is_enabled(0x04) => {
+                let effects: u64 = 0xFFFF_FFFF_FFFF;
+                execute_config_routes(route, &effects.to_le_bytes(), &mut self.rgbmatrix)
+                    .inspect(|_| self.saved_configs += usize::from(route == [0x05]))
+            }
+            _ => None,
+        }
+    }
+
+    fn execute_audio(&mut self, route: &[u8]) -> Option<Vec<u8>> {
+        match route {
+            [0x01] => Some(0b11_1010_u32.to_le_bytes().to_vec()),
+            [0x03] | [0x04, ..] => execute_config_routes(route, &[], &mut self.audio),
+            [0x05] => {
+                self.saved_configs += 1;
+                Some(vec![])
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Handles the capabilities, effects and get/set/save config routes shared by all lighting and
+/// audio subsystems.
+fn execute_config_routes(route: &[u8], effects: &[u8], config: &mut [u8]) -> Option<Vec<u8>> {
+    match route {
+        [0x01] => Some(0b11_1110_u32.to_le_bytes().to_vec()),
+        [0x02] if !effects.is_empty() => Some(effects.to_vec()),
+        [0x03] => Some(config.to_vec()),
+        [0x04, new_config @ ..] if new_config.len() == config.len() => {
+            config.copy_from_slice(new_config);
+            Some(vec![])
+        }
+        [0x05] => Some(vec![]),
+        _ => None,
+    }
+}
+
+fn is_secure_route(request: &[u8]) -> bool {
+    matches!(
+        request,
+        [0x01, 0x07, ..] | [0x01, 0x09, ..] | [0x05, 0x03, ..] | [0x05, 0x04, ..]
+    )
+}
+
+fn secure_status_byte(status: XapSecureStatus) -> u8 {
+    match status {
+        XapSecureStatus::Locked => 0,
+        XapSecureStatus::Unlocking => 1,
+        XapSecureStatus::Unlocked => 2,
+    }
+}
+
+fn default_config_json(config: &SimulatorConfig) -> String {
+    let layout: Vec<_> = (0..config.rows)
+        .flat_map(|row| {
+            (0..config.columns).map(move |column| {
+                json!({
+                    "matrix": [row, column],
+                    "x": column,
+                    "y": row,
+                })
+            })
+        })
+        .collect();
+
+    json!({
+        "layouts": {
+            "LAYOUT": {
+                "layout": layout,
+            }
+        },
+        "matrix_size": {
+            "rows": config.rows,
+            "cols": config.columns,
+        },
+    })
+    .to_string()
+}
+
+#[derive(Debug)]
+pub struct SimulatedTransport {
+    keyboard: SimulatedKeyboard,
+}
+
+impl XapTransport for SimulatedTransport {
+    fn write_report(&mut self, report: &[u8]) -> Result<()> {
+        let mut state = self.keyboard.lock();
+        if !state.connected {
+            bail!(
+                "simulated keyboard {} is disconnected",
+                self.keyboard.identity
+            );
+        }
+        state.handle_request(report)
+    }
+
+    fn read_report(&mut self, report: &mut [u8]) -> Result<usize> {
+        let mut state = self.keyboard.lock();
+        if !state.connected {
+            bail!(
+                "simulated keyboard {} is disconnected",
+                self.keyboard.identity
+            );
+        }
+        let Some(pending) = state.reports.pop_front() else {
+            return Ok(0);
+        };
+
+        // Reports are always transferred with their full size, padded with zeroes
+        let length = report.len().min(SIMULATOR_REPORT_SIZE);
+        report[..length].fill(0);
+        report[..pending.len()].copy_from_slice(&pending);
+        Ok(length)
+    }
+
+    fn report_size(&self) -> usize {
+        SIMULATOR_REPORT_SIZE
+    }
+
+    fn identity(&self) -> &XapDeviceIdentity {
+        &self.keyboard.identity
+    }
+}
+
+/// Discovers a fixed set of simulated keyboards, of which only the connected ones are reported.
+#[derive(Debug, Default)]
+pub struct SimulatedDiscovery {
+    keyboards: Vec<SimulatedKeyboard>,
+}
+
+impl SimulatedDiscovery {
+    pub fn new(keyboards: Vec<SimulatedKeyboard>) -> Self {
+        Self { keyboards }
+    }
+}
+
+impl XapDiscovery for SimulatedDiscovery {
+    fn enumerate(&mut self) -> Result<Vec<XapDeviceIdentity>> {
+        Ok(self
+            .keyboards
+            .iter()
+            .filter(|keyboard| keyboard.is_connected())
+            .map(|keyboard| keyboard.identity.clone())
+            .collect())
+    }
+
+    fn open(&mut self, identity: &XapDeviceIdentity) -> Result<Box<dyn XapTransport>> {
+        match self
+            .keyboards
+            .iter()
+            .find(|keyboard| keyboard.identity == *identity && keyboard.is_connected())
+        {
+            Some(keyboard) => Ok(Box::new(keyboard.transport())),
+            None => bail!("simulated keyboard {identity} is not connected"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use serde_json::{json, Value};
+    use xap_specs::{
+        constants::XapConstants, dynamic::RouteTable, error::XapError, token::TokenAllocator,
+    };
+
+    use super::*;
+    use crate::{
+        aggregation::Point3D,
+        client::{XapClient, MAX_REPORTS_PER_POLL},
+        device::XapDevice,
+        event::XapClientEvent,
+        spec::{
+            keymap::{
+                KeymapGetEncoderKeycodeArg, KeymapGetEncoderKeycodeRequest, KeymapGetKeycodeArg,
+                KeymapGetKeycodeRequest, KeymapGetLayerCountRequest,
+            },
+            lighting::{
+                rgblight::{
+                    RgblightGetConfigRequest, RgblightSaveConfigRequest, RgblightSetConfigRequest,
+                },
+                rgbmatrix::RgbmatrixGetConfigRequest,
+            },
+            qmk::{QmkJumpToBootloaderRequest, QmkReinitializeEepromRequest},
+            remapping::RemappingSetKeycodeArg,
+            types::RgbLightConfig,
+            xap::XapSecureUnlockRequest,
+        },
+    };
+
+    #[derive(Debug)]
+    struct TokenRecorder {
+        inner: SimulatedTransport,
+        tokens: Arc<Mutex<Vec<[u8; 2]>>>,
+    }
+
+    impl XapTransport for TokenRecorder {
+        fn write_report(&mut self, report: &[u8]) -> Result<()> {
+            self.tokens.lock().unwrap().push([report[0], report[1]]);
+            self.inner.write_report(report)
+        }
+
+        fn read_report(&mut self, report: &mut [u8]) -> Result<usize> {
+            self.inner.read_report(report)
+        }
+
+        fn report_size(&self) -> usize {
+            self.inner.report_size()
+        }
+
+        fn identity(&self) -> &XapDeviceIdentity {
+            self.inner.identity()
+        }
+    }
+
+    fn constants() -> Arc<XapConstants> {
+        Arc::new(
+            XapConstants::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../xap-specs/assets").into())
+                .expect("failed to load XAP constants"),
+        )
+    }
+
+    fn device(config: SimulatorConfig) -> (SimulatedKeyboard, XapDevice) {
+        let keyboard = SimulatedKeyboard::new(config).unwrap();
+        let device = XapDevice::new(constants(), Box::new(keyboard.transport()))
+            .expect("failed to initialize simulated device");
+        (keyboard, device)
+    }
+
+    #[test]
+    fn device_info() {
+        let (_, device) = device(SimulatorConfig::default());
+        let info = device.xap_info();
+
+        assert_eq!(info.xap.version, XAP_VERSION_0_3_0);
+        assert_eq!(info.qmk.version.to_string(), "0.24.8");
+        assert_eq!(info.qmk.manufacturer, "QMK");
+        assert_eq!(info.qmk.product_name, "XAP Simulator");
+        assert_eq!(info.qmk.board_ids.vendor_id, 0xFEED);
+        assert!(info.qmk.jump_to_bootloader_enabled);
+        assert_eq!(info.keymap.unwrap().layer_count, Some(4));
+        assert_eq!(info.remap.unwrap().layer_count, Some(4));
+
+        let lighting = info.lighting.unwrap();
+        assert!(lighting.backlight.is_none());
+        assert!(lighting.rgblight.is_some());
+        assert!(lighting.rgbmatrix.is_some());
+    }
+
+    #[test]
+    fn older_xap_version_hides_subsystems() {
+        let (_, device) = device(SimulatorConfig {
+            xap_version: XAP_VERSION_0_1_0,
+            ..Default::default()
+        });
+        let info = device.xap_info();
+
+        assert!(info.keymap.is_none());
+        assert!(info.remap.is_none());
+        assert!(info.lighting.is_none());
+    }
+
+    #[test]
+    fn newer_routes_are_rejected() {
+        let (_, mut device) = device(SimulatorConfig {
+            xap_version: XAP_VERSION_0_1_0,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            device.query(KeymapGetLayerCountRequest(())).unwrap_err(),
+            XapError::UnsupportedByVersion {
+                route: vec![4, 2],
+                required: XAP_VERSION_0_2_0,
+                device: XAP_VERSION_0_1_0,
+            }
+        );
+    }
+
+    #[test]
+    fn missing_capabilities_fail_fast() {
+        let (_, mut device) = device(SimulatorConfig {
+            encoders: 0,
+            remapping_enabled: false,
+            rgbmatrix_enabled: false,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            device
+                .query(KeymapGetEncoderKeycodeRequest(KeymapGetEncoderKeycodeArg {
+                    layer: 0,
+                    encoder: 0,
+                    clockwise: 1,
+                }))
+                .unwrap_err(),
+            XapError::UnsupportedByCapability {
+                route: vec![4, 4],
+                condition: "defined(ENCODER_MAP_ENABLE)".to_owned()
+            }
+        );
+        assert_eq!(
+            XapError::from(
+                device
+                    .remap_key(RemappingSetKeycodeArg {
+                        layer: 0,
+                        row: 0,
+                        column: 0,
+                        keycode: 0x0004,
+                    })
+                    .unwrap_err()
+            ),
+            XapError::UnsupportedByCapability {
+                route: vec![5, 3],
+                condition: "defined(DYNAMIC_KEYMAP_ENABLE)".to_owned()
+            }
+        );
+        assert_eq!(
+            device.query(RgbmatrixGetConfigRequest(())).unwrap_err(),
+            XapError::UnsupportedByCapability {
+                route: vec![6, 4, 3],
+                condition: "defined(RGB_MATRIX_ENABLE)".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn keymap() {
+        let (keyboard, device) = device(SimulatorConfig::default());
+
+        let keymap = device.keymap_with_layout("LAYOUT".to_owned()).unwrap();
+        let key = keymap.keys[0][0][2].as_ref().unwrap();
+        assert_eq!(key.key.code.code, keyboard.keycode(0, 0, 2).unwrap());
+        assert_eq!(key.key.code.key, "KC_C");
+    }
+
+    #[test]
+    fn pipelined_query_keeps_order_and_errors() {
+        let (keyboard, mut device) = device(SimulatorConfig::default());
+
+        let responses = device
+            .query_pipelined((0..20).map(|column| {
+                KeymapGetKeycodeRequest(KeymapGetKeycodeArg {
+                    layer: 0,
+                    row: 0,
+                    column,
+                })
+            }))
+            .unwrap();
+
+        assert_eq!(responses.len(), 20);
+        for (column, response) in responses.into_iter().enumerate() {
+            match keyboard.keycode(0, 0, column as u8) {
+                Some(keycode) => assert_eq!(response.unwrap().0, keycode),
+                None => assert_eq!(response.unwrap_err(), XapError::RequestFailed),
+            }
+        }
+    }
+
+    #[test]
+    fn remap_key_requires_unlock() {
+        let (keyboard, mut device) = device(SimulatorConfig {
+            auto_unlock: false,
+            ..Default::default()
+        });
+        let arg = RemappingSetKeycodeArg {
+            layer: 1,
+            row: 2,
+            column: 3,
+            keycode: 0x0029,
+        };
+
+        assert_eq!(
+            XapError::from(device.remap_key(arg.clone()).unwrap_err()),
+            XapError::SecureRouteLocked { route: vec![5, 3] }
+        );
+
+        device.query(XapSecureUnlockRequest(())).unwrap();
+        assert!(matches!(
+            keyboard.secure_status(),
+            XapSecureStatus::Unlocking
+        ));
+        keyboard.complete_unlock();
+        device.remap_key(arg).unwrap();
+
+        assert_eq!(keyboard.keycode(1, 2, 3), Some(0x0029));
+        let key = device.query_key(Point3D { z: 1, y: 2, x: 3 }).unwrap();
+        assert_eq!(key.code.key, "KC_ESCAPE");
+    }
+
+    #[test]
+    fn custom_keycodes_from_config() {
+        let mut config = serde_json::from_str::<serde_json::Value>(&default_config_json(
+            &SimulatorConfig::default(),
+        ))
+        .unwrap();
+        config["keycodes"] = json!([
+            {"key": "CUSTOM_MACRO", "label": "Macro"},
+            {"key": "CUSTOM_RESET"},
+        ]);
+        let (keyboard, mut device) = device(SimulatorConfig {
+            config: Some(config.to_string()),
+            ..Default::default()
+        });
+
+        let code = device.constants().parse_keycode("CUSTOM_RESET").unwrap();
+        assert_eq!(code, 0x7E01);
+        device.set_auto_unlock(true);
+        device
+            .remap_key(RemappingSetKeycodeArg {
+                layer: 0,
+                row: 0,
+                column: 1,
+                keycode: code,
+            })
+            .unwrap();
+
+        assert_eq!(keyboard.keycode(0, 0, 1), Some(0x7E01));
+        let key = device.query_key(Point3D { z: 0, y: 0, x: 1 }).unwrap();
+        assert_eq!(key.code.key, "CUSTOM_RESET");
+        assert_eq!(
+            device.constants().get_keycode(0x7E00).label.as_deref(),
+            Some("Macro")
+        );
+    }
+
+    #[test]
+    fn too_many_custom_keycodes() {
+        let mut config = serde_json::from_str::<serde_json::Value>(&default_config_json(
+            &SimulatorConfig::default(),
+        ))
+        .unwrap();
+        config["keycodes"] = (0..300)
+            .map(|i| json!({ "key": format!("CUSTOM_{i}") }))
+            .collect();
+        let (_, device) = device(SimulatorConfig {
+            config: Some(config.to_string()),
+            ..Default::default()
+        });
+
+        // The device is still usable, just without the custom keycodes
+        assert!(device.constants().parse_keycode("CUSTOM_0").is_err());
+        assert_eq!(device.xap_info().keymap.unwrap().layer_count, Some(4));
+    }
+
+    #[test]
+    fn lighting_config() {
+        let (keyboard, mut device) = device(SimulatorConfig::default());
+        let config = RgbLightConfig {
+            enable: 1,
+            mode: 5,
+            hue: 10,
+            sat: 20,
+            val: 30,
+            speed: 40,
+        };
+
+        device.query(RgblightSetConfigRequest(config)).unwrap();
+        device.query(RgblightSaveConfigRequest(())).unwrap();
+
+        assert_eq!(keyboard.rgblight_config(), [1, 5, 10, 20, 30, 40]);
+        assert_eq!(keyboard.saved_configs(), 1);
+        let read_back = device.query(RgblightGetConfigRequest(())).unwrap();
+        assert_eq!(read_back.hue, 10);
+    }
+
+    #[test]
+    fn dynamic_routes() {
+        let (keyboard, mut device) = device(SimulatorConfig::default());
+        let routes =
+            RouteTable::from_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/../xap-specs/assets"))
+                .expect("failed to read XAP specs");
+
+        let config = json!({"enable": 1, "mode": 5, "hue": 10, "sat": 20, "val": 30, "speed": 40});
+        device
+            .query_dynamic(&routes, "lighting.rgblight.set_config", &config)
+            .unwrap();
+        assert_eq!(keyboard.rgblight_config(), [1, 5, 10, 20, 30, 40]);
+        assert_eq!(
+            device
+                .query_dynamic(&routes, "lighting.rgblight.get_config", &Value::Null)
+                .unwrap(),
+            config
+        );
+        assert_eq!(
+            device
+                .query_dynamic(&routes, "qmk.product_name", &Value::Null)
+                .unwrap(),
+            json!("XAP Simulator")
+        );
+
+        assert_eq!(
+            device
+                .query_dynamic(&routes, "qmk.jump_to_bootloader", &Value::Null)
+                .unwrap_err(),
+            XapError::SecureRouteLocked { route: vec![1, 7] }
+        );
+        assert!(matches!(
+            device.query_dynamic(&routes, "lighting.rgblight.set_config", &json!({"hue": 1})),
+            Err(XapError::InvalidArguments { .. })
+        ));
+    }
+
+    #[test]
+    fn fire_and_forget() {
+        let (keyboard, mut device) = device(SimulatorConfig::default());
+
+        device
+            .send_without_response(RgblightSetConfigRequest(RgbLightConfig {
+                enable: 0,
+                ..Default::default()
+            }))
+            .unwrap();
+
+        assert_eq!(keyboard.rgblight_config(), [0; 6]);
+        assert_eq!(device.poll().unwrap(), None);
+    }
+
+    #[test]
+    fn seeded_tokens_are_reproducible() {
+        let run = || {
+            let keyboard = SimulatedKeyboard::new(SimulatorConfig::default()).unwrap();
+            let recorder = TokenRecorder {
+                inner: keyboard.transport(),
+                tokens: Arc::new(Mutex::new(Vec::new())),
+            };
+            let tokens = Arc::clone(&recorder.tokens);
+            XapDevice::with_token_allocator(
+                constants(),
+                Box::new(recorder),
+                TokenAllocator::with_seed(7),
+            )
+            .unwrap();
+            Arc::into_inner(tokens).unwrap().into_inner().unwrap()
+        };
+
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn secure_route() {
+        let (keyboard, mut device) = device(SimulatorConfig::default());
+
+        assert_eq!(
+            device.query(QmkJumpToBootloaderRequest(())).unwrap_err(),
+            XapError::SecureRouteLocked { route: vec![1, 7] }
+        );
+        assert_eq!(keyboard.bootloader_jumps(), 0);
+
+        device.query(XapSecureUnlockRequest(())).unwrap();
+        device.query(QmkJumpToBootloaderRequest(())).unwrap();
+        device.query(QmkReinitializeEepromRequest(())).unwrap();
+        assert_eq!(keyboard.bootloader_jumps(), 1);
+        assert_eq!(keyboard.eeprom_resets(), 1);
+    }
+
+    #[test]
+    fn secure_status_behind_discarded_response() {
+        let keyboard = SimulatedKeyboard::new(SimulatorConfig::default()).unwrap();
+        let recorder = TokenRecorder {
+            inner: keyboard.transport(),
+            tokens: Arc::new(Mutex::new(Vec::new())),
+        };
+        let tokens = Arc::clone(&recorder.tokens);
+        let mut device = XapDevice::new(constants(), Box::new(recorder)).unwrap();
+
+        // The last response of the initialization is sent twice, the duplicate is discarded
+        // but must not hide the secure status broadcast that follows it
+        let token = u16::from_le_bytes(*tokens.lock().unwrap().last().unwrap());
+        keyboard
+            .lock()
+            .push_response(token, ResponseFlags::SUCCESS, &[]);
+        keyboard.emit_secure_status(XapSecureStatus::Unlocked);
+
+        device.query(QmkJumpToBootloaderRequest(())).unwrap();
+        assert_eq!(keyboard.bootloader_jumps(), 1);
+    }
+
+    /// Plays the user that reacts on the unlock request by either finishing or aborting it.
+    fn user(keyboard: &SimulatedKeyboard, complete: bool) -> std::thread::JoinHandle<()> {
+        let keyboard = keyboard.clone();
+        std::thread::spawn(move || {
+            while !matches!(keyboard.secure_status(), XapSecureStatus::Unlocking) {
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+            if complete {
+                keyboard.complete_unlock();
+            } else {
+                keyboard.emit_secure_status(XapSecureStatus::Locked);
+            }
+        })
+    }
+
+    #[test]
+    fn auto_unlock() {
+        let (keyboard, mut device) = device(SimulatorConfig {
+            auto_unlock: false,
+            ..Default::default()
+        });
+        device.set_auto_unlock(true);
+
+        let user = user(&keyboard, true);
+        device.query(QmkJumpToBootloaderRequest(())).unwrap();
+        user.join().unwrap();
+
+        assert!(matches!(device.secure_status(), XapSecureStatus::Unlocked));
+        assert_eq!(keyboard.bootloader_jumps(), 1);
+    }
+
+    #[test]
+    fn auto_unlock_aborted() {
+        let (keyboard, mut device) = device(SimulatorConfig {
+            auto_unlock: false,
+            ..Default::default()
+        });
+        device.set_auto_unlock(true);
+
+        let user = user(&keyboard, false);
+        assert_eq!(
+            device.query(QmkJumpToBootloaderRequest(())).unwrap_err(),
+            XapError::UnlockFailed
+        );
+        user.join().unwrap();
+
+        assert_eq!(keyboard.bootloader_jumps(), 0);
+    }
+
+    #[test]
+    fn unlock_aborted_before_response() {
+        // The whole Locked -> Unlocking -> Locked sequence is reported before the response to
+        // the unlock request
+        let (keyboard, mut device) = device(SimulatorConfig {
+            auto_unlock: false,
+            abort_unlock: true,
+            ..Default::default()
+        });
+
+        let started = std::time::Instant::now();
+        assert_eq!(device.unlock().unwrap_err(), XapError::UnlockFailed);
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        assert!(matches!(keyboard.secure_status(), XapSecureStatus::Locked));
+    }
+
+    #[test]
+    fn client_drains_log_broadcasts() {
+        let keyboard = SimulatedKeyboard::new(SimulatorConfig::default()).unwrap();
+        let mut client = XapClient::with_discovery(
+            constants().as_ref().clone(),
+            Box::new(SimulatedDiscovery::new(vec![keyboard.clone()])),
+        );
+        client.enumerate_xap_devices().unwrap();
+
+        // Every line spans three reports, more than a single polling cycle reads
+        let expected: Vec<String> = (0..120)
+            .map(|i| format!("{i:03} {}", "x".repeat(146)))
+            .collect();
+        for line in &expected {
+            keyboard.emit_broadcast(BroadcastType::LogMessage, format!("{line}\n").as_bytes());
+        }
+
+        let lines = |events: Vec<XapClientEvent>| -> Vec<String> {
+            events
+                .into_iter()
+                .map(|event| match event {
+                    XapClientEvent::LogReceived { line, .. } => line.text,
+                    event => panic!("expected a log line, got {event:?}"),
+                })
+                .collect()
+        };
+        let mut received = lines(client.poll_devices().unwrap());
+        assert_eq!(received.len(), MAX_REPORTS_PER_POLL / 3);
+        received.extend(lines(client.poll_devices().unwrap()));
+
+        assert_eq!(received, expected);
+        assert!(client.poll_devices().unwrap().is_empty());
+    }
+
+    #[test]
+    fn client_enumeration_and_broadcasts() {
+        let keyboard = SimulatedKeyboard::new(SimulatorConfig::default()).unwrap();
+        let mut client = XapClient::with_discovery(
+            constants().as_ref().clone(),
+            Box::new(SimulatedDiscovery::new(vec![keyboard.clone()])),
+        );
+
+        let events = client.enumerate_xap_devices().unwrap();
+        let [XapClientEvent::NewDevice { id }] = events[..] else {
+            panic!("expected a single new device, got {events:?}");
+        };
+
+        keyboard.emit_secure_status(XapSecureStatus::Unlocked);
+        let events = client.poll_devices().unwrap();
+        assert!(matches!(
+            events[..],
+            [XapClientEvent::SecureStatusChanged {
+                secure_status: XapSecureStatus::Unlocked,
+                ..
+            }]
+        ));
+
+        client
+            .broadcast_decoders_mut()
+            .register_keyboard(0xFEED, 0x0001, |payload| {
+                Ok(serde_json::json!({ "layer": payload[0] }))
+            });
+        keyboard.emit_broadcast(BroadcastType::User, &[0x01, 0x02]);
+        keyboard.emit_broadcast(BroadcastType::Keyboard, &[0x03]);
+        let mut events = client.poll_devices().unwrap();
+        events.extend(client.poll_devices().unwrap());
+        let [XapClientEvent::UserBroadcast {
+            broadcast: user, ..
+        }, XapClientEvent::KeyboardBroadcast {
+            broadcast: keyboard_broadcast,
+            ..
+        }] = &events[..]
+        else {
+            panic!("expected a user and a keyboard broadcast, got {events:?}");
+        };
+        assert_eq!(user.payload, [0x01, 0x02]);
+        assert_eq!(user.decoded, None);
+        assert_eq!(
+            keyboard_broadcast.decoded,
+            Some(serde_json::json!({ "layer": 3 }))
+        );
+        assert!(client.poll_devices().unwrap().is_empty());
+
+        keyboard.emit_broadcast(BroadcastType::LogMessage, b"Hello ");
+        keyboard.emit_broadcast(BroadcastType::LogMessage, b"QMK!\nnext");
+        let mut events = client.poll_devices().unwrap();
+        events.extend(client.poll_devices().unwrap());
+        let [XapClientEvent::LogReceived { line, .. }] = &events[..] else {
+            panic!("expected a single log line, got {events:?}");
+        };
+        assert_eq!(line.text, "Hello QMK!");
+        assert_eq!(client.get_device(&id).unwrap().console().lines().count(), 1);
+
+        keyboard.set_connected(false);
+        let events = client.enumerate_xap_devices().unwrap();
+        assert!(
+            matches!(events[..], [XapClientEvent::RemovedDevice { id: removed }] if removed == id)
+        );
+    }
+}
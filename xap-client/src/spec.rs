@@ -6,11 +6,11 @@ pub mod xap {
     use binrw::{BinRead, BinWrite};
     use bitflags::bitflags;
     use serde::{Deserialize, Serialize};
-    use specta::Type;
 
-    use crate::xap::spec::types::*;
-    use xap_specs::request::XapRequest;
+    use crate::spec::types::*;
+    use xap_specs::request::{XapCapability, XapRequest};
     use xap_specs::response::UTF8String;
+    use xap_specs::version::BcdVersion;
 
     /// ======================================================================
     /// Version
@@ -20,7 +20,8 @@ pub mod xap {
     /// * Returns the BCD-encoded version in the format of XX.YY.ZZZZ => `0xXXYYZZZZ`
     ///     * e.g. 3.2.115 will match `0x03020115`, or bytes {0x15,0x01,0x02,0x03}.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct XapVersionRequest(pub ());
 
     impl From<()> for XapVersionRequest {
@@ -36,20 +37,22 @@ pub mod xap {
             &[0x00, 0x00]
         }
 
-        fn xap_version() -> u32 {
-            0x00000001
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 0, 1)
         }
     }
 
-    #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
-    pub struct XapVersionResponse(pub u32);
+    #[derive(BinRead, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
+    pub struct XapVersionResponse(pub BcdVersion);
 
     /// ======================================================================
     ///  capabilities
     ///
     /// XAP subsystem capabilities query. Each bit should be considered as a "usable" route within this subsystem.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct XapCapabilitiesRequest(pub ());
 
     impl From<()> for XapCapabilitiesRequest {
@@ -65,14 +68,13 @@ pub mod xap {
             &[0x00, 0x01]
         }
 
-        fn xap_version() -> u32 {
-            0x00000100
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 1, 0)
         }
     }
 
-    #[derive(
-        BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize, Type,
-    )]
+    #[derive(BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct XapCapabilitiesFlags(u32);
 
     bitflags! {
@@ -92,7 +94,8 @@ pub mod xap {
     ///
     /// XAP protocol subsystem query. Each bit should be considered as a "usable" subsystem. For example, checking `(value & (1 << XAP_ROUTE_QMK) != 0)` means the QMK subsystem is enabled and available for querying.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct XapEnabledSubsystemCapabilitiesRequest(pub ());
 
     impl From<()> for XapEnabledSubsystemCapabilitiesRequest {
@@ -108,14 +111,13 @@ pub mod xap {
             &[0x00, 0x02]
         }
 
-        fn xap_version() -> u32 {
-            0x00000100
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 1, 0)
         }
     }
 
-    #[derive(
-        BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize, Type,
-    )]
+    #[derive(BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct XapEnabledSubsystemCapabilitiesFlags(u32);
 
     bitflags! {
@@ -142,7 +144,8 @@ pub mod xap {
     /// * 2 means secure routes are allowed
     /// * any other value should be interpreted as disabled
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct XapSecureStatusRequest(pub ());
 
     impl From<()> for XapSecureStatusRequest {
@@ -158,12 +161,13 @@ pub mod xap {
             &[0x00, 0x03]
         }
 
-        fn xap_version() -> u32 {
-            0x00000100
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 1, 0)
         }
     }
 
-    #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinRead, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct XapSecureStatusResponse(pub u8);
 
     /// ======================================================================
@@ -171,7 +175,8 @@ pub mod xap {
     ///
     /// Initiate secure route unlock sequence
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct XapSecureUnlockRequest(pub ());
 
     impl From<()> for XapSecureUnlockRequest {
@@ -187,8 +192,8 @@ pub mod xap {
             &[0x00, 0x04]
         }
 
-        fn xap_version() -> u32 {
-            0x00000100
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 1, 0)
         }
     }
 
@@ -197,7 +202,8 @@ pub mod xap {
     ///
     /// Disable secure routes
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct XapSecureLockRequest(pub ());
 
     impl From<()> for XapSecureLockRequest {
@@ -213,8 +219,8 @@ pub mod xap {
             &[0x00, 0x05]
         }
 
-        fn xap_version() -> u32 {
-            0x00000100
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 1, 0)
         }
     }
 }
@@ -225,11 +231,11 @@ pub mod qmk {
     use binrw::{BinRead, BinWrite};
     use bitflags::bitflags;
     use serde::{Deserialize, Serialize};
-    use specta::Type;
 
-    use crate::xap::spec::types::*;
-    use xap_specs::request::XapRequest;
+    use crate::spec::types::*;
+    use xap_specs::request::{XapCapability, XapRequest};
     use xap_specs::response::UTF8String;
+    use xap_specs::version::BcdVersion;
 
     /// ======================================================================
     /// Version
@@ -239,7 +245,8 @@ pub mod qmk {
     /// * Returns the BCD-encoded version in the format of XX.YY.ZZZZ => `0xXXYYZZZZ`
     ///     * e.g. 3.2.115 will match `0x03020115`, or bytes {0x15,0x01,0x02,0x03}.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkVersionRequest(pub ());
 
     impl From<()> for QmkVersionRequest {
@@ -255,20 +262,22 @@ pub mod qmk {
             &[0x01, 0x00]
         }
 
-        fn xap_version() -> u32 {
-            0x00000100
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 1, 0)
         }
     }
 
-    #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
-    pub struct QmkVersionResponse(pub u32);
+    #[derive(BinRead, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
+    pub struct QmkVersionResponse(pub BcdVersion);
 
     /// ======================================================================
     ///  capabilities
     ///
     /// QMK subsystem capabilities query. Each bit should be considered as a "usable" route within this subsystem.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkCapabilitiesRequest(pub ());
 
     impl From<()> for QmkCapabilitiesRequest {
@@ -284,14 +293,13 @@ pub mod qmk {
             &[0x01, 0x01]
         }
 
-        fn xap_version() -> u32 {
-            0x00000100
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 1, 0)
         }
     }
 
-    #[derive(
-        BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize, Type,
-    )]
+    #[derive(BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkCapabilitiesFlags(u32);
 
     bitflags! {
@@ -315,7 +323,8 @@ pub mod qmk {
     ///
     /// Retrieves the set of identifying information for the board.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkBoardIdentifiersRequest(pub ());
 
     impl From<()> for QmkBoardIdentifiersRequest {
@@ -331,12 +340,13 @@ pub mod qmk {
             &[0x01, 0x02]
         }
 
-        fn xap_version() -> u32 {
-            0x00000100
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 1, 0)
         }
     }
 
-    #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinRead, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkBoardIdentifiersResponse {
         pub vendor_id: u16,
         pub product_id: u16,
@@ -349,7 +359,8 @@ pub mod qmk {
     ///
     /// Retrieves the name of the manufacturer
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkBoardManufacturerRequest(pub ());
 
     impl From<()> for QmkBoardManufacturerRequest {
@@ -365,12 +376,13 @@ pub mod qmk {
             &[0x01, 0x03]
         }
 
-        fn xap_version() -> u32 {
-            0x00000100
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 1, 0)
         }
     }
 
-    #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinRead, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkBoardManufacturerResponse(pub UTF8String);
 
     /// ======================================================================
@@ -378,7 +390,8 @@ pub mod qmk {
     ///
     /// Retrieves the product name
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkProductNameRequest(pub ());
 
     impl From<()> for QmkProductNameRequest {
@@ -394,12 +407,13 @@ pub mod qmk {
             &[0x01, 0x04]
         }
 
-        fn xap_version() -> u32 {
-            0x00000100
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 1, 0)
         }
     }
 
-    #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinRead, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkProductNameResponse(pub UTF8String);
 
     /// ======================================================================
@@ -407,7 +421,8 @@ pub mod qmk {
     ///
     /// Retrieves the length of the configuration data bundled within the firmware
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkConfigBlobLengthRequest(pub ());
 
     impl From<()> for QmkConfigBlobLengthRequest {
@@ -423,12 +438,13 @@ pub mod qmk {
             &[0x01, 0x05]
         }
 
-        fn xap_version() -> u32 {
-            0x00000100
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 1, 0)
         }
     }
 
-    #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinRead, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkConfigBlobLengthResponse(pub u16);
 
     /// ======================================================================
@@ -436,7 +452,8 @@ pub mod qmk {
     ///
     /// Retrieves a chunk of the configuration data bundled within the firmware
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkConfigBlobChunkRequest(pub u16);
 
     impl From<u16> for QmkConfigBlobChunkRequest {
@@ -452,12 +469,13 @@ pub mod qmk {
             &[0x01, 0x06]
         }
 
-        fn xap_version() -> u32 {
-            0x00000100
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 1, 0)
         }
     }
 
-    #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinRead, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkConfigBlobChunkResponse(pub [u8; 32]);
 
     /// ======================================================================
@@ -470,7 +488,8 @@ pub mod qmk {
     /// * 0 means secure routes are disabled, and should be considered as a failure
     /// * 1 means successful, board will jump to bootloader
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkJumpToBootloaderRequest(pub ());
 
     impl From<()> for QmkJumpToBootloaderRequest {
@@ -486,12 +505,25 @@ pub mod qmk {
             &[0x01, 0x07]
         }
 
-        fn xap_version() -> u32 {
-            0x00000100
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 1, 0)
+        }
+
+        fn is_secure() -> bool {
+            true
+        }
+
+        fn capabilities() -> &'static [XapCapability] {
+            &[XapCapability {
+                route: &[0x01, 0x01],
+                bit: 7,
+                condition: "defined(BOOTLOADER_JUMP_SUPPORTED)",
+            }]
         }
     }
 
-    #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinRead, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkJumpToBootloaderResponse(pub u8);
 
     /// ======================================================================
@@ -499,7 +531,8 @@ pub mod qmk {
     ///
     /// Retrieves a unique identifier for the board.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkHardwareIdentifierRequest(pub ());
 
     impl From<()> for QmkHardwareIdentifierRequest {
@@ -515,12 +548,13 @@ pub mod qmk {
             &[0x01, 0x08]
         }
 
-        fn xap_version() -> u32 {
-            0x00000100
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 1, 0)
         }
     }
 
-    #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinRead, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkHardwareIdentifierResponse(pub [u32; 4]);
 
     /// ======================================================================
@@ -533,7 +567,8 @@ pub mod qmk {
     /// * 0 means secure routes are disabled, and should be considered as a failure
     /// * 1 means successful, board will reinitialize and then reboot
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkReinitializeEepromRequest(pub ());
 
     impl From<()> for QmkReinitializeEepromRequest {
@@ -549,12 +584,25 @@ pub mod qmk {
             &[0x01, 0x09]
         }
 
-        fn xap_version() -> u32 {
-            0x00000100
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 1, 0)
+        }
+
+        fn is_secure() -> bool {
+            true
+        }
+
+        fn capabilities() -> &'static [XapCapability] {
+            &[XapCapability {
+                route: &[0x01, 0x01],
+                bit: 9,
+                condition: "!defined(NO_RESET)",
+            }]
         }
     }
 
-    #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinRead, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct QmkReinitializeEepromResponse(pub u8);
 }
 
@@ -564,11 +612,11 @@ pub mod keyboard {
     use binrw::{BinRead, BinWrite};
     use bitflags::bitflags;
     use serde::{Deserialize, Serialize};
-    use specta::Type;
 
-    use crate::xap::spec::types::*;
-    use xap_specs::request::XapRequest;
+    use crate::spec::types::*;
+    use xap_specs::request::{XapCapability, XapRequest};
     use xap_specs::response::UTF8String;
+    use xap_specs::version::BcdVersion;
 }
 
 #[allow(dead_code)]
@@ -577,11 +625,11 @@ pub mod user {
     use binrw::{BinRead, BinWrite};
     use bitflags::bitflags;
     use serde::{Deserialize, Serialize};
-    use specta::Type;
 
-    use crate::xap::spec::types::*;
-    use xap_specs::request::XapRequest;
+    use crate::spec::types::*;
+    use xap_specs::request::{XapCapability, XapRequest};
     use xap_specs::response::UTF8String;
+    use xap_specs::version::BcdVersion;
 }
 
 #[allow(dead_code)]
@@ -590,18 +638,19 @@ pub mod keymap {
     use binrw::{BinRead, BinWrite};
     use bitflags::bitflags;
     use serde::{Deserialize, Serialize};
-    use specta::Type;
 
-    use crate::xap::spec::types::*;
-    use xap_specs::request::XapRequest;
+    use crate::spec::types::*;
+    use xap_specs::request::{XapCapability, XapRequest};
     use xap_specs::response::UTF8String;
+    use xap_specs::version::BcdVersion;
 
     /// ======================================================================
     ///  capabilities
     ///
     /// Keymap subsystem capabilities query. Each bit should be considered as a "usable" route within this subsystem.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct KeymapCapabilitiesRequest(pub ());
 
     impl From<()> for KeymapCapabilitiesRequest {
@@ -617,14 +666,13 @@ pub mod keymap {
             &[0x04, 0x01]
         }
 
-        fn xap_version() -> u32 {
-            0x00000200
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 2, 0)
         }
     }
 
-    #[derive(
-        BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize, Type,
-    )]
+    #[derive(BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct KeymapCapabilitiesFlags(u32);
 
     bitflags! {
@@ -642,7 +690,8 @@ pub mod keymap {
     ///
     /// Query maximum number of layers that can be addressed within the keymap.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct KeymapGetLayerCountRequest(pub ());
 
     impl From<()> for KeymapGetLayerCountRequest {
@@ -658,12 +707,13 @@ pub mod keymap {
             &[0x04, 0x02]
         }
 
-        fn xap_version() -> u32 {
-            0x00000200
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 2, 0)
         }
     }
 
-    #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinRead, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct KeymapGetLayerCountResponse(pub u8);
 
     /// ======================================================================
@@ -671,10 +721,12 @@ pub mod keymap {
     ///
     /// Query the Keycode at the requested location.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct KeymapGetKeycodeRequest(pub KeymapGetKeycodeArg);
 
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Deserialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize, Deserialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct KeymapGetKeycodeArg {
         pub layer: u8,
         pub row: u8,
@@ -694,12 +746,13 @@ pub mod keymap {
             &[0x04, 0x03]
         }
 
-        fn xap_version() -> u32 {
-            0x00000200
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 2, 0)
         }
     }
 
-    #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinRead, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct KeymapGetKeycodeResponse(pub u16);
 
     /// ======================================================================
@@ -707,10 +760,12 @@ pub mod keymap {
     ///
     /// Query the Keycode at the requested location.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct KeymapGetEncoderKeycodeRequest(pub KeymapGetEncoderKeycodeArg);
 
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Deserialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize, Deserialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct KeymapGetEncoderKeycodeArg {
         pub layer: u8,
         pub encoder: u8,
@@ -730,12 +785,21 @@ pub mod keymap {
             &[0x04, 0x04]
         }
 
-        fn xap_version() -> u32 {
-            0x00000200
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 2, 0)
+        }
+
+        fn capabilities() -> &'static [XapCapability] {
+            &[XapCapability {
+                route: &[0x04, 0x01],
+                bit: 4,
+                condition: "defined(ENCODER_MAP_ENABLE)",
+            }]
         }
     }
 
-    #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinRead, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct KeymapGetEncoderKeycodeResponse(pub u16);
 }
 
@@ -745,18 +809,19 @@ pub mod remapping {
     use binrw::{BinRead, BinWrite};
     use bitflags::bitflags;
     use serde::{Deserialize, Serialize};
-    use specta::Type;
 
-    use crate::xap::spec::types::*;
-    use xap_specs::request::XapRequest;
+    use crate::spec::types::*;
+    use xap_specs::request::{XapCapability, XapRequest};
     use xap_specs::response::UTF8String;
+    use xap_specs::version::BcdVersion;
 
     /// ======================================================================
     ///  capabilities
     ///
     /// Remapping subsystem capabilities query. Each bit should be considered as a "usable" route within this subsystem.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct RemappingCapabilitiesRequest(pub ());
 
     impl From<()> for RemappingCapabilitiesRequest {
@@ -772,14 +837,21 @@ pub mod remapping {
             &[0x05, 0x01]
         }
 
-        fn xap_version() -> u32 {
-            0x00000200
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 2, 0)
+        }
+
+        fn capabilities() -> &'static [XapCapability] {
+            &[XapCapability {
+                route: &[0x00, 0x02],
+                bit: 5,
+                condition: "defined(DYNAMIC_KEYMAP_ENABLE)",
+            }]
         }
     }
 
-    #[derive(
-        BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize, Type,
-    )]
+    #[derive(BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct RemappingCapabilitiesFlags(u32);
 
     bitflags! {
@@ -797,7 +869,8 @@ pub mod remapping {
     ///
     /// Query maximum number of layers that can be addressed within the keymap.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct RemappingGetLayerCountRequest(pub ());
 
     impl From<()> for RemappingGetLayerCountRequest {
@@ -813,12 +886,21 @@ pub mod remapping {
             &[0x05, 0x02]
         }
 
-        fn xap_version() -> u32 {
-            0x00000200
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 2, 0)
+        }
+
+        fn capabilities() -> &'static [XapCapability] {
+            &[XapCapability {
+                route: &[0x00, 0x02],
+                bit: 5,
+                condition: "defined(DYNAMIC_KEYMAP_ENABLE)",
+            }]
         }
     }
 
-    #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinRead, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct RemappingGetLayerCountResponse(pub u8);
 
     /// ======================================================================
@@ -826,10 +908,12 @@ pub mod remapping {
     ///
     /// Modify the Keycode at the requested location.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct RemappingSetKeycodeRequest(pub RemappingSetKeycodeArg);
 
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Deserialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize, Deserialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct RemappingSetKeycodeArg {
         pub layer: u8,
         pub row: u8,
@@ -850,8 +934,20 @@ pub mod remapping {
             &[0x05, 0x03]
         }
 
-        fn xap_version() -> u32 {
-            0x00000200
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 2, 0)
+        }
+
+        fn is_secure() -> bool {
+            true
+        }
+
+        fn capabilities() -> &'static [XapCapability] {
+            &[XapCapability {
+                route: &[0x00, 0x02],
+                bit: 5,
+                condition: "defined(DYNAMIC_KEYMAP_ENABLE)",
+            }]
         }
     }
 
@@ -860,10 +956,12 @@ pub mod remapping {
     ///
     /// Modify the Keycode at the requested location.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct RemappingSetEncoderKeycodeRequest(pub RemappingSetEncoderKeycodeArg);
 
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Deserialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize, Deserialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct RemappingSetEncoderKeycodeArg {
         pub layer: u8,
         pub encoder: u8,
@@ -884,8 +982,27 @@ pub mod remapping {
             &[0x05, 0x04]
         }
 
-        fn xap_version() -> u32 {
-            0x00000200
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 2, 0)
+        }
+
+        fn is_secure() -> bool {
+            true
+        }
+
+        fn capabilities() -> &'static [XapCapability] {
+            &[
+                XapCapability {
+                    route: &[0x00, 0x02],
+                    bit: 5,
+                    condition: "defined(DYNAMIC_KEYMAP_ENABLE)",
+                },
+                XapCapability {
+                    route: &[0x05, 0x01],
+                    bit: 4,
+                    condition: "defined(ENCODER_MAP_ENABLE)",
+                },
+            ]
         }
     }
 }
@@ -896,18 +1013,19 @@ pub mod lighting {
     use binrw::{BinRead, BinWrite};
     use bitflags::bitflags;
     use serde::{Deserialize, Serialize};
-    use specta::Type;
 
-    use crate::xap::spec::types::*;
-    use xap_specs::request::XapRequest;
+    use crate::spec::types::*;
+    use xap_specs::request::{XapCapability, XapRequest};
     use xap_specs::response::UTF8String;
+    use xap_specs::version::BcdVersion;
 
     /// ======================================================================
     ///  capabilities
     ///
     /// Lighting subsystem capabilities query. Each bit should be considered as a "usable" route within this subsystem.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct LightingCapabilitiesRequest(pub ());
 
     impl From<()> for LightingCapabilitiesRequest {
@@ -923,14 +1041,13 @@ pub mod lighting {
             &[0x06, 0x01]
         }
 
-        fn xap_version() -> u32 {
-            0x00000200
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 2, 0)
         }
     }
 
-    #[derive(
-        BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize, Type,
-    )]
+    #[derive(BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct LightingCapabilitiesFlags(u32);
 
     bitflags! {
@@ -949,18 +1066,19 @@ pub mod lighting {
         use binrw::{BinRead, BinWrite};
         use bitflags::bitflags;
         use serde::{Deserialize, Serialize};
-        use specta::Type;
 
-        use crate::xap::spec::types::*;
-        use xap_specs::request::XapRequest;
+        use crate::spec::types::*;
+        use xap_specs::request::{XapCapability, XapRequest};
         use xap_specs::response::UTF8String;
+        use xap_specs::version::BcdVersion;
 
         /// ======================================================================
         ///  capabilities
         ///
         /// backlight subsystem capabilities query. Each bit should be considered as a "usable" route within this subsystem.
         /// ======================================================================
-        #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct BacklightCapabilitiesRequest(pub ());
 
         impl From<()> for BacklightCapabilitiesRequest {
@@ -976,14 +1094,21 @@ pub mod lighting {
                 &[0x06, 0x02, 0x01]
             }
 
-            fn xap_version() -> u32 {
-                0x00000300
+            fn xap_version() -> BcdVersion {
+                BcdVersion::new(0, 3, 0)
+            }
+
+            fn capabilities() -> &'static [XapCapability] {
+                &[XapCapability {
+                    route: &[0x06, 0x01],
+                    bit: 2,
+                    condition: "defined(BACKLIGHT_ENABLE)",
+                }]
             }
         }
 
-        #[derive(
-            BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize, Type,
-        )]
+        #[derive(BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct BacklightCapabilitiesFlags(u32);
 
         bitflags! {
@@ -1002,7 +1127,8 @@ pub mod lighting {
         ///
         /// Each bit should be considered as a "usable" effect id
         /// ======================================================================
-        #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct BacklightGetEnabledEffectsRequest(pub ());
 
         impl From<()> for BacklightGetEnabledEffectsRequest {
@@ -1018,12 +1144,21 @@ pub mod lighting {
                 &[0x06, 0x02, 0x02]
             }
 
-            fn xap_version() -> u32 {
-                0x00000300
+            fn xap_version() -> BcdVersion {
+                BcdVersion::new(0, 3, 0)
+            }
+
+            fn capabilities() -> &'static [XapCapability] {
+                &[XapCapability {
+                    route: &[0x06, 0x01],
+                    bit: 2,
+                    condition: "defined(BACKLIGHT_ENABLE)",
+                }]
             }
         }
 
-        #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinRead, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct BacklightGetEnabledEffectsResponse(pub u8);
 
         /// ======================================================================
@@ -1031,7 +1166,8 @@ pub mod lighting {
         ///
         /// Query the current config.
         /// ======================================================================
-        #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct BacklightGetConfigRequest(pub ());
 
         impl From<()> for BacklightGetConfigRequest {
@@ -1047,8 +1183,16 @@ pub mod lighting {
                 &[0x06, 0x02, 0x03]
             }
 
-            fn xap_version() -> u32 {
-                0x00000300
+            fn xap_version() -> BcdVersion {
+                BcdVersion::new(0, 3, 0)
+            }
+
+            fn capabilities() -> &'static [XapCapability] {
+                &[XapCapability {
+                    route: &[0x06, 0x01],
+                    bit: 2,
+                    condition: "defined(BACKLIGHT_ENABLE)",
+                }]
             }
         }
 
@@ -1057,7 +1201,8 @@ pub mod lighting {
         ///
         /// Set the current config.
         /// ======================================================================
-        #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct BacklightSetConfigRequest(pub BacklightConfig);
 
         impl From<BacklightConfig> for BacklightSetConfigRequest {
@@ -1073,8 +1218,16 @@ pub mod lighting {
                 &[0x06, 0x02, 0x04]
             }
 
-            fn xap_version() -> u32 {
-                0x00000300
+            fn xap_version() -> BcdVersion {
+                BcdVersion::new(0, 3, 0)
+            }
+
+            fn capabilities() -> &'static [XapCapability] {
+                &[XapCapability {
+                    route: &[0x06, 0x01],
+                    bit: 2,
+                    condition: "defined(BACKLIGHT_ENABLE)",
+                }]
             }
         }
 
@@ -1083,7 +1236,8 @@ pub mod lighting {
         ///
         /// Save the current config.
         /// ======================================================================
-        #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct BacklightSaveConfigRequest(pub ());
 
         impl From<()> for BacklightSaveConfigRequest {
@@ -1099,8 +1253,16 @@ pub mod lighting {
                 &[0x06, 0x02, 0x05]
             }
 
-            fn xap_version() -> u32 {
-                0x00000300
+            fn xap_version() -> BcdVersion {
+                BcdVersion::new(0, 3, 0)
+            }
+
+            fn capabilities() -> &'static [XapCapability] {
+                &[XapCapability {
+                    route: &[0x06, 0x01],
+                    bit: 2,
+                    condition: "defined(BACKLIGHT_ENABLE)",
+                }]
             }
         }
     }
@@ -1111,18 +1273,19 @@ pub mod lighting {
         use binrw::{BinRead, BinWrite};
         use bitflags::bitflags;
         use serde::{Deserialize, Serialize};
-        use specta::Type;
 
-        use crate::xap::spec::types::*;
-        use xap_specs::request::XapRequest;
+        use crate::spec::types::*;
+        use xap_specs::request::{XapCapability, XapRequest};
         use xap_specs::response::UTF8String;
+        use xap_specs::version::BcdVersion;
 
         /// ======================================================================
         ///  capabilities
         ///
         /// rgblight subsystem capabilities query. Each bit should be considered as a "usable" route within this subsystem.
         /// ======================================================================
-        #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct RgblightCapabilitiesRequest(pub ());
 
         impl From<()> for RgblightCapabilitiesRequest {
@@ -1138,14 +1301,21 @@ pub mod lighting {
                 &[0x06, 0x03, 0x01]
             }
 
-            fn xap_version() -> u32 {
-                0x00000300
+            fn xap_version() -> BcdVersion {
+                BcdVersion::new(0, 3, 0)
+            }
+
+            fn capabilities() -> &'static [XapCapability] {
+                &[XapCapability {
+                    route: &[0x06, 0x01],
+                    bit: 3,
+                    condition: "defined(RGBLIGHT_ENABLE)",
+                }]
             }
         }
 
-        #[derive(
-            BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize, Type,
-        )]
+        #[derive(BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct RgblightCapabilitiesFlags(u32);
 
         bitflags! {
@@ -1164,7 +1334,8 @@ pub mod lighting {
         ///
         /// Each bit should be considered as a "usable" effect id
         /// ======================================================================
-        #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct RgblightGetEnabledEffectsRequest(pub ());
 
         impl From<()> for RgblightGetEnabledEffectsRequest {
@@ -1180,12 +1351,21 @@ pub mod lighting {
                 &[0x06, 0x03, 0x02]
             }
 
-            fn xap_version() -> u32 {
-                0x00000300
+            fn xap_version() -> BcdVersion {
+                BcdVersion::new(0, 3, 0)
+            }
+
+            fn capabilities() -> &'static [XapCapability] {
+                &[XapCapability {
+                    route: &[0x06, 0x01],
+                    bit: 3,
+                    condition: "defined(RGBLIGHT_ENABLE)",
+                }]
             }
         }
 
-        #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinRead, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct RgblightGetEnabledEffectsResponse(pub u64);
 
         /// ======================================================================
@@ -1193,7 +1373,8 @@ pub mod lighting {
         ///
         /// Query the current config.
         /// ======================================================================
-        #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct RgblightGetConfigRequest(pub ());
 
         impl From<()> for RgblightGetConfigRequest {
@@ -1209,8 +1390,16 @@ pub mod lighting {
                 &[0x06, 0x03, 0x03]
             }
 
-            fn xap_version() -> u32 {
-                0x00000300
+            fn xap_version() -> BcdVersion {
+                BcdVersion::new(0, 3, 0)
+            }
+
+            fn capabilities() -> &'static [XapCapability] {
+                &[XapCapability {
+                    route: &[0x06, 0x01],
+                    bit: 3,
+                    condition: "defined(RGBLIGHT_ENABLE)",
+                }]
             }
         }
 
@@ -1219,7 +1408,8 @@ pub mod lighting {
         ///
         /// Set the current config.
         /// ======================================================================
-        #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct RgblightSetConfigRequest(pub RgbLightConfig);
 
         impl From<RgbLightConfig> for RgblightSetConfigRequest {
@@ -1235,8 +1425,16 @@ pub mod lighting {
                 &[0x06, 0x03, 0x04]
             }
 
-            fn xap_version() -> u32 {
-                0x00000300
+            fn xap_version() -> BcdVersion {
+                BcdVersion::new(0, 3, 0)
+            }
+
+            fn capabilities() -> &'static [XapCapability] {
+                &[XapCapability {
+                    route: &[0x06, 0x01],
+                    bit: 3,
+                    condition: "defined(RGBLIGHT_ENABLE)",
+                }]
             }
         }
 
@@ -1245,7 +1443,8 @@ pub mod lighting {
         ///
         /// Save the current config.
         /// ======================================================================
-        #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct RgblightSaveConfigRequest(pub ());
 
         impl From<()> for RgblightSaveConfigRequest {
@@ -1261,8 +1460,16 @@ pub mod lighting {
                 &[0x06, 0x03, 0x05]
             }
 
-            fn xap_version() -> u32 {
-                0x00000300
+            fn xap_version() -> BcdVersion {
+                BcdVersion::new(0, 3, 0)
+            }
+
+            fn capabilities() -> &'static [XapCapability] {
+                &[XapCapability {
+                    route: &[0x06, 0x01],
+                    bit: 3,
+                    condition: "defined(RGBLIGHT_ENABLE)",
+                }]
             }
         }
     }
@@ -1273,18 +1480,19 @@ pub mod lighting {
         use binrw::{BinRead, BinWrite};
         use bitflags::bitflags;
         use serde::{Deserialize, Serialize};
-        use specta::Type;
 
-        use crate::xap::spec::types::*;
-        use xap_specs::request::XapRequest;
+        use crate::spec::types::*;
+        use xap_specs::request::{XapCapability, XapRequest};
         use xap_specs::response::UTF8String;
+        use xap_specs::version::BcdVersion;
 
         /// ======================================================================
         ///  capabilities
         ///
         /// rgb matrix subsystem capabilities query. Each bit should be considered as a "usable" route within this subsystem.
         /// ======================================================================
-        #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct RgbmatrixCapabilitiesRequest(pub ());
 
         impl From<()> for RgbmatrixCapabilitiesRequest {
@@ -1300,14 +1508,21 @@ pub mod lighting {
                 &[0x06, 0x04, 0x01]
             }
 
-            fn xap_version() -> u32 {
-                0x00000300
+            fn xap_version() -> BcdVersion {
+                BcdVersion::new(0, 3, 0)
+            }
+
+            fn capabilities() -> &'static [XapCapability] {
+                &[XapCapability {
+                    route: &[0x06, 0x01],
+                    bit: 4,
+                    condition: "defined(RGB_MATRIX_ENABLE)",
+                }]
             }
         }
 
-        #[derive(
-            BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize, Type,
-        )]
+        #[derive(BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct RgbmatrixCapabilitiesFlags(u32);
 
         bitflags! {
@@ -1326,7 +1541,8 @@ pub mod lighting {
         ///
         /// Each bit should be considered as a "usable" effect id
         /// ======================================================================
-        #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct RgbmatrixGetEnabledEffectsRequest(pub ());
 
         impl From<()> for RgbmatrixGetEnabledEffectsRequest {
@@ -1342,12 +1558,21 @@ pub mod lighting {
                 &[0x06, 0x04, 0x02]
             }
 
-            fn xap_version() -> u32 {
-                0x00000300
+            fn xap_version() -> BcdVersion {
+                BcdVersion::new(0, 3, 0)
+            }
+
+            fn capabilities() -> &'static [XapCapability] {
+                &[XapCapability {
+                    route: &[0x06, 0x01],
+                    bit: 4,
+                    condition: "defined(RGB_MATRIX_ENABLE)",
+                }]
             }
         }
 
-        #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinRead, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct RgbmatrixGetEnabledEffectsResponse(pub u64);
 
         /// ======================================================================
@@ -1355,7 +1580,8 @@ pub mod lighting {
         ///
         /// Query the current config.
         /// ======================================================================
-        #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct RgbmatrixGetConfigRequest(pub ());
 
         impl From<()> for RgbmatrixGetConfigRequest {
@@ -1371,8 +1597,16 @@ pub mod lighting {
                 &[0x06, 0x04, 0x03]
             }
 
-            fn xap_version() -> u32 {
-                0x00000300
+            fn xap_version() -> BcdVersion {
+                BcdVersion::new(0, 3, 0)
+            }
+
+            fn capabilities() -> &'static [XapCapability] {
+                &[XapCapability {
+                    route: &[0x06, 0x01],
+                    bit: 4,
+                    condition: "defined(RGB_MATRIX_ENABLE)",
+                }]
             }
         }
 
@@ -1381,7 +1615,8 @@ pub mod lighting {
         ///
         /// Set the current config.
         /// ======================================================================
-        #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct RgbmatrixSetConfigRequest(pub RgbMatrixConfig);
 
         impl From<RgbMatrixConfig> for RgbmatrixSetConfigRequest {
@@ -1397,8 +1632,16 @@ pub mod lighting {
                 &[0x06, 0x04, 0x04]
             }
 
-            fn xap_version() -> u32 {
-                0x00000300
+            fn xap_version() -> BcdVersion {
+                BcdVersion::new(0, 3, 0)
+            }
+
+            fn capabilities() -> &'static [XapCapability] {
+                &[XapCapability {
+                    route: &[0x06, 0x01],
+                    bit: 4,
+                    condition: "defined(RGB_MATRIX_ENABLE)",
+                }]
             }
         }
 
@@ -1407,7 +1650,8 @@ pub mod lighting {
         ///
         /// Save the current config.
         /// ======================================================================
-        #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+        #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+        #[cfg_attr(feature = "specta", derive(specta::Type))]
         pub struct RgbmatrixSaveConfigRequest(pub ());
 
         impl From<()> for RgbmatrixSaveConfigRequest {
@@ -1423,8 +1667,16 @@ pub mod lighting {
                 &[0x06, 0x04, 0x05]
             }
 
-            fn xap_version() -> u32 {
-                0x00000300
+            fn xap_version() -> BcdVersion {
+                BcdVersion::new(0, 3, 0)
+            }
+
+            fn capabilities() -> &'static [XapCapability] {
+                &[XapCapability {
+                    route: &[0x06, 0x01],
+                    bit: 4,
+                    condition: "defined(RGB_MATRIX_ENABLE)",
+                }]
             }
         }
     }
@@ -1436,18 +1688,19 @@ pub mod audio {
     use binrw::{BinRead, BinWrite};
     use bitflags::bitflags;
     use serde::{Deserialize, Serialize};
-    use specta::Type;
 
-    use crate::xap::spec::types::*;
-    use xap_specs::request::XapRequest;
+    use crate::spec::types::*;
+    use xap_specs::request::{XapCapability, XapRequest};
     use xap_specs::response::UTF8String;
+    use xap_specs::version::BcdVersion;
 
     /// ======================================================================
     ///  capabilities
     ///
     /// Audio subsystem capabilities query. Each bit should be considered as a "usable" route within this subsystem.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct AudioCapabilitiesRequest(pub ());
 
     impl From<()> for AudioCapabilitiesRequest {
@@ -1463,14 +1716,21 @@ pub mod audio {
             &[0x07, 0x01]
         }
 
-        fn xap_version() -> u32 {
-            0x00000300
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 3, 0)
+        }
+
+        fn capabilities() -> &'static [XapCapability] {
+            &[XapCapability {
+                route: &[0x00, 0x02],
+                bit: 7,
+                condition: "defined(AUDIO_ENABLE)",
+            }]
         }
     }
 
-    #[derive(
-        BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize, Type,
-    )]
+    #[derive(BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct AudioCapabilitiesFlags(u32);
 
     bitflags! {
@@ -1488,7 +1748,8 @@ pub mod audio {
     ///
     /// Query the current config.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct AudioGetConfigRequest(pub ());
 
     impl From<()> for AudioGetConfigRequest {
@@ -1504,8 +1765,16 @@ pub mod audio {
             &[0x07, 0x03]
         }
 
-        fn xap_version() -> u32 {
-            0x00000300
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 3, 0)
+        }
+
+        fn capabilities() -> &'static [XapCapability] {
+            &[XapCapability {
+                route: &[0x00, 0x02],
+                bit: 7,
+                condition: "defined(AUDIO_ENABLE)",
+            }]
         }
     }
 
@@ -1514,7 +1783,8 @@ pub mod audio {
     ///
     /// Set the current config.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct AudioSetConfigRequest(pub AudioConfig);
 
     impl From<AudioConfig> for AudioSetConfigRequest {
@@ -1530,8 +1800,16 @@ pub mod audio {
             &[0x07, 0x04]
         }
 
-        fn xap_version() -> u32 {
-            0x00000300
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 3, 0)
+        }
+
+        fn capabilities() -> &'static [XapCapability] {
+            &[XapCapability {
+                route: &[0x00, 0x02],
+                bit: 7,
+                condition: "defined(AUDIO_ENABLE)",
+            }]
         }
     }
 
@@ -1540,7 +1818,8 @@ pub mod audio {
     ///
     /// Save the current config.
     /// ======================================================================
-    #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+    #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct AudioSaveConfigRequest(pub ());
 
     impl From<()> for AudioSaveConfigRequest {
@@ -1556,8 +1835,124 @@ pub mod audio {
             &[0x07, 0x05]
         }
 
-        fn xap_version() -> u32 {
-            0x00000300
+        fn xap_version() -> BcdVersion {
+            BcdVersion::new(0, 3, 0)
+        }
+
+        fn capabilities() -> &'static [XapCapability] {
+            &[XapCapability {
+                route: &[0x00, 0x02],
+                bit: 7,
+                condition: "defined(AUDIO_ENABLE)",
+            }]
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[allow(unused_imports)]
+pub mod broadcast {
+    use binrw::BinRead;
+
+    use crate::spec::types::*;
+    use xap_specs::broadcast::XapBroadcast;
+    use xap_specs::response::UTF8String;
+
+    /// Ids of the broadcast messages, sent after the broadcast token.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(u8)]
+    pub enum BroadcastType {
+        /// Log message
+        LogMessage = 0x00,
+        /// Secure Status
+        SecureStatus = 0x01,
+        /// Keyboard
+        Keyboard = 0x02,
+        /// User
+        User = 0x03,
+    }
+
+    impl BroadcastType {
+        pub fn from_id(id: u8) -> Option<Self> {
+            match id {
+                0x00 => Some(Self::LogMessage),
+                0x01 => Some(Self::SecureStatus),
+                0x02 => Some(Self::Keyboard),
+                0x03 => Some(Self::User),
+                _ => None,
+            }
+        }
+    }
+
+    /// ======================================================================
+    /// Log message
+    ///
+    /// Replicates and replaces the same functionality as if using the standard QMK `CONSOLE_ENABLE = yes` in `rules.mk`. Normal prints within the firmware will manifest as log messages broadcast to the host. `hid_listen` will not be functional with XAP enabled.
+    ///
+    /// Log message payloads include a `u8` signifying the length of the text, followed by the `u8[Length]` containing the text itself.
+    ///
+    /// **Example Log Broadcast** -- log message "Hello QMK!"
+    ///
+    /// | Byte | 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9 | 10 | 11 | 12 | 13 |
+    /// | --- | --- | --- | --- | --- | --- | --- | --- | --- | --- | --- | --- | --- | --- | --- |
+    /// | **Purpose** | Token | Token | Broadcast Type | Length | Payload | Payload | Payload | Payload | Payload | Payload | Payload | Payload | Payload | Payload |
+    /// | **Value** | `0xFF` | `0xFF` | `0x00` | `0x0A`(10) | `0x48`(H) | `0x65`(e) | `0x6C`(l) | `0x6C`(l) | `0x6F`(o) | `0x20`(&nbsp;) | `0x51`(Q) | `0x4D`(M) | `0x4B`(K) | `0x21`(!) |
+    /// ======================================================================
+    #[derive(BinRead, Debug, Clone)]
+    pub struct LogMessageBroadcast(#[br(parse_with = binrw::helpers::until_eof)] pub Vec<u8>);
+
+    impl XapBroadcast for LogMessageBroadcast {
+        fn id() -> u8 {
+            0x00
+        }
+    }
+
+    /// ======================================================================
+    /// Secure Status
+    ///
+    /// Secure status has changed. Payloads include a `u8` matching a 'Secure Status' request.
+    ///
+    /// **Example Secure Status Broadcast** -- secure "Unlocking"
+    ///
+    /// | Byte | 0 | 1 | 2 | 3 |
+    /// | --- | --- | --- | --- | --- |
+    /// | **Purpose** | Token | Token | Broadcast Type | Secure Status |
+    /// | **Value** | `0xFF` | `0xFF` | `0x01` | `0x01` |
+    /// ======================================================================
+    #[derive(BinRead, Debug, Clone)]
+    pub struct SecureStatusBroadcast(pub u8);
+
+    impl XapBroadcast for SecureStatusBroadcast {
+        fn id() -> u8 {
+            0x01
+        }
+    }
+
+    /// ======================================================================
+    /// Keyboard
+    ///
+    /// Reserved for vendor-specific functionality. No messages are defined by XAP.
+    /// ======================================================================
+    #[derive(BinRead, Debug, Clone)]
+    pub struct KeyboardBroadcast(#[br(parse_with = binrw::helpers::until_eof)] pub Vec<u8>);
+
+    impl XapBroadcast for KeyboardBroadcast {
+        fn id() -> u8 {
+            0x02
+        }
+    }
+
+    /// ======================================================================
+    /// User
+    ///
+    /// Reserved for user-specific functionality. No messages are defined by XAP.
+    /// ======================================================================
+    #[derive(BinRead, Debug, Clone)]
+    pub struct UserBroadcast(#[br(parse_with = binrw::helpers::until_eof)] pub Vec<u8>);
+
+    impl XapBroadcast for UserBroadcast {
+        fn id() -> u8 {
+            0x03
         }
     }
 }
@@ -1565,17 +1960,18 @@ pub mod audio {
 pub mod types {
     use binrw::{BinRead, BinWrite};
     use serde::{Deserialize, Serialize};
-    use specta::Type;
 
     /// Config for audio subsystem
-    #[derive(BinRead, BinWrite, Default, Debug, Clone, Serialize, Deserialize, Type)]
+    #[derive(BinRead, BinWrite, Default, Debug, Clone, Serialize, Deserialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct AudioConfig {
         pub enable: u8,
         pub clicky_enable: u8,
     }
 
     /// Config for lighting subsystem
-    #[derive(BinRead, BinWrite, Default, Debug, Clone, Serialize, Deserialize, Type)]
+    #[derive(BinRead, BinWrite, Default, Debug, Clone, Serialize, Deserialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct BacklightConfig {
         pub enable: u8,
         pub mode: u8,
@@ -1583,14 +1979,34 @@ pub mod types {
     }
 
     /// Packet format for broadcast messages.
-    #[derive(BinRead, BinWrite, Default, Debug, Clone, Serialize, Deserialize, Type)]
+    #[derive(BinRead, BinWrite, Default, Debug, Clone, Serialize, Deserialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct BroadcastHeader {
+        pub token: u16,
         pub r#type: u8,
         pub length: u8,
     }
 
+    /// Packet format for inbound data.
+    #[derive(BinRead, BinWrite, Default, Debug, Clone, Serialize, Deserialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
+    pub struct RequestHeader {
+        pub token: u16,
+        pub length: u8,
+    }
+
+    /// Packet format for outbound data.
+    #[derive(BinRead, BinWrite, Default, Debug, Clone, Serialize, Deserialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
+    pub struct ResponseHeader {
+        pub token: u16,
+        pub flags: u8,
+        pub length: u8,
+    }
+
     /// RGB config for RGB lighting subsystem
-    #[derive(BinRead, BinWrite, Default, Debug, Clone, Serialize, Deserialize, Type)]
+    #[derive(BinRead, BinWrite, Default, Debug, Clone, Serialize, Deserialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct RgbLightConfig {
         pub enable: u8,
         pub mode: u8,
@@ -1601,7 +2017,8 @@ pub mod types {
     }
 
     /// RGB config for RGB matrix subsystem
-    #[derive(BinRead, BinWrite, Default, Debug, Clone, Serialize, Deserialize, Type)]
+    #[derive(BinRead, BinWrite, Default, Debug, Clone, Serialize, Deserialize)]
+    #[cfg_attr(feature = "specta", derive(specta::Type))]
     pub struct RgbMatrixConfig {
         pub enable: u8,
         pub mode: u8,
@@ -1612,15 +2029,31 @@ pub mod types {
         pub flags: u8,
     }
 
-    /// Packet format for inbound data.
-    #[derive(BinRead, BinWrite, Default, Debug, Clone, Serialize, Deserialize, Type)]
-    pub struct RequestHeader {
-        pub length: u8,
-    }
+    #[cfg(test)]
+    mod test {
+        use std::io::Cursor;
 
-    /// Packet format for outbound data.
-    #[derive(BinRead, BinWrite, Default, Debug, Clone, Serialize, Deserialize, Type)]
-    pub struct ResponseHeader {
-        pub length: u8,
+        use binrw::{BinWrite, BinWriterExt};
+        use similar_asserts::assert_eq;
+
+        use super::*;
+
+        fn length<T: Default + for<'a> BinWrite<Args<'a> = ()>>() -> usize {
+            let mut writer = Cursor::new(Vec::new());
+            writer.write_le(&T::default()).unwrap();
+            writer.into_inner().len()
+        }
+
+        /// The encoded structs have to be exactly as long as declared in the spec.
+        #[test]
+        fn struct_lengths() {
+            assert_eq!(length::<AudioConfig>(), 2, "AudioConfig");
+            assert_eq!(length::<BacklightConfig>(), 3, "BacklightConfig");
+            assert_eq!(length::<BroadcastHeader>(), 4, "BroadcastHeader");
+            assert_eq!(length::<RequestHeader>(), 3, "RequestHeader");
+            assert_eq!(length::<ResponseHeader>(), 4, "ResponseHeader");
+            assert_eq!(length::<RgbLightConfig>(), 6, "RgbLightConfig");
+            assert_eq!(length::<RgbMatrixConfig>(), 7, "RgbMatrixConfig");
+        }
     }
 }
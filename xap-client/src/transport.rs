@@ -0,0 +1,49 @@
+// Abstraction over the medium that carries XAP reports to and from a device
+
+use std::fmt::{Debug, Display};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Identifies a XAP endpoint independent of the transport that is used to talk to it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct XapDeviceIdentity {
+    pub path: String,
+    pub vendor_id: u16,
+    pub product_id: u16,
+}
+
+impl Display for XapDeviceIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04X}:{:04X} at {}",
+            self.vendor_id, self.product_id, self.path
+        )
+    }
+}
+
+/// A bidirectional, report based connection to a single XAP device.
+pub trait XapTransport: Debug + Send {
+    /// Writes a single XAP report, the report is at most `report_size` bytes long and doesn't
+    /// contain any transport specific framing like the HID report id.
+    fn write_report(&mut self, report: &[u8]) -> Result<()>;
+
+    /// Reads a single XAP report into `report` without blocking. Returns the number of bytes
+    /// read, which is zero if no report is pending.
+    fn read_report(&mut self, report: &mut [u8]) -> Result<usize>;
+
+    /// Size of a single XAP report in bytes.
+    fn report_size(&self) -> usize;
+
+    fn identity(&self) -> &XapDeviceIdentity;
+}
+
+/// A source of XAP devices, e.g. all HID devices with the XAP usage page attached to this host.
+pub trait XapDiscovery: Send {
+    /// Returns the identities of all currently available XAP devices.
+    fn enumerate(&mut self) -> Result<Vec<XapDeviceIdentity>>;
+
+    /// Opens a transport to a device previously returned by `enumerate`.
+    fn open(&mut self, identity: &XapDeviceIdentity) -> Result<Box<dyn XapTransport>>;
+}
@@ -1,11 +1,9 @@
 use std::{
-    collections::{BTreeMap, HashMap},
-    fmt::Display,
-    fs::{self, read_dir, File},
+    fmt::Write as fmtWrite,
+    fs::{self, File},
     io::Write,
     path::{Path, PathBuf},
     process::Command,
-    vec,
 };
 
 use anyhow::{bail, Result};
@@ -13,49 +11,17 @@ use clap::Parser;
 use convert_case::{Case, Casing};
 use env_logger::Env;
 use log::{error, info};
-use serde::{de::Visitor, Deserialize, Deserializer, Serialize};
-
-#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
-pub struct XapVersion(u32);
-
-impl TryFrom<u32> for XapVersion {
-    type Error = anyhow::Error;
-
-    fn try_from(value: u32) -> Result<Self, Self::Error> {
-        match value {
-            0x01 | 0x0100 | 0x0200 | 0x0300 => Ok(Self(value)),
-            _ => Err(anyhow::anyhow!(format!(
-                "{value:06X} is not a valid BCD encoded XAP version"
-            ))),
-        }
-    }
-}
-
-impl Display for XapVersion {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for digit in self.0.to_be_bytes() {
-            write!(f, "{digit:02X}")?;
-        }
-        Ok(())
-    }
-}
+use xap_specs::spec::{
+    BasicType, Route, RouteCapability, RouteType, Spec, ENABLED_SUBSYSTEMS_ROUTE,
+};
+use xap_specs::{docs, lint};
 
-#[derive(Debug, Serialize, Default, Clone, PartialEq)]
-#[serde(rename_all = "lowercase")]
-enum BasicType {
-    #[default]
-    Unit,
-    U8,
-    U16,
-    U32,
-    U64,
-    Struct,
-    String,
-    Array(Box<BasicType>, usize),
-    Predefined(String),
+trait AsRustType {
+    fn as_type(&self) -> String;
+    fn resolve_type(&self, spec: &Spec) -> Result<String>;
 }
 
-impl BasicType {
+impl AsRustType for BasicType {
     fn as_type(&self) -> String {
         match self {
             BasicType::Unit => "()".to_owned(),
@@ -69,124 +35,76 @@ impl BasicType {
             BasicType::Predefined(ty) => ty.to_owned(),
         }
     }
-}
-
-struct BasicTypeVisitor;
 
-impl<'de> Visitor<'de> for BasicTypeVisitor {
-    type Value = BasicType;
-
-    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        formatter.write_str(
-            "a string representing a basic type like u8, u16, u32, u64, string, array or struct",
-        )
-    }
-
-    fn visit_str<E>(self, value: &str) -> std::result::Result<Self::Value, E>
-    where
-        E: serde::de::Error,
-    {
-        if let Some((ty, len)) = value.split_once('[') {
-            let len = len
-                .strip_suffix(']')
-                .ok_or_else(|| E::custom("array type missing closing bracket"))?;
-            let len = len
-                .parse::<usize>()
-                .map_err(|_| E::custom("array type length is not a number"))?;
-
-            return Ok(BasicType::Array(Box::new(self.visit_str(ty)?), len));
-        }
-
-        match value {
-            "u8" => Ok(BasicType::U8),
-            "u16" => Ok(BasicType::U16),
-            "u32" => Ok(BasicType::U32),
-            "u64" => Ok(BasicType::U64),
-            "struct" => Ok(BasicType::Struct),
-            "string" => Ok(BasicType::String),
-            _ => Ok(BasicType::Predefined(value.to_owned())),
-            // _ => Err(E::custom(format!("unknown basic type: {value}"))),
+    /// Like `as_type`, but predefined types are resolved from the type definitions of the spec.
+    /// Structs are referred to by their generated name, all other types by their basic type.
+    fn resolve_type(&self, spec: &Spec) -> Result<String> {
+        match self {
+            BasicType::Array(ty, len) => Ok(format!("[{}; {len}]", ty.resolve_type(spec)?)),
+            BasicType::Predefined(ty) => match spec.type_definitions.get(ty) {
+                Some(definition) if definition.r#type == BasicType::Struct => {
+                    Ok(ty.to_case(Case::Pascal))
+                }
+                Some(definition) => definition.r#type.resolve_type(spec),
+                None => bail!("missing type definition for {ty}"),
+            },
+            _ => Ok(self.as_type()),
         }
     }
 }
 
-impl<'de> Deserialize<'de> for BasicType {
-    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        deserializer.deserialize_str(BasicTypeVisitor)
-    }
-}
-
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct StructMember {
-    name: String,
-    r#type: BasicType,
+fn render_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("0x{byte:02x}"))
+        .collect::<Vec<String>>()
+        .join(", ")
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
-#[serde(rename_all = "lowercase")]
-enum RouteType {
-    #[default]
-    Router,
-    Command,
+fn render_capability(capability: &RouteCapability) -> String {
+    format!(
+        "XapCapability {{ route: &[{}], bit: {}, condition: {:?} }},",
+        render_bytes(&capability.route),
+        capability.bit,
+        capability.condition
+    )
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
-#[serde(default)]
-struct Route {
-    #[serde(skip_deserializing)]
-    id: Vec<u8>,
-    #[serde(skip_deserializing)]
-    xap_version: Option<XapVersion>,
-    name: Option<String>,
-    description: Option<String>,
-    r#type: Option<RouteType>,
-    #[serde(deserialize_with = "deserialize_routes")]
-    routes: BTreeMap<u8, Route>,
-    return_type: BasicType,
-    return_struct_members: Vec<StructMember>,
-    return_purpose: Option<String>,
-    request_type: BasicType,
-    request_struct_members: Vec<StructMember>,
+trait RenderRoute {
+    fn render_id(&self) -> String;
+    fn render_capabilities(&self, ctx: &Context) -> String;
+    fn render_request_type(&self, ctx: &mut Context) -> Result<(String, String)>;
+    fn render_return_type(&self, ctx: &mut Context) -> Result<(String, String)>;
+    fn render_command(&self, ctx: &mut Context) -> Result<String>;
+    fn render_route(&self, ctx: &mut Context) -> Result<()>;
+    fn render(&self, ctx: &mut Context) -> Result<()>;
 }
 
-use std::fmt::Write as fmtWrite;
-
-impl Route {
+impl RenderRoute for Route {
     fn render_id(&self) -> String {
-        let mut rendered = String::new();
+        render_bytes(&self.id)
+    }
 
-        let mut bytes = self.id.iter().peekable();
-        while let Some(byte) = bytes.next() {
-            rendered.push_str(&format!("0x{byte:02x}"));
+    /// Renders the capability bits this route depends on.
+    fn render_capabilities(&self, ctx: &Context) -> String {
+        let capabilities: Vec<String> = ctx
+            .spec
+            .capabilities(&self.id)
+            .iter()
+            .map(render_capability)
+            .collect();
 
-            if bytes.peek().is_some() {
-                rendered.push_str(", ");
-            }
+        if capabilities.is_empty() {
+            return String::new();
         }
 
-        rendered
-    }
-
-    fn merge(&mut self, other: &Route) {
-        if other.name.is_some() {
-            self.name.clone_from(&other.name);
-        }
-        if other.description.is_some() {
-            self.description.clone_from(&other.description);
-        }
-        if other.r#type.is_some() {
-            self.r#type.clone_from(&other.r#type);
-        }
-        for (key, other_route) in &other.routes {
-            if let Some(route) = self.routes.get_mut(key) {
-                route.merge(other_route);
-            } else {
-                self.routes.insert(*key, other_route.clone());
-            }
-        }
+        format!(
+            r#"
+                fn capabilities() -> &'static [XapCapability] {{
+                    &[{}]
+                }}"#,
+            capabilities.join("\n")
+        )
     }
 
     fn render_request_type(&self, ctx: &mut Context) -> Result<(String, String)> {
@@ -198,7 +116,8 @@ impl Route {
 
                 let mut request_struct = format!(
                     r#"
-                    #[derive(BinWrite, Default, Debug, Clone, Serialize, Deserialize, Type)]
+                    #[derive(BinWrite, Default, Debug, Clone, Serialize, Deserialize)]
+                    #[cfg_attr(feature = "specta", derive(specta::Type))]
                     pub struct {request_struct_name} {{
                     "#
                 );
@@ -208,7 +127,7 @@ impl Route {
                         &mut request_struct,
                         "    pub {}: {},",
                         member.name.to_case(Case::Snake),
-                        member.r#type.as_type()
+                        member.r#type.resolve_type(ctx.spec)?
                     )?;
                 }
 
@@ -216,13 +135,7 @@ impl Route {
 
                 (request_struct_name, request_struct)
             }
-            BasicType::Predefined(ty) => {
-                if !ctx.spec.type_definitions.contains_key(ty) {
-                    bail!("missing type definition for request type {ty}");
-                }
-                (ty.to_case(Case::Pascal), String::new())
-            }
-            _ => (self.request_type.as_type(), String::new()),
+            _ => (self.request_type.resolve_type(ctx.spec)?, String::new()),
         };
 
         Ok((request_type_name, request_type))
@@ -231,17 +144,13 @@ impl Route {
     fn render_return_type(&self, ctx: &mut Context) -> Result<(String, String)> {
         let name = format!("{}{}", ctx.current_module(), self.name.as_ref().unwrap());
 
-        if self
-            .return_purpose
-            .as_ref()
-            .is_some_and(|p| p == "capabilities")
-        {
+        if self.is_capabilities() {
             let capabilities_name = format!("{name}Flags", name = name.to_case(Case::Pascal));
             let mut capabilities_enum = String::new();
             let capabilities_type = self.return_type.as_type();
 
             // TODO: Hardcoded check for XAP enabled subsystems route
-            let toplevel = if self.id == [0, 2] {
+            let toplevel = if self.id == ENABLED_SUBSYSTEMS_ROUTE {
                 &ctx.spec.routes
             } else {
                 &ctx.module_path.last().unwrap().routes
@@ -250,7 +159,8 @@ impl Route {
             writeln!(
                 &mut capabilities_enum,
                 r#"
-                #[derive(BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize, Type)]
+                #[derive(BinRead, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Serialize)]
+                #[cfg_attr(feature = "specta", derive(specta::Type))]
                 pub struct {capabilities_name}({capabilities_type});
 
                 bitflags! {{
@@ -273,12 +183,7 @@ impl Route {
 
         let (return_type_name, return_type) = match &self.return_type {
             BasicType::Unit => ("()".to_owned(), String::new()),
-            BasicType::Predefined(ty) => {
-                if !ctx.spec.type_definitions.contains_key(ty) {
-                    bail!("missing type definition for return type {ty}");
-                }
-                (ty.to_case(Case::Pascal), String::new())
-            }
+            BasicType::Predefined(_) => (self.return_type.resolve_type(ctx.spec)?, String::new()),
             _ => {
                 let response_struct_name = format!("{}Response", name.to_case(Case::Pascal));
                 let mut response_struct = String::new();
@@ -287,7 +192,8 @@ impl Route {
                     writeln!(
                         &mut response_struct,
                         r#"
-                        #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
+                        #[derive(BinRead, Default, Debug, Clone, Serialize)]
+                        #[cfg_attr(feature = "specta", derive(specta::Type))]
                         pub struct {response_struct_name} {{
                         "#
                     )?;
@@ -296,18 +202,27 @@ impl Route {
                             &mut response_struct,
                             "    pub {}: {},",
                             member.name.to_case(Case::Snake),
-                            member.r#type.as_type()
+                            member.r#type.resolve_type(ctx.spec)?
                         )?;
                     }
                     writeln!(&mut response_struct, "}}\n")?;
                 } else {
+                    let return_type = if self
+                        .return_purpose
+                        .as_ref()
+                        .is_some_and(|p| p == "bcd-version")
+                    {
+                        "BcdVersion".to_owned()
+                    } else {
+                        self.return_type.as_type()
+                    };
                     writeln!(
                         &mut response_struct,
                         r#"
-                        #[derive(BinRead, Default, Debug, Clone, Serialize, Type)]
-                        pub struct {response_struct_name}(pub {});
-                        "#,
-                        self.return_type.as_type()
+                        #[derive(BinRead, Default, Debug, Clone, Serialize)]
+                        #[cfg_attr(feature = "specta", derive(specta::Type))]
+                        pub struct {response_struct_name}(pub {return_type});
+                        "#
                     )?;
                 }
 
@@ -328,6 +243,20 @@ impl Route {
         let (response_type_name, response_type) = self.render_return_type(ctx)?;
         let id = self.render_id();
         let xap_version = self.xap_version.as_ref().unwrap();
+        let (major, minor, patch) = (
+            xap_version.major(),
+            xap_version.minor(),
+            xap_version.patch(),
+        );
+        let capabilities = self.render_capabilities(ctx);
+        let is_secure = if self.is_secure() {
+            r#"
+                fn is_secure() -> bool {
+                    true
+                }"#
+        } else {
+            ""
+        };
 
         write!(
             &mut ctx.xap_spec_file,
@@ -337,7 +266,8 @@ impl Route {
             ///
             /// {description}
             /// ======================================================================
-            #[derive(BinWrite, Default, Debug, Clone, Serialize, Type)]
+            #[derive(BinWrite, Default, Debug, Clone, Serialize)]
+            #[cfg_attr(feature = "specta", derive(specta::Type))]
             pub struct {name_pascal}Request(pub {request_type_name});
 
             {request_type}
@@ -355,9 +285,11 @@ impl Route {
                     &[{id}]
                 }}
 
-                fn xap_version() -> u32 {{
-                    0x{xap_version}
+                fn xap_version() -> BcdVersion {{
+                    BcdVersion::new({major}, {minor}, {patch})
                 }}
+                {is_secure}
+                {capabilities}
             }}
 
             {response_type}"#
@@ -378,7 +310,6 @@ impl Route {
                             .lock()
                             .unwrap()
                             .query(id, {name_pascal}Request(()))
-                            .map_err(Into::into)
                     }}
                     "#
                 )?;
@@ -398,15 +329,51 @@ impl Route {
                             .lock()
                             .unwrap()
                             .query(id, {name_pascal}Request(arg))
-                            .map_err(Into::into)
                     }}
                     "#
                 )?;
             }
         }
 
-        ctx.commands
-            .push(format!("{}::{name_snake}", ctx.full_module_path()));
+        let module_path = ctx.full_module_path();
+        let route_path = ctx
+            .module_path
+            .iter()
+            .chain([self])
+            .map(|route| route.name.as_ref().unwrap().to_case(Case::Snake))
+            .collect::<Vec<String>>()
+            .join(".");
+        let capabilities = ctx
+            .spec
+            .capabilities(&self.id)
+            .iter()
+            .map(|capability| {
+                render_capability(capability)
+                    .trim_end_matches(',')
+                    .to_owned()
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        writeln!(
+            &mut ctx.registry,
+            r#"    RouteMetadata {{
+        id: &[{id}],
+        module: {module_path:?},
+        path: {route_path:?},
+        name: {name:?},
+        description: {description:?},
+        xap_version: BcdVersion::new({major}, {minor}, {patch}),
+        secure: {secure},
+        request_type: {request_type_name:?},
+        response_type: {response_type_name:?},
+        capabilities: &[{capabilities}],
+    }},"#,
+            name = command_name.trim(),
+            description = self.description.as_deref().unwrap_or_default().trim_end(),
+            secure = self.is_secure()
+        )?;
+
+        ctx.commands.push(format!("{module_path}::{name_snake}"));
 
         Ok(name_snake)
     }
@@ -423,11 +390,11 @@ impl Route {
                 use binrw::{{BinRead, BinWrite}};
                 use bitflags::bitflags;
                 use serde::{{Serialize, Deserialize}};
-                use specta::Type;
 
-                use xap_specs::request::XapRequest;
+                use xap_specs::request::{{XapCapability, XapRequest}};
                 use xap_specs::response::UTF8String;
-                use crate::xap::spec::types::*;
+                use xap_specs::version::BcdVersion;
+                use crate::spec::types::*;
             "#
         )?;
 
@@ -445,10 +412,11 @@ impl Route {
                 use tauri::State;
                 use uuid::Uuid;
 
-                use crate::xap::client::XapClient;
+                use xap_client::XapClient;
+                use xap_client::spec::types::*;
+                use xap_client::spec::{module_path}::*;
+
                 use crate::rpc::spec::error::Error;
-                use crate::xap::spec::types::*;
-                use crate::xap::spec::{module_path}::*;
                 "#
         )?;
 
@@ -489,88 +457,104 @@ impl Route {
         }
         Ok(())
     }
-
-    fn expand_ids(&mut self, parent_id: &mut Vec<u8>) {
-        parent_id.extend_from_slice(&self.id);
-        self.id.clone_from(parent_id);
-
-        for route in self.routes.values_mut() {
-            route.expand_ids(&mut parent_id.clone());
-        }
-    }
-
-    fn expand_xap_specs(&mut self, xap_version: XapVersion) {
-        if self.xap_version.is_none() || self.xap_version > Some(xap_version) {
-            self.xap_version.replace(xap_version);
-        }
-
-        for route in &mut self.routes.values_mut() {
-            route.expand_xap_specs(xap_version);
-        }
-    }
 }
 
-#[derive(Debug, Deserialize, Clone)]
-struct TypeDefinition {
-    name: String,
-    description: String,
-    r#type: BasicType,
-    struct_length: Option<usize>,
-    #[serde(default)]
-    struct_members: Vec<StructMember>,
+trait RenderSpec {
+    fn render_broadcasts(&self, ctx: &mut Context) -> Result<()>;
+    fn render(&self, ctx: &mut Context) -> Result<()>;
 }
 
-#[derive(Debug, Deserialize, Clone)]
-struct Message {
-    name: String,
-    description: String,
-}
+impl RenderSpec for Spec {
+    /// Renders the broadcast type ids and a payload struct for every broadcast message.
+    fn render_broadcasts(&self, ctx: &mut Context) -> Result<()> {
+        let Some(broadcast_messages) = &self.broadcast_messages else {
+            return Ok(());
+        };
 
-#[derive(Debug, Deserialize, Clone)]
-struct BroadcastMessages {
-    #[serde(default)]
-    messages: HashMap<String, Message>,
-}
+        let mut variants = String::new();
+        let mut from_id = String::new();
+        let mut payloads = String::new();
 
-#[derive(Debug, Deserialize, Clone)]
-struct Spec {
-    #[serde(deserialize_with = "deserialize_xap_version")]
-    version: XapVersion,
-    #[serde(default, deserialize_with = "deserialize_routes")]
-    routes: BTreeMap<u8, Route>,
-    broadcast_messages: Option<BroadcastMessages>,
-    #[serde(default)]
-    type_definitions: HashMap<String, TypeDefinition>,
-}
+        for (id, message) in &broadcast_messages.messages {
+            let name = message.name.to_case(Case::Pascal);
+            let description = message.description.trim_end().replace('\n', "\n/// ");
 
-impl Spec {
-    fn merge(&mut self, other: &Spec) {
-        for (id, route) in &other.routes {
-            match self.routes.get_mut(id) {
-                Some(existing_route) => {
-                    existing_route.merge(route);
+            writeln!(
+                &mut variants,
+                "/// {message_name}\n{name} = 0x{id:02x},",
+                message_name = message.name
+            )?;
+            writeln!(&mut from_id, "0x{id:02x} => Some(Self::{name}),")?;
+
+            let payload = match &message.return_type {
+                BasicType::Unit => {
+                    "#[br(parse_with = binrw::helpers::until_eof)] pub Vec<u8>".to_owned()
                 }
-                None => {
-                    self.routes.insert(*id, route.clone());
+                BasicType::Predefined(ty) => {
+                    if !self.type_definitions.contains_key(ty) {
+                        bail!("missing type definition for broadcast type {ty}");
+                    }
+                    format!("pub {}", ty.to_case(Case::Pascal))
                 }
-            }
-        }
+                ty => format!("pub {}", ty.as_type()),
+            };
 
-        for route in &mut self.routes.values_mut() {
-            route.expand_xap_specs(self.version)
-        }
+            write!(
+                &mut payloads,
+                r#"
+                /// ======================================================================
+                /// {message_name}
+                ///
+                /// {description}
+                /// ======================================================================
+                #[derive(BinRead, Debug, Clone)]
+                pub struct {name}Broadcast({payload});
 
-        if let Some(broadcast_messages) = &other.broadcast_messages {
-            if let Some(existing_broadcast_messages) = &mut self.broadcast_messages {
-                existing_broadcast_messages
-                    .messages
-                    .extend(broadcast_messages.messages.clone());
-            } else {
-                self.broadcast_messages = Some(broadcast_messages.clone());
-            }
+                impl XapBroadcast for {name}Broadcast {{
+                    fn id() -> u8 {{
+                        0x{id:02x}
+                    }}
+                }}
+                "#,
+                message_name = message.name
+            )?;
         }
 
-        self.type_definitions.extend(other.type_definitions.clone());
+        writeln!(
+            &mut ctx.xap_spec_file,
+            r#"
+            #[allow(dead_code)]
+            #[allow(unused_imports)]
+            pub mod broadcast {{
+                use binrw::BinRead;
+
+                use xap_specs::broadcast::XapBroadcast;
+                use xap_specs::response::UTF8String;
+                use crate::spec::types::*;
+
+                /// Ids of the broadcast messages, sent after the broadcast token.
+                #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+                #[repr(u8)]
+                pub enum BroadcastType {{
+                {variants}
+                }}
+
+                impl BroadcastType {{
+                    pub fn from_id(id: u8) -> Option<Self> {{
+                        match id {{
+                            {from_id}
+                            _ => None,
+                        }}
+                    }}
+                }}
+                {payloads}
+            }}
+            "#,
+            variants = variants.trim_end(),
+            from_id = from_id.trim_end()
+        )?;
+
+        Ok(())
     }
 
     fn render(&self, ctx: &mut Context) -> Result<()> {
@@ -583,14 +567,7 @@ impl Spec {
             r#"// This file was generated by xap-specs, do not edit manually
 
             pub mod error {{
-                #[derive(Debug, Clone, serde::Deserialize, serde::Serialize, specta::Type)]
-                pub struct Error(pub String);
-
-                impl From<anyhow::Error> for Error {{
-                    fn from(err: anyhow::Error) -> Self {{
-                        Self(err.to_string())
-                    }}
-                }}
+                pub use xap_specs::error::XapError as Error;
             }}
 "#
         )?;
@@ -599,41 +576,48 @@ impl Spec {
             route.render(ctx)?;
         }
 
+        self.render_broadcasts(ctx)?;
+
         writeln!(
             &mut ctx.xap_spec_file,
             "pub mod types {{
                 use binrw::{{BinRead, BinWrite}};
                 use serde::{{Serialize, Deserialize}};
-                use specta::Type;
             "
         )?;
 
-        for (name, ty) in self
-            .type_definitions
-            .iter()
-            .filter(|(_, ty)| ty.r#type == BasicType::Struct)
-        {
+        let mut struct_lengths = String::new();
+
+        // Structs are rendered after the structs they are composed of
+        for (name, ty) in self.struct_definitions()? {
             let name = name.to_case(Case::Pascal);
             let description = ty.description.replace('\n', "\n/// ");
             let members = ty
                 .struct_members
                 .iter()
-                .filter(|member| !matches!(member.r#type, BasicType::Predefined(_)))
                 .map(|member| {
-                    format!(
+                    Ok(format!(
                         "    pub {}: {},",
                         member.name.to_case(Case::Snake).replace("type", "r#type"),
-                        member.r#type.as_type()
-                    )
+                        member.r#type.resolve_type(self)?
+                    ))
                 })
-                .collect::<Vec<String>>()
+                .collect::<Result<Vec<String>>>()?
                 .join("\n");
 
+            if let Some(struct_length) = ty.struct_length {
+                writeln!(
+                    &mut struct_lengths,
+                    "assert_eq!(length::<{name}>(), {struct_length}, \"{name}\");"
+                )?;
+            }
+
             writeln!(
                 &mut ctx.xap_spec_file,
                 r#"
                 /// {description}
-                #[derive(BinRead, BinWrite, Default, Debug, Clone, Serialize, Deserialize, Type)]
+                #[derive(BinRead, BinWrite, Default, Debug, Clone, Serialize, Deserialize)]
+                #[cfg_attr(feature = "specta", derive(specta::Type))]
                 pub struct {name} {{
                 {members}
                 }}
@@ -642,6 +626,37 @@ impl Spec {
                 members = members
             )?;
         }
+
+        // Only specs declaring struct lengths get a test, an empty one would leave `length` unused
+        if !struct_lengths.is_empty() {
+            writeln!(
+                &mut ctx.xap_spec_file,
+                r#"
+            #[cfg(test)]
+            mod test {{
+                use std::io::Cursor;
+
+                use binrw::{{BinWrite, BinWriterExt}};
+                use similar_asserts::assert_eq;
+
+                use super::*;
+
+                fn length<T: Default + for<'a> BinWrite<Args<'a> = ()>>() -> usize {{
+                    let mut writer = Cursor::new(Vec::new());
+                    writer.write_le(&T::default()).unwrap();
+                    writer.into_inner().len()
+                }}
+
+                /// The encoded structs have to be exactly as long as declared in the spec.
+                #[test]
+                fn struct_lengths() {{
+                    {struct_lengths}
+                }}
+            }}
+            "#,
+                struct_lengths = struct_lengths.trim_end()
+            )?;
+        }
         writeln!(&mut ctx.xap_spec_file, "}}")?;
 
         writeln!(
@@ -669,82 +684,14 @@ impl Spec {
 
         Ok(())
     }
-
-    fn from_file(path: impl AsRef<Path>) -> Result<Spec> {
-        let content = fs::read_to_string(path)?;
-
-        let mut spec = deser_hjson::from_str::<Spec>(&content)?;
-
-        for route in &mut spec.routes.values_mut() {
-            route.expand_ids(&mut Vec::new());
-            route.expand_xap_specs(spec.version)
-        }
-
-        Ok(spec)
-    }
-}
-
-fn deserialize_routes<'de, D>(d: D) -> Result<BTreeMap<u8, Route>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let routes: BTreeMap<String, Route> = Deserialize::deserialize(d)?;
-
-    routes
-        .into_iter()
-        .map(|(id, mut route)| {
-            let id = u8::from_str_radix(id.trim_start_matches("0x"), 16)
-                .map_err(serde::de::Error::custom)?;
-
-            route.name = route
-                .name
-                .as_mut()
-                .map(|name| name.replace("query", "").replace("Query", ""));
-
-            if route
-                .return_purpose
-                .as_ref()
-                .is_some_and(|p| p == "capabilities")
-            {
-                route.name = route.name.as_mut().map(|name| {
-                    let mut name = name.replace("capabilities", "").replace("Capabilities", "");
-                    name.push_str("capabilities");
-                    name
-                })
-            }
-            route.id = vec![id];
-            Ok((id, route))
-        })
-        .collect::<Result<BTreeMap<u8, Route>, D::Error>>()
-}
-
-fn deserialize_xap_version<'de, D>(d: D) -> Result<XapVersion, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let raw: String = Deserialize::deserialize(d)?;
-
-    let mut version = [0_u8; 4];
-
-    for (i, digit) in raw.split('.').enumerate().take(3) {
-        version[version.len() - (i + 2)] = digit.parse::<u8>().map_err(|e| {
-            serde::de::Error::custom(format!(
-                "failed to parse digit {digit} in version string {raw} with error {e}"
-            ))
-        })?;
-    }
-
-    XapVersion::try_from(u32::from_le_bytes(version)).map_err(|e| {
-        serde::de::Error::custom(format!(
-            "failed to deserialize XAP version from {raw} with error {e}"
-        ))
-    })
 }
 
 struct Context<'a> {
     xap_spec_file: &'a mut dyn Write,
     tauri_rpc_file: &'a mut dyn Write,
     commands: Vec<String>,
+    /// Route metadata entries of the registry in `xap_specs::metadata`
+    registry: String,
     spec: &'a Spec,
     module_path: Vec<Route>,
 }
@@ -771,13 +718,21 @@ fn get_default_spec_dir() -> &'static str {
 }
 
 fn get_default_xap_spec_file() -> &'static str {
-    concat!(env!("CARGO_MANIFEST_DIR"), "/../src-tauri/src/xap/spec.rs")
+    concat!(env!("CARGO_MANIFEST_DIR"), "/../xap-client/src/spec.rs")
 }
 
 fn get_default_tauri_rpc_file() -> &'static str {
     concat!(env!("CARGO_MANIFEST_DIR"), "/../src-tauri/src/rpc/spec.rs")
 }
 
+fn get_default_docs_dir() -> &'static str {
+    concat!(env!("CARGO_MANIFEST_DIR"), "/../docs/xap")
+}
+
+fn get_default_registry_file() -> &'static str {
+    concat!(env!("CARGO_MANIFEST_DIR"), "/src/metadata/registry.rs")
+}
+
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
@@ -787,8 +742,15 @@ struct Args {
     xap_spec_file: PathBuf,
     #[arg(long, default_value = get_default_tauri_rpc_file())]
     tauri_rpc_file: PathBuf,
+    #[arg(long, default_value = get_default_registry_file())]
+    registry_file: PathBuf,
+    #[arg(long, default_value = get_default_docs_dir())]
+    docs_dir: PathBuf,
     #[arg(long, default_value_t = true)]
     format: bool,
+    /// Only check the specs for mistakes, without generating any code
+    #[arg(long)]
+    check_spec: bool,
 }
 
 fn main() -> Result<()> {
@@ -796,35 +758,21 @@ fn main() -> Result<()> {
 
     let args = Args::parse();
 
-    let mut specs = Vec::new();
-
-    for entry in read_dir(&args.spec_dir)?.filter_map(|entry| entry.ok()) {
-        let path = entry.path();
-
-        if path.is_dir() {
-            continue;
-        }
-
-        if let Some(filename) = path.file_name().map(|name| name.to_string_lossy()) {
-            if !filename.starts_with("xap") || !filename.ends_with(".hjson") {
-                info!("skipping file {filename}");
-                continue;
-            }
-            info!("reading spec {filename}");
-            specs.push(Spec::from_file(path)?);
-        } else {
-            info!("skipping file without filename {path:?}");
-        }
+    // Broken specs would otherwise only show up as generated code that doesn't compile
+    let issues = lint::check_dir(&args.spec_dir)?;
+    for issue in &issues {
+        error!("{issue}");
     }
-
-    // Make sure we process specs in ascending order, as they build upon eachother
-    specs.sort_by(|lhs, rhs| lhs.version.cmp(&rhs.version));
-
-    for i in 1..specs.len() {
-        let spec_lower_version = specs[i - 1].clone();
-        specs[i].merge(&spec_lower_version);
+    if !issues.is_empty() {
+        bail!("found {} issues in the specs", issues.len());
+    }
+    if args.check_spec {
+        info!("specs are valid");
+        return Ok(());
     }
 
+    let specs = Spec::versions_from_dir(&args.spec_dir)?;
+
     // Only render the latest spec as it contains all previous iterations
     if let Some(spec) = specs.last() {
         info!(
@@ -836,11 +784,31 @@ fn main() -> Result<()> {
             xap_spec_file: &mut File::create(&args.xap_spec_file)?,
             tauri_rpc_file: &mut File::create(&args.tauri_rpc_file)?,
             commands: Vec::new(),
+            registry: String::new(),
             module_path: Vec::new(),
             spec,
         };
 
         spec.render(&mut context)?;
+
+        info!(
+            "writing route registry to {}",
+            args.registry_file.to_string_lossy()
+        );
+
+        write!(
+            File::create(&args.registry_file)?,
+            r#"// This file was generated by xap-specs, do not edit manually
+
+use super::RouteMetadata;
+use crate::request::XapCapability;
+use crate::version::BcdVersion;
+
+pub static ROUTES: &[RouteMetadata] = &[
+{}];
+"#,
+            context.registry
+        )?;
     }
 
     if args.format {
@@ -859,7 +827,34 @@ fn main() -> Result<()> {
 
         format_file(&args.xap_spec_file);
         format_file(&args.tauri_rpc_file);
+        format_file(&args.registry_file);
+    }
+
+    // The documentation covers every version, each including all previous iterations
+    info!(
+        "writing documentation to {}",
+        args.docs_dir.to_string_lossy()
+    );
+    fs::create_dir_all(&args.docs_dir)?;
+    for spec in &specs {
+        let name = format!("xap_{}", spec.version);
+        fs::write(
+            args.docs_dir.join(format!("{name}.md")),
+            docs::render_markdown(spec)?,
+        )?;
+        fs::write(
+            args.docs_dir.join(format!("{name}.html")),
+            docs::render_html(spec)?,
+        )?;
     }
+    fs::write(
+        args.docs_dir.join("README.md"),
+        docs::render_index(&specs, "md")?,
+    )?;
+    fs::write(
+        args.docs_dir.join("index.html"),
+        docs::render_index(&specs, "html")?,
+    )?;
 
     Ok(())
 }
@@ -1,28 +1,19 @@
 use core::fmt::Debug;
-use std::io::Cursor;
+use std::{collections::HashMap, io::Cursor};
 
-use anyhow::Result;
-use binrw::{binread, BinRead, BinReaderExt, Endian};
+use binrw::{binread, BinRead};
 use log::trace;
+use serde::Serialize;
 
+use crate::error::XapResult;
 use crate::token::Token;
 use crate::XapSecureStatus;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-#[binread]
-#[br(repr = u8)]
-pub enum BroadcastType {
-    Log = 0,
-    SecureStatus = 1,
-    Keyboard = 2,
-    User = 3,
-}
-
 #[binread]
 #[derive(Debug, Clone)]
 pub struct BroadcastRaw {
     _token: Token,
-    broadcast_type: BroadcastType,
+    broadcast_type: u8,
     #[br(temp)]
     payload_len: u8,
     #[br(count = payload_len as usize)]
@@ -30,18 +21,27 @@ pub struct BroadcastRaw {
 }
 
 impl BroadcastRaw {
-    pub fn broadcast_type(&self) -> &BroadcastType {
-        &self.broadcast_type
+    /// Id of the broadcast message as defined in the `broadcast_messages` of the spec.
+    pub fn broadcast_type(&self) -> u8 {
+        self.broadcast_type
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    pub fn into_payload(self) -> Vec<u8> {
+        self.payload
     }
 
-    pub fn from_raw_report(report: &[u8]) -> Result<Self> {
+    pub fn from_raw_report(report: &[u8]) -> XapResult<Self> {
         let mut reader = Cursor::new(report);
         let broadcast = Self::read_le(&mut reader)?;
         trace!("received raw XAP broadcast: {:#?}", broadcast);
         Ok(broadcast)
     }
 
-    pub fn into_xap_broadcast<T>(self) -> Result<T>
+    pub fn into_xap_broadcast<T>(self) -> XapResult<T>
     where
         T: XapBroadcast,
     {
@@ -50,30 +50,145 @@ impl BroadcastRaw {
     }
 }
 
-pub trait XapBroadcast: Sized + Debug + for<'a> BinRead<Args<'a> = ()> {}
+pub trait XapBroadcast: Sized + Debug + for<'a> BinRead<Args<'a> = ()> {
+    fn id() -> u8;
+}
+
+/// Vendor or user specific broadcast, the payload is only known to the firmware that sent it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub struct CustomBroadcast {
+    pub payload: Vec<u8>,
+    /// Payload as decoded by a registered decoder, `None` if no decoder is registered.
+    pub decoded: Option<serde_json::Value>,
+}
+
+/// Decoded broadcast of any of the XAP broadcast types.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[serde(tag = "kind", content = "data")]
+pub enum XapBroadcastMessage {
+    /// Raw log output, lines and even characters may be split across broadcasts.
+    Log(Vec<u8>),
+    SecureStatus(XapSecureStatus),
+    Keyboard(CustomBroadcast),
+    User(CustomBroadcast),
+}
+
+pub type BroadcastDecoder = Box<dyn Fn(&[u8]) -> XapResult<serde_json::Value> + Send + Sync>;
+
+/// Registry of decoders for the Keyboard and User broadcasts, whose payloads are defined by the
+/// keyboard and keymap firmware instead of the XAP spec.
+#[derive(Default)]
+pub struct BroadcastDecoders {
+    keyboard: HashMap<(u16, u16), BroadcastDecoder>,
+    user: HashMap<Option<(u16, u16)>, BroadcastDecoder>,
+}
+
+impl BroadcastDecoders {
+    /// Registers the decoder for Keyboard broadcasts of the device with the given VID/PID.
+    pub fn register_keyboard(
+        &mut self,
+        vendor_id: u16,
+        product_id: u16,
+        decoder: impl Fn(&[u8]) -> XapResult<serde_json::Value> + Send + Sync + 'static,
+    ) {
+        self.keyboard
+            .insert((vendor_id, product_id), Box::new(decoder));
+    }
+
+    /// Registers the decoder for User broadcasts, either for the device with the given VID/PID or
+    /// as fallback for all devices if `device` is `None`.
+    pub fn register_user(
+        &mut self,
+        device: Option<(u16, u16)>,
+        decoder: impl Fn(&[u8]) -> XapResult<serde_json::Value> + Send + Sync + 'static,
+    ) {
+        self.user.insert(device, Box::new(decoder));
+    }
 
-#[derive(Debug)]
-pub struct LogBroadcast(pub String);
+    /// Decodes the payload of a Keyboard broadcast with the decoder of the device.
+    pub fn decode_keyboard(
+        &self,
+        vendor_id: u16,
+        product_id: u16,
+        payload: Vec<u8>,
+    ) -> XapResult<CustomBroadcast> {
+        Self::decode_custom(self.keyboard.get(&(vendor_id, product_id)), payload)
+    }
 
-impl BinRead for LogBroadcast {
-    type Args<'a> = ();
+    /// Decodes the payload of a User broadcast with the decoder of the device or the fallback.
+    pub fn decode_user(
+        &self,
+        vendor_id: u16,
+        product_id: u16,
+        payload: Vec<u8>,
+    ) -> XapResult<CustomBroadcast> {
+        Self::decode_custom(
+            self.user
+                .get(&Some((vendor_id, product_id)))
+                .or_else(|| self.user.get(&None)),
+            payload,
+        )
+    }
 
-    fn read_options<R: std::io::Read + std::io::Seek>(
-        reader: &mut R,
-        _endian: Endian,
-        _args: Self::Args<'_>,
-    ) -> binrw::BinResult<Self> {
-        let len: u8 = reader.read_le()?;
-        let mut bytes = Vec::with_capacity(len as usize);
-        reader.read_exact(&mut bytes[..len as usize])?;
-        let mut cursor = Cursor::new(&bytes);
-        Ok(Self(std::io::read_to_string(&mut cursor)?))
+    fn decode_custom(
+        decoder: Option<&BroadcastDecoder>,
+        payload: Vec<u8>,
+    ) -> XapResult<CustomBroadcast> {
+        Ok(CustomBroadcast {
+            decoded: decoder.map(|decoder| decoder(&payload)).transpose()?,
+            payload,
+        })
     }
 }
 
-impl XapBroadcast for LogBroadcast {}
+impl Debug for BroadcastDecoders {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BroadcastDecoders")
+            .field("keyboard", &self.keyboard.keys().collect::<Vec<_>>())
+            .field("user", &self.user.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
 
-#[derive(BinRead, Debug)]
-pub struct SecureStatusBroadcast(pub XapSecureStatus);
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use similar_asserts::assert_eq;
 
-impl XapBroadcast for SecureStatusBroadcast {}
+    use super::*;
+    use crate::error::XapError;
+
+    #[test]
+    fn decode_custom_broadcasts() {
+        let mut decoders = BroadcastDecoders::default();
+        decoders.register_keyboard(0xFEED, 0x0001, |payload| Ok(json!({ "layer": payload[0] })));
+        decoders.register_user(None, |payload| Ok(json!(payload.len())));
+        decoders.register_user(Some((0xFEED, 0x0002)), |_| {
+            Err(XapError::Decode("bad user payload".to_owned()))
+        });
+
+        let keyboard = decoders.decode_keyboard(0xFEED, 0x0001, vec![3]).unwrap();
+        assert_eq!(keyboard.decoded, Some(json!({ "layer": 3 })));
+
+        // Keyboard decoders only apply to their own device
+        assert_eq!(
+            decoders.decode_keyboard(0xFEED, 0x0002, vec![3]).unwrap(),
+            CustomBroadcast {
+                payload: vec![3],
+                decoded: None
+            }
+        );
+
+        let user = decoders.decode_user(0xFEED, 0x0001, vec![1, 2]).unwrap();
+        assert_eq!(user.decoded, Some(json!(2)));
+
+        assert_eq!(
+            decoders
+                .decode_user(0xFEED, 0x0002, vec![1, 2])
+                .unwrap_err(),
+            XapError::Decode("bad user payload".to_owned())
+        );
+    }
+}
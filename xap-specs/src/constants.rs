@@ -1,18 +1,28 @@
+pub mod host_layout;
 pub mod keycode;
 pub mod lighting;
 
 use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{anyhow, ensure, Result};
 use serde::Serialize;
-use specta::Type;
 
-use self::keycode::{read_xap_keycodes, KeyCode, XapKeyCodeCategory};
+use self::host_layout::{read_host_layouts, HostLayout};
+use self::keycode::{
+    parse_keycode, read_xap_keycode_ranges, read_xap_keycodes, CompoundKeyCode, KeyCode,
+    KeyCodeDatabase, KeyCodeRange, XapKeyCodeCategory,
+};
 use self::lighting::{read_xap_lighting_effects, LightingEffects};
 
-#[derive(Debug, Clone, Serialize, Type)]
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct XapConstants {
     pub keycodes: Vec<XapKeyCodeCategory>,
+    pub keycode_ranges: Vec<KeyCodeRange>,
+    #[serde(skip)]
+    #[cfg_attr(feature = "specta", specta(skip))]
+    pub keycode_database: KeyCodeDatabase,
+    pub host_layouts: Vec<HostLayout>,
     pub rgblight_modes: LightingEffects,
     pub rgb_matrix_modes: LightingEffects,
     pub led_matrix_modes: LightingEffects,
@@ -20,20 +30,275 @@ pub struct XapConstants {
 
 impl XapConstants {
     pub fn new(specs_path: PathBuf) -> Result<Self> {
+        let keycode_database = read_xap_keycodes(&specs_path)?;
+
         Ok(Self {
-            keycodes: read_xap_keycodes(&specs_path)?,
+            keycodes: keycode_database.categories(),
+            host_layouts: read_host_layouts(&specs_path, &keycode_database)?,
+            keycode_database,
+            keycode_ranges: read_xap_keycode_ranges(&specs_path)?,
             rgblight_modes: read_xap_lighting_effects(&specs_path, "rgblight")?,
             rgb_matrix_modes: read_xap_lighting_effects(&specs_path, "rgb_matrix")?,
             led_matrix_modes: read_xap_lighting_effects(&specs_path, "led_matrix")?,
         })
     }
 
+    /// Looks up a keycode, keycodes that aren't listed are decoded from the range they fall into,
+    /// e.g. `0x4104` into `LT(1, KC_A)`.
     pub fn get_keycode(&self, code: u16) -> KeyCode {
-        for category in &self.keycodes {
-            if let Some(code) = category.codes.iter().find(|keycode| keycode.code == code) {
-                return code.clone();
+        self.get_localized_keycode(code, None)
+    }
+
+    /// Looks up a keycode like [`Self::get_keycode`] with the labels of a host keyboard layout,
+    /// e.g. `german`. Unknown layouts keep the US ANSI labels.
+    pub fn get_localized_keycode(&self, code: u16, layout: Option<&str>) -> KeyCode {
+        let host_layout = layout.and_then(|name| self.host_layout(name));
+
+        if let Some(keycode) = self.keycode_database.get(code) {
+            let mut keycode = keycode.clone();
+            if let Some(host_layout) = host_layout {
+                host_layout.localize(&mut keycode);
             }
+            return keycode;
         }
-        KeyCode::new_custom(code)
+
+        self.keycode_ranges
+            .iter()
+            .find(|range| range.contains(code))
+            .and_then(|range| {
+                CompoundKeyCode::decode(&range.define, code, |basic| {
+                    self.get_localized_keycode(basic, layout)
+                })
+                .map(|compound| KeyCode::new_compound(code, range, compound))
+            })
+            .unwrap_or_else(|| KeyCode::new_custom(code))
+    }
+
+    /// Constants of a keyboard that declares custom keycodes in its info.json. Like QMK they are
+    /// numbered from `QK_KB_0` in the order they are declared and take precedence over the
+    /// keycodes of the specs.
+    pub fn with_custom_keycodes(&self, keycodes: &[KeyCode]) -> Result<Self> {
+        let range = self
+            .keycode_ranges
+            .iter()
+            .find(|range| range.define == "QK_KB")
+            .ok_or_else(|| anyhow!("keycode range QK_KB is not defined"))?;
+        ensure!(
+            keycodes.len() <= usize::from(range.end - range.start) + 1,
+            "keyboard declares {} custom keycodes, QK_KB only has room for {}",
+            keycodes.len(),
+            range.end - range.start + 1
+        );
+
+        let mut constants = self.clone();
+        constants
+            .keycode_database
+            .extend(
+                keycodes
+                    .iter()
+                    .zip(range.start..)
+                    .map(|(keycode, code)| KeyCode {
+                        code,
+                        group: Some(keycode.group.clone().unwrap_or("custom".to_owned())),
+                        ..keycode.clone()
+                    }),
+            );
+        constants.keycodes = constants.keycode_database.categories();
+
+        Ok(constants)
+    }
+
+    pub fn host_layout(&self, name: &str) -> Option<&HostLayout> {
+        self.host_layouts.iter().find(|layout| layout.name == name)
+    }
+
+    /// Finds a keycode by its name or one of its aliases, e.g. `KC_A` or `KC_ENT`.
+    pub fn find_keycode(&self, name: &str) -> Option<&KeyCode> {
+        self.keycode_database.find(name)
+    }
+
+    /// Fuzzy search over the names, aliases and labels of the keycodes, best matches first. The
+    /// keycodes are localized to the given host layout before they are ranked.
+    pub fn search_keycodes(&self, query: &str, limit: usize, layout: Option<&str>) -> Vec<KeyCode> {
+        self.keycode_database
+            .search(query, limit, layout.and_then(|name| self.host_layout(name)))
+    }
+
+    /// Parses a keycode as written in a QMK keymap, e.g. `LT(2, KC_SPC)` or `KC_TRNS`. The
+    /// aliases of all host layouts are accepted as well, e.g. `DE_ODIA`.
+    pub fn parse_keycode(&self, expression: &str) -> Result<u16> {
+        parse_keycode(
+            expression,
+            &|name| {
+                self.find_keycode(name)
+                    .map(|keycode| keycode.code)
+                    .or_else(|| {
+                        self.host_layouts
+                            .iter()
+                            .find_map(|layout| layout.find(name))
+                    })
+            },
+            &self.keycode_ranges,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use similar_asserts::assert_eq;
+
+    use super::*;
+
+    fn constants() -> XapConstants {
+        XapConstants::new(concat!(env!("CARGO_MANIFEST_DIR"), "/assets").into()).unwrap()
+    }
+
+    #[test]
+    fn decode_compound_keycodes() {
+        let constants = constants();
+
+        let layer_tap = constants.get_keycode(0x4104);
+        assert_eq!(layer_tap.key, "LT(1, KC_A)");
+        assert_eq!(layer_tap.label.as_deref(), Some("A / L1"));
+        assert_eq!(layer_tap.group.as_deref(), Some("layer_tap"));
+        assert_eq!(
+            layer_tap.compound,
+            Some(CompoundKeyCode::LayerTap {
+                layer: 1,
+                keycode: Box::new(constants.get_keycode(0x0004))
+            })
+        );
+
+        let mod_tap = constants.get_keycode(0x2204);
+        assert_eq!(mod_tap.key, "MT(MOD_LSFT, KC_A)");
+        assert_eq!(mod_tap.label.as_deref(), Some("A / LSFT"));
+
+        let keys: Vec<String> = [0x0304, 0x1229, 0x5221, 0x5042, 0x52A2, 0x5707]
+            .into_iter()
+            .map(|code| constants.get_keycode(code).key)
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                "LCTL(LSFT(KC_A))",
+                "RSFT(KC_ESCAPE)",
+                "MO(1)",
+                "LM(2, MOD_LSFT)",
+                "OSM(MOD_LSFT)",
+                "TD(7)"
+            ]
+        );
+    }
+
+    #[test]
+    fn listed_keycodes_take_precedence() {
+        let constants = constants();
+
+        assert_eq!(constants.get_keycode(0x56F0).key, "SH_TG");
+        assert_eq!(constants.get_keycode(0x5604).key, "SH_T(KC_A)");
+        assert_eq!(constants.get_keycode(0x7E01).key, "USER-CUSTOM-32257");
+        // Modifier keycodes without any modifier are not valid
+        assert_eq!(constants.get_keycode(0x1004).compound, None);
+    }
+
+    #[test]
+    fn parse_decoded_keycodes() {
+        let constants = constants();
+
+        for code in [
+            0x0004, 0x0304, 0x1229, 0x2204, 0x4104, 0x5042, 0x5203, 0x5221, 0x5245, 0x5262, 0x5281,
+            0x52A2, 0x52C4, 0x5604, 0x5707, 0x56F0,
+        ] {
+            let key = constants.get_keycode(code).key;
+            assert_eq!(constants.parse_keycode(&key).unwrap(), code, "{key}");
+        }
+    }
+
+    #[test]
+    fn search_keycodes() {
+        let constants = constants();
+
+        let keys = |query: &str| -> Vec<String> {
+            constants
+                .search_keycodes(query, 3, None)
+                .into_iter()
+                .map(|keycode| keycode.key)
+                .collect()
+        };
+
+        assert_eq!(keys("a")[0], "KC_A");
+        assert_eq!(keys("a").len(), 3);
+        assert_eq!(keys("ent")[0], "KC_ENTER");
+        assert_eq!(keys("trns")[0], "KC_TRANSPARENT");
+        assert!(keys("").is_empty());
+        assert!(keys("zzzz").is_empty());
+        assert_eq!(constants.find_keycode("_______").unwrap().code, 0x0001);
+    }
+
+    #[test]
+    fn localize_keycodes() {
+        let constants = constants();
+
+        assert_eq!(constants.get_keycode(0x0033).label.as_deref(), Some(";"));
+        assert_eq!(
+            constants
+                .get_localized_keycode(0x0033, Some("german"))
+                .label
+                .as_deref(),
+            Some("Ö")
+        );
+        assert_eq!(
+            constants
+                .get_localized_keycode(0x4133, Some("swedish"))
+                .label
+                .as_deref(),
+            Some("Ö / L1")
+        );
+        assert_eq!(
+            constants
+                .get_localized_keycode(0x0033, Some("klingon"))
+                .label
+                .as_deref(),
+            Some(";")
+        );
+        assert_eq!(constants.parse_keycode("LT(1, DE_ODIA)").unwrap(), 0x4133);
+
+        let german = constants.search_keycodes("DE_ODIA", 1, Some("german"));
+        assert_eq!(german[0].code, 0x0033);
+        assert_eq!(german[0].label.as_deref(), Some("Ö"));
+        assert_eq!(
+            constants.search_keycodes("Ö", 1, Some("german"))[0].code,
+            0x0033
+        );
+        assert!(constants.search_keycodes("DE_ODIA", 1, None).is_empty());
+    }
+
+    #[test]
+    fn merge_custom_keycodes() {
+        let constants = constants();
+        let custom = constants
+            .with_custom_keycodes(&[
+                KeyCode {
+                    key: "CUSTOM_MACRO".to_owned(),
+                    label: Some("Macro".to_owned()),
+                    ..Default::default()
+                },
+                KeyCode {
+                    key: "CUSTOM_RESET".to_owned(),
+                    ..Default::default()
+                },
+            ])
+            .unwrap();
+
+        assert_eq!(constants.get_keycode(0x7E01).key, "USER-CUSTOM-32257");
+        assert_eq!(custom.get_keycode(0x7E00).key, "CUSTOM_MACRO");
+        assert_eq!(custom.get_keycode(0x7E01).key, "CUSTOM_RESET");
+        assert_eq!(custom.parse_keycode("LT(1, KC_A)").unwrap(), 0x4104);
+        assert_eq!(custom.parse_keycode("CUSTOM_RESET").unwrap(), 0x7E01);
+        assert_eq!(custom.find_keycode("SAFE_RANGE"), None);
+        assert!(custom
+            .keycodes
+            .iter()
+            .any(|category| category.name == "custom" && category.codes.len() == 2));
     }
 }
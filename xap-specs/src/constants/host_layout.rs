@@ -0,0 +1,144 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, read_to_string},
+    path::Path,
+};
+
+use anyhow::Result;
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use super::keycode::{KeyCode, KeyCodeDatabase};
+
+/// Labels and aliases of the keycodes on a host keyboard layout, e.g. German QWERTZ. The
+/// tables mirror QMK's `keymap_extras` and are read from the `extras` directory of the specs.
+#[derive(Clone, Serialize, Debug)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub struct HostLayout {
+    pub name: String,
+    pub keys: BTreeMap<u16, HostLayoutKey>,
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub struct HostLayoutKey {
+    /// Alias of the keycode on this layout, e.g. `DE_ODIA`
+    pub key: String,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+impl HostLayout {
+    /// Replaces the label of the keycode with the one of this layout and adds its alias.
+    pub fn localize(&self, keycode: &mut KeyCode) {
+        let Some(key) = self.keys.get(&keycode.code) else {
+            return;
+        };
+        if let Some(label) = &key.label {
+            keycode.label = Some(label.clone());
+        }
+        if !keycode.aliases.contains(&key.key) {
+            keycode.aliases.push(key.key.clone());
+        }
+    }
+
+    /// Finds the code of an alias of this layout, e.g. `DE_ODIA`.
+    pub fn find(&self, name: &str) -> Option<u16> {
+        self.keys
+            .iter()
+            .find(|(_, key)| key.key == name)
+            .map(|(code, _)| *code)
+    }
+}
+
+/// Layout tables are keyed by the US ANSI keycode they are typed with.
+#[derive(Deserialize, Debug)]
+struct RawHostLayout {
+    aliases: HashMap<String, HostLayoutKey>,
+}
+
+/// Reads the `extras/keycodes_<layout>_<version>.hjson` tables, the keys are resolved with the
+/// given keycodes.
+pub(crate) fn read_host_layouts(
+    path: impl AsRef<Path>,
+    keycodes: &KeyCodeDatabase,
+) -> Result<Vec<HostLayout>> {
+    let path = path.as_ref().join("extras");
+    if !path.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut layouts = Vec::new();
+
+    for entry in fs::read_dir(path)?.filter_map(|e| e.ok()) {
+        let path = entry.path();
+
+        let Some(name) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.strip_prefix("keycodes_"))
+            .and_then(|stem| stem.rsplit_once('_'))
+            .map(|(name, _version)| name.to_owned())
+        else {
+            continue;
+        };
+
+        let raw_hjson = read_to_string(&path)?;
+
+        let raw = match deser_hjson::from_str::<RawHostLayout>(&raw_hjson) {
+            Ok(raw) => raw,
+            Err(err) => {
+                error!("failed to deserialize host layout from file {path:?} with error: {err}");
+                continue;
+            }
+        };
+
+        let mut keys = BTreeMap::new();
+        for (base, key) in raw.aliases {
+            match keycodes.find(&base) {
+                Some(keycode) => {
+                    keys.insert(keycode.code, key);
+                }
+                None => error!("unknown keycode {base} in host layout {name}"),
+            }
+        }
+
+        layouts.push(HostLayout { name, keys });
+    }
+
+    layouts.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(layouts)
+}
+
+#[cfg(test)]
+mod test {
+    use similar_asserts::assert_eq;
+
+    use super::*;
+    use crate::constants::keycode::read_xap_keycodes;
+
+    #[test]
+    pub fn read_bundled_layouts() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets");
+        let keycodes = read_xap_keycodes(path).unwrap();
+        let layouts = read_host_layouts(path, &keycodes).unwrap();
+
+        let names: Vec<&str> = layouts.iter().map(|layout| layout.name.as_str()).collect();
+        assert_eq!(names, vec!["french", "german", "swedish"]);
+        // Every key of the tables resolves to a keycode
+        for layout in &layouts {
+            assert_eq!(layout.keys.len(), 48, "{}", layout.name);
+        }
+
+        let german = &layouts[1];
+        let mut keycode = keycodes.find("KC_SCLN").unwrap().clone();
+        german.localize(&mut keycode);
+        assert_eq!(keycode.label.as_deref(), Some("Ö"));
+        assert_eq!(keycode.aliases, vec!["KC_SCLN", "DE_ODIA"]);
+        assert_eq!(
+            german.find("DE_Z"),
+            Some(keycodes.find("KC_Y").unwrap().code)
+        );
+    }
+}
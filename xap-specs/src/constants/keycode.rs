@@ -1,18 +1,21 @@
 use std::{
+    borrow::Cow,
     collections::HashMap,
     fs::{self, read_to_string},
     path::Path,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, ensure, Result};
 use log::error;
 use serde::{de::Error, Deserialize, Deserializer, Serialize};
 use serde_with::{serde_as, skip_serializing_none, NoneAsEmptyString};
-use specta::Type;
+
+use super::host_layout::HostLayout;
 
 #[serde_as]
 #[skip_serializing_none]
-#[derive(Deserialize, Clone, Serialize, Default, Debug, PartialEq, Eq, Type)]
+#[derive(Deserialize, Clone, Serialize, Default, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct KeyCode {
     #[serde(default)]
     pub code: u16,
@@ -24,9 +27,15 @@ pub struct KeyCode {
     pub label: Option<String>,
     #[serde(default)]
     pub aliases: Vec<String>,
+    #[serde(default)]
+    // `None` is never serialized, exporting the field as optional instead of nullable also keeps
+    // specta from recursing endlessly through the keycode of the compound keycode.
+    #[cfg_attr(feature = "specta", specta(type = CompoundKeyCode))]
+    pub compound: Option<CompoundKeyCode>,
 }
 
-#[derive(Debug, Serialize, Clone, Type)]
+#[derive(Debug, Serialize, Clone)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct XapKeyCodeCategory {
     pub name: String,
     pub codes: Vec<KeyCode>,
@@ -40,18 +49,574 @@ impl KeyCode {
             group: Some("USER-CUSTOM".to_owned()),
             label: Some(format!("{code}")),
             aliases: vec![],
+            compound: None,
+        }
+    }
+
+    pub fn new_compound(code: u16, range: &KeyCodeRange, compound: CompoundKeyCode) -> Self {
+        Self {
+            code,
+            key: compound.key(),
+            group: Some(range.define.trim_start_matches("QK_").to_lowercase()),
+            label: Some(compound.label()),
+            aliases: vec![],
+            compound: Some(compound),
+        }
+    }
+
+    fn label_or_key(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.key)
+    }
+}
+
+/// Keycodes of the specs indexed by their code and by their name and aliases.
+#[derive(Debug, Clone, Default)]
+pub struct KeyCodeDatabase {
+    codes: HashMap<u16, KeyCode>,
+    names: HashMap<String, u16>,
+}
+
+impl KeyCodeDatabase {
+    pub fn new(keycodes: impl IntoIterator<Item = KeyCode>) -> Self {
+        let mut database = Self::default();
+        database.extend(keycodes);
+        database
+    }
+
+    /// Adds keycodes to the database, keycodes with the same code are replaced.
+    pub fn extend(&mut self, keycodes: impl IntoIterator<Item = KeyCode>) {
+        for keycode in keycodes {
+            if self.codes.contains_key(&keycode.code) {
+                self.names.retain(|_, code| *code != keycode.code);
+            }
+            for name in std::iter::once(&keycode.key).chain(&keycode.aliases) {
+                self.names.insert(name.clone(), keycode.code);
+            }
+            self.codes.insert(keycode.code, keycode);
+        }
+    }
+
+    pub fn get(&self, code: u16) -> Option<&KeyCode> {
+        self.codes.get(&code)
+    }
+
+    /// Finds a keycode by its name or one of its aliases, e.g. `KC_A` or `KC_ENT`.
+    pub fn find(&self, name: &str) -> Option<&KeyCode> {
+        self.names.get(name).and_then(|code| self.codes.get(code))
+    }
+
+    pub fn len(&self) -> usize {
+        self.codes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    /// Keycodes grouped by their `group`, sorted by name and code.
+    pub fn categories(&self) -> Vec<XapKeyCodeCategory> {
+        let mut categories = self
+            .codes
+            .values()
+            .fold(HashMap::new(), |mut category, keycode| {
+                category
+                    .entry(keycode.group.clone().unwrap_or("other".to_owned()))
+                    .or_insert(Vec::new())
+                    .push(keycode.clone());
+
+                category
+            })
+            .into_iter()
+            .map(|(name, mut codes)| {
+                codes.sort_by_key(|code| code.code);
+                XapKeyCodeCategory { name, codes }
+            })
+            .collect::<Vec<XapKeyCodeCategory>>();
+        categories.sort_by(|a, b| a.name.cmp(&b.name));
+
+        categories
+    }
+
+    /// Searches the names, aliases and labels of all keycodes, the best matches come first.
+    /// Exact matches rank before prefixes and substrings, names that merely contain the
+    /// characters of the query in order rank last. With a host layout the keycodes are localized
+    /// first, so its aliases and labels are found as well, e.g. `DE_ODIA` or `Ö`.
+    pub fn search(
+        &self,
+        query: &str,
+        limit: usize,
+        host_layout: Option<&HostLayout>,
+    ) -> Vec<KeyCode> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut matches: Vec<(usize, Cow<KeyCode>)> = self
+            .codes
+            .values()
+            .map(|keycode| match host_layout {
+                Some(host_layout) if host_layout.keys.contains_key(&keycode.code) => {
+                    let mut keycode = keycode.clone();
+                    host_layout.localize(&mut keycode);
+                    Cow::Owned(keycode)
+                }
+                _ => Cow::Borrowed(keycode),
+            })
+            .filter_map(|keycode| {
+                std::iter::once(&keycode.key)
+                    .chain(&keycode.aliases)
+                    .chain(&keycode.label)
+                    .filter_map(|name| match_rank(&query, name))
+                    .min()
+                    .map(|rank| (rank, keycode))
+            })
+            .collect();
+        matches.sort_by_key(|(rank, keycode)| (*rank, keycode.code));
+
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(_, keycode)| keycode.into_owned())
+            .collect()
+    }
+}
+
+/// Ranks how well a name matches the lowercase query, lower is better. The `KC_` and `QK_`
+/// prefixes are optional, e.g. `ent` is an exact match for `KC_ENT`.
+fn match_rank(query: &str, name: &str) -> Option<usize> {
+    let name = name.to_lowercase();
+    let short = name
+        .strip_prefix("kc_")
+        .or_else(|| name.strip_prefix("qk_"))
+        .unwrap_or(&name);
+
+    if name == query || short == query {
+        return Some(0);
+    }
+    if short.starts_with(query) || name.starts_with(query) {
+        return Some(1);
+    }
+    if name.contains(query) {
+        return Some(2);
+    }
+
+    // Every skipped character between the characters of the query lowers the rank
+    let mut rest = name.as_str();
+    let mut gaps = 0;
+    for query_char in query.chars() {
+        let pos = rest.find(query_char)?;
+        gaps += rest[..pos].chars().count();
+        rest = &rest[pos + query_char.len_utf8()..];
+    }
+    Some(3 + gaps)
+}
+
+/// Range of keycodes from the `ranges` table of the keycode specs, e.g. `QK_LAYER_TAP`.
+#[derive(Deserialize, Clone, Serialize, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub struct KeyCodeRange {
+    #[serde(default)]
+    pub start: u16,
+    /// Last keycode of the range, inclusive
+    #[serde(default)]
+    pub end: u16,
+    pub define: String,
+}
+
+impl KeyCodeRange {
+    pub fn contains(&self, code: u16) -> bool {
+        (self.start..=self.end).contains(&code)
+    }
+}
+
+/// Keycode that is composed of a basic keycode, modifiers or a layer, e.g. `LT(1, KC_A)`.
+/// Modifiers are in the 5-bit format of QMK, bit 4 selects the right hand modifiers.
+#[derive(Deserialize, Clone, Serialize, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CompoundKeyCode {
+    Mods { mods: u8, keycode: Box<KeyCode> },
+    ModTap { mods: u8, keycode: Box<KeyCode> },
+    LayerTap { layer: u8, keycode: Box<KeyCode> },
+    LayerMod { layer: u8, mods: u8 },
+    To { layer: u8 },
+    Momentary { layer: u8 },
+    DefaultLayer { layer: u8 },
+    ToggleLayer { layer: u8 },
+    OneShotLayer { layer: u8 },
+    OneShotMod { mods: u8 },
+    LayerTapToggle { layer: u8 },
+    SwapHandsTap { keycode: Box<KeyCode> },
+    TapDance { index: u8 },
+}
+
+const MOD_NAMES: [&str; 4] = ["CTL", "SFT", "ALT", "GUI"];
+
+/// Shorthands QMK defines for single and combined modifiers, they are used as modifier functions,
+/// with a `_T` suffix for mod-taps and with a `MOD_` prefix.
+const MOD_ALIASES: [(&str, u8); 38] = [
+    ("C", 0x01),
+    ("CTL", 0x01),
+    ("S", 0x02),
+    ("SFT", 0x02),
+    ("A", 0x04),
+    ("ALT", 0x04),
+    ("OPT", 0x04),
+    ("LOPT", 0x04),
+    ("G", 0x08),
+    ("GUI", 0x08),
+    ("CMD", 0x08),
+    ("LCMD", 0x08),
+    ("WIN", 0x08),
+    ("LWIN", 0x08),
+    ("ALGR", 0x14),
+    ("ROPT", 0x14),
+    ("RCMD", 0x18),
+    ("RWIN", 0x18),
+    ("C_S", 0x03),
+    ("LCS", 0x03),
+    ("LCA", 0x05),
+    ("LSA", 0x06),
+    ("MEH", 0x07),
+    ("SGUI", 0x0A),
+    ("SCMD", 0x0A),
+    ("SWIN", 0x0A),
+    ("LSG", 0x0A),
+    ("LAG", 0x0C),
+    ("LCAG", 0x0D),
+    ("HYPR", 0x0F),
+    ("ALL", 0x0F),
+    ("RCS", 0x13),
+    ("RCA", 0x15),
+    ("RSA", 0x16),
+    ("SAGR", 0x16),
+    ("RSG", 0x1A),
+    ("RAG", 0x1C),
+    ("RCAG", 0x1D),
+];
+
+impl CompoundKeyCode {
+    /// Decodes a keycode of the range with the given define, `basic` looks up the basic
+    /// keycodes that are part of it. Returns `None` for ranges without compound keycodes.
+    pub fn decode(define: &str, code: u16, basic: impl Fn(u16) -> KeyCode) -> Option<Self> {
+        let keycode = || Box::new(basic(code & 0xFF));
+        let mods = ((code >> 8) & 0x1F) as u8;
+        let layer = (code & 0x1F) as u8;
+
+        let compound = match define {
+            "QK_MODS" => Self::Mods {
+                mods,
+                keycode: keycode(),
+            },
+            "QK_MOD_TAP" => Self::ModTap {
+                mods,
+                keycode: keycode(),
+            },
+            "QK_LAYER_TAP" => Self::LayerTap {
+                layer: ((code >> 8) & 0x0F) as u8,
+                keycode: keycode(),
+            },
+            "QK_LAYER_MOD" => Self::LayerMod {
+                layer: ((code >> 5) & 0x0F) as u8,
+                mods: (code & 0x1F) as u8,
+            },
+            "QK_TO" => Self::To { layer },
+            "QK_MOMENTARY" => Self::Momentary { layer },
+            "QK_DEF_LAYER" => Self::DefaultLayer { layer },
+            "QK_TOGGLE_LAYER" => Self::ToggleLayer { layer },
+            "QK_ONE_SHOT_LAYER" => Self::OneShotLayer { layer },
+            "QK_ONE_SHOT_MOD" => Self::OneShotMod {
+                mods: (code & 0x1F) as u8,
+            },
+            "QK_LAYER_TAP_TOGGLE" => Self::LayerTapToggle { layer },
+            "QK_SWAP_HANDS" => Self::SwapHandsTap { keycode: keycode() },
+            "QK_TAP_DANCE" => Self::TapDance {
+                index: (code & 0xFF) as u8,
+            },
+            _ => return None,
+        };
+
+        match compound {
+            Self::Mods { mods, .. }
+            | Self::ModTap { mods, .. }
+            | Self::LayerMod { mods, .. }
+            | Self::OneShotMod { mods }
+                if mods & 0x0F == 0 =>
+            {
+                None
+            }
+            compound => Some(compound),
+        }
+    }
+
+    /// Name of the keycode as written in a QMK keymap, e.g. `MT(MOD_LSFT, KC_A)`.
+    pub fn key(&self) -> String {
+        match self {
+            Self::Mods { mods, keycode } => mod_names(*mods)
+                .iter()
+                .rev()
+                .fold(keycode.key.clone(), |key, name| format!("{name}({key})")),
+            Self::ModTap { mods, keycode } => {
+                format!("MT({}, {})", mod_defines(*mods), keycode.key)
+            }
+            Self::LayerTap { layer, keycode } => format!("LT({layer}, {})", keycode.key),
+            Self::LayerMod { layer, mods } => format!("LM({layer}, {})", mod_defines(*mods)),
+            Self::To { layer } => format!("TO({layer})"),
+            Self::Momentary { layer } => format!("MO({layer})"),
+            Self::DefaultLayer { layer } => format!("DF({layer})"),
+            Self::ToggleLayer { layer } => format!("TG({layer})"),
+            Self::OneShotLayer { layer } => format!("OSL({layer})"),
+            Self::OneShotMod { mods } => format!("OSM({})", mod_defines(*mods)),
+            Self::LayerTapToggle { layer } => format!("TT({layer})"),
+            Self::SwapHandsTap { keycode } => format!("SH_T({})", keycode.key),
+            Self::TapDance { index } => format!("TD({index})"),
+        }
+    }
+
+    /// Short label for a keycap, dual function keys show the tap action first, e.g. `A / LSFT`.
+    pub fn label(&self) -> String {
+        match self {
+            Self::Mods { mods, keycode } => {
+                let mut parts = mod_names(*mods);
+                parts.push(keycode.label_or_key().to_owned());
+                parts.join("+")
+            }
+            Self::ModTap { mods, keycode } => {
+                format!(
+                    "{} / {}",
+                    keycode.label_or_key(),
+                    mod_names(*mods).join("+")
+                )
+            }
+            Self::LayerTap { layer, keycode } => format!("{} / L{layer}", keycode.label_or_key()),
+            Self::LayerMod { layer, mods } => format!("L{layer}+{}", mod_names(*mods).join("+")),
+            Self::To { layer } => format!("TO {layer}"),
+            Self::Momentary { layer } => format!("MO {layer}"),
+            Self::DefaultLayer { layer } => format!("DF {layer}"),
+            Self::ToggleLayer { layer } => format!("TG {layer}"),
+            Self::OneShotLayer { layer } => format!("OSL {layer}"),
+            Self::OneShotMod { mods } => format!("OSM {}", mod_names(*mods).join("+")),
+            Self::LayerTapToggle { layer } => format!("TT {layer}"),
+            Self::SwapHandsTap { keycode } => format!("{} / SH", keycode.label_or_key()),
+            Self::TapDance { index } => format!("TD {index}"),
+        }
+    }
+}
+
+/// Names of the modifiers, e.g. `LCTL` and `LSFT` for `0x03`.
+fn mod_names(mods: u8) -> Vec<String> {
+    let hand = if mods & 0x10 != 0 { 'R' } else { 'L' };
+    MOD_NAMES
+        .iter()
+        .enumerate()
+        .filter(|(bit, _)| mods & (1 << bit) != 0)
+        .map(|(_, name)| format!("{hand}{name}"))
+        .collect()
+}
+
+fn mod_defines(mods: u8) -> String {
+    mod_names(mods)
+        .iter()
+        .map(|name| format!("MOD_{name}"))
+        .collect::<Vec<String>>()
+        .join(" | ")
+}
+
+/// Parses a keycode as written in a QMK keymap into its code, e.g. `LT(2, KC_SPC)`,
+/// `MT(MOD_LCTL | MOD_LSFT, KC_A)` or `KC_TRNS`. Numbers are accepted as well, either decimal or
+/// hex with a `0x` prefix. `find` looks up keycodes by their name or one of their aliases,
+/// compound keycodes are encoded into the range of `ranges` they belong to.
+pub fn parse_keycode(
+    expression: &str,
+    find: &dyn Fn(&str) -> Option<u16>,
+    ranges: &[KeyCodeRange],
+) -> Result<u16> {
+    let expression = expression.trim();
+    if let Some(code) = parse_number(expression) {
+        return Ok(code);
+    }
+
+    let Some((name, args)) = expression.split_once('(') else {
+        return find(expression).ok_or_else(|| anyhow!("unknown keycode {expression}"));
+    };
+    let args = args
+        .strip_suffix(')')
+        .ok_or_else(|| anyhow!("missing closing parenthesis in {expression}"))?;
+    let args = split_args(args)?;
+
+    let encode = |define: &str, bits: u16| -> Result<u16> {
+        let range = ranges
+            .iter()
+            .find(|range| range.define == define)
+            .ok_or_else(|| anyhow!("keycode range {define} is not defined"))?;
+        let code = range.start | bits;
+        ensure!(
+            range.contains(code),
+            "{expression} is out of the range of {define}"
+        );
+        Ok(code)
+    };
+    let basic = |expression: &str| -> Result<u16> {
+        let code = parse_keycode(expression, find, ranges)?;
+        ensure!(code <= 0xFF, "{expression} is not a basic keycode");
+        Ok(code)
+    };
+
+    match (name.trim(), &args[..]) {
+        ("MT", [mods, keycode]) => encode(
+            "QK_MOD_TAP",
+            u16::from(parse_mods(mods)?) << 8 | basic(keycode)?,
+        ),
+        ("LT", [layer, keycode]) => encode(
+            "QK_LAYER_TAP",
+            parse_index(layer, 0x0F)? << 8 | basic(keycode)?,
+        ),
+        ("LM", [layer, mods]) => encode(
+            "QK_LAYER_MOD",
+            parse_index(layer, 0x0F)? << 5 | u16::from(parse_mods(mods)?),
+        ),
+        ("TO", [layer]) => encode("QK_TO", parse_index(layer, 0x1F)?),
+        ("MO", [layer]) => encode("QK_MOMENTARY", parse_index(layer, 0x1F)?),
+        ("DF", [layer]) => encode("QK_DEF_LAYER", parse_index(layer, 0x1F)?),
+        ("TG", [layer]) => encode("QK_TOGGLE_LAYER", parse_index(layer, 0x1F)?),
+        ("OSL", [layer]) => encode("QK_ONE_SHOT_LAYER", parse_index(layer, 0x1F)?),
+        ("OSM", [mods]) => encode("QK_ONE_SHOT_MOD", u16::from(parse_mods(mods)?)),
+        ("TT", [layer]) => encode("QK_LAYER_TAP_TOGGLE", parse_index(layer, 0x1F)?),
+        ("SH_T", [keycode]) => encode("QK_SWAP_HANDS", basic(keycode)?),
+        ("TD", [index]) => encode("QK_TAP_DANCE", parse_index(index, 0xFF)?),
+        (name, [keycode]) => {
+            if let Some(mods) = name.strip_suffix("_T").and_then(mod_bits) {
+                encode("QK_MOD_TAP", u16::from(mods) << 8 | basic(keycode)?)
+            } else if let Some(mods) = mod_bits(name) {
+                // Modifier functions nest, e.g. `LCTL(LSFT(KC_A))`
+                let code = u16::from(mods) << 8 | parse_keycode(keycode, find, ranges)?;
+                let range = ranges
+                    .iter()
+                    .find(|range| range.define == "QK_MODS")
+                    .ok_or_else(|| anyhow!("keycode range QK_MODS is not defined"))?;
+                ensure!(
+                    range.contains(code),
+                    "{expression} is not a valid modifier keycode"
+                );
+                Ok(code)
+            } else {
+                bail!("unknown keycode function {name} in {expression}")
+            }
+        }
+        (name, args) => bail!(
+            "unknown keycode function {name} with {} arguments in {expression}",
+            args.len()
+        ),
+    }
+}
+
+fn parse_number(expression: &str) -> Option<u16> {
+    match expression.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => expression.parse().ok(),
+    }
+}
+
+fn parse_index(expression: &str, max: u16) -> Result<u16> {
+    let index = parse_number(expression.trim())
+        .ok_or_else(|| anyhow!("invalid number {}", expression.trim()))?;
+    ensure!(
+        index <= max,
+        "{index} is out of range, at most {max} is allowed"
+    );
+    Ok(index)
+}
+
+/// Parses modifiers joined by `|`, e.g. `MOD_LCTL | MOD_LSFT`.
+fn parse_mods(expression: &str) -> Result<u8> {
+    let mods = expression.split('|').try_fold(0, |mods, name| {
+        let name = name.trim();
+        name.strip_prefix("MOD_")
+            .and_then(mod_bits)
+            .map(u16::from)
+            .or_else(|| parse_number(name))
+            .map(|bits| mods | bits)
+            .ok_or_else(|| anyhow!("unknown modifier {name}"))
+    })?;
+    ensure!(
+        mods <= 0x1F && mods & 0x0F != 0,
+        "invalid modifiers {expression}"
+    );
+    Ok(mods as u8)
+}
+
+/// Bits of a modifier name, e.g. `LSFT`, `RALT` or `MEH`.
+fn mod_bits(name: &str) -> Option<u8> {
+    if let Some((_, bits)) = MOD_ALIASES.iter().find(|(alias, _)| *alias == name) {
+        return Some(*bits);
+    }
+    let hand = match name.get(..1)? {
+        "L" => 0x00,
+        "R" => 0x10,
+        _ => return None,
+    };
+    MOD_NAMES
+        .iter()
+        .position(|mod_name| *mod_name == &name[1..])
+        .map(|bit| hand | 1 << bit)
+}
+
+/// Splits the arguments of a keycode function at the commas that aren't part of nested calls.
+fn split_args(args: &str) -> Result<Vec<&str>> {
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut split = Vec::new();
+
+    for (pos, char) in args.char_indices() {
+        match char {
+            '(' => depth += 1,
+            ')' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or_else(|| anyhow!("unbalanced parenthesis in {args}"))?
+            }
+            ',' if depth == 0 => {
+                split.push(args[start..pos].trim());
+                start = pos + 1;
+            }
+            _ => {}
         }
     }
+    ensure!(depth == 0, "unbalanced parenthesis in {args}");
+    split.push(args[start..].trim());
+
+    Ok(split)
 }
 
 #[derive(Deserialize, Debug)]
 struct KeyCodes {
     #[serde(deserialize_with = "xap_keycode_from_hex_map")]
     keycodes: HashMap<u16, KeyCode>,
+    #[serde(default, deserialize_with = "xap_keycode_ranges_from_hex_map")]
+    ranges: Vec<KeyCodeRange>,
+}
+
+pub(crate) fn read_xap_keycodes(path: impl AsRef<Path>) -> Result<KeyCodeDatabase> {
+    Ok(KeyCodeDatabase::new(
+        read_keycode_files(path)?
+            .into_iter()
+            .flat_map(|codes| codes.keycodes.into_values()),
+    ))
 }
 
-pub(crate) fn read_xap_keycodes(path: impl AsRef<Path>) -> Result<Vec<XapKeyCodeCategory>> {
-    let mut all = HashMap::new();
+pub(crate) fn read_xap_keycode_ranges(path: impl AsRef<Path>) -> Result<Vec<KeyCodeRange>> {
+    let mut ranges: Vec<KeyCodeRange> = read_keycode_files(path)?
+        .into_iter()
+        .flat_map(|codes| codes.ranges)
+        .collect();
+    ranges.sort_by_key(|range| range.start);
+
+    Ok(ranges)
+}
+
+fn read_keycode_files(path: impl AsRef<Path>) -> Result<Vec<KeyCodes>> {
+    let mut all = Vec::new();
 
     for entry in fs::read_dir(path)?.filter_map(|e| e.ok()) {
         let path = entry.path();
@@ -68,7 +633,7 @@ pub(crate) fn read_xap_keycodes(path: impl AsRef<Path>) -> Result<Vec<XapKeyCode
 
         match deser_hjson::from_str::<KeyCodes>(&raw_hjson) {
             Ok(codes) => {
-                all.extend(codes.keycodes);
+                all.push(codes);
             }
             Err(err) => {
                 error!("failed to deserialize keycodes from file {path:?} with error: {err}",);
@@ -76,26 +641,7 @@ pub(crate) fn read_xap_keycodes(path: impl AsRef<Path>) -> Result<Vec<XapKeyCode
         }
     }
 
-    let keycodes = all
-        .into_iter()
-        .fold(HashMap::new(), |mut category, (_, keycode)| {
-            category
-                .entry(keycode.group.clone().unwrap_or("other".to_owned()))
-                .or_insert(Vec::new())
-                .push(keycode);
-
-            category
-        });
-
-    let keycodes = keycodes
-        .into_iter()
-        .map(|(name, mut codes)| {
-            codes.sort_by_key(|code| code.code);
-            XapKeyCodeCategory { name, codes }
-        })
-        .collect();
-
-    Ok(keycodes)
+    Ok(all)
 }
 
 fn xap_keycode_from_hex_map<'de, D>(deserializer: D) -> Result<HashMap<u16, KeyCode>, D::Error>
@@ -114,6 +660,25 @@ where
         .ok_or(D::Error::custom("failed to parse keycode table"))
 }
 
+/// Ranges are keyed by their first keycode and size minus one, e.g. `0x4000/0x0FFF`.
+fn xap_keycode_ranges_from_hex_map<'de, D>(deserializer: D) -> Result<Vec<KeyCodeRange>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let map: HashMap<String, KeyCodeRange> = Deserialize::deserialize(deserializer)?;
+
+    map.into_iter()
+        .map(|(raw_range, mut range)| {
+            let parse = |raw: &str| u16::from_str_radix(raw.trim_start_matches("0x"), 16).ok();
+            let (start, size) = raw_range.split_once('/')?;
+            range.start = parse(start)?;
+            range.end = range.start.checked_add(parse(size)?)?;
+            Some(range)
+        })
+        .collect::<Option<Vec<KeyCodeRange>>>()
+        .ok_or(D::Error::custom("failed to parse keycode ranges"))
+}
+
 #[cfg(test)]
 mod test {
     use similar_asserts::assert_eq;
@@ -165,7 +730,8 @@ mod test {
                 group: Some("internal".to_owned()),
                 key: "KC_NO".to_owned(),
                 label: None,
-                aliases: vec!["XXXXXXX".to_owned()]
+                aliases: vec!["XXXXXXX".to_owned()],
+                compound: None
             }
         );
 
@@ -176,7 +742,8 @@ mod test {
                 group: Some("internal".to_owned()),
                 key: "KC_TRANSPARENT".to_owned(),
                 label: None,
-                aliases: vec!["_______".to_owned(), "KC_TRNS".to_owned()]
+                aliases: vec!["_______".to_owned(), "KC_TRNS".to_owned()],
+                compound: None
             }
         );
 
@@ -187,7 +754,8 @@ mod test {
                 group: Some("basic".to_owned()),
                 key: "KC_A".to_owned(),
                 label: Some("A".to_owned()),
-                aliases: vec![]
+                aliases: vec![],
+                compound: None
             }
         );
 
@@ -198,8 +766,155 @@ mod test {
                 group: Some("basic".to_owned()),
                 key: "KC_B".to_owned(),
                 label: Some("B".to_owned()),
-                aliases: vec![]
+                aliases: vec![],
+                compound: None
             }
         );
     }
+
+    #[test]
+    pub fn deserialize_ranges() {
+        let input = r#"{
+            "ranges": {
+                "0x4000/0x0FFF": {
+                    "define": "QK_LAYER_TAP"
+                },
+                "0x8000/0x7FFF": {
+                    "define": "QK_UNICODE"
+                }
+            },
+            "keycodes": {}
+        }"#;
+
+        let mut codes: KeyCodes = deser_hjson::from_str(input).expect("deserialization failed");
+        codes.ranges.sort_by_key(|range| range.start);
+
+        assert_eq!(
+            codes.ranges,
+            vec![
+                KeyCodeRange {
+                    start: 0x4000,
+                    end: 0x4FFF,
+                    define: "QK_LAYER_TAP".to_owned()
+                },
+                KeyCodeRange {
+                    start: 0x8000,
+                    end: 0xFFFF,
+                    define: "QK_UNICODE".to_owned()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    pub fn parse_expressions() {
+        let ranges: Vec<KeyCodeRange> = [
+            (0x0100, 0x1FFF, "QK_MODS"),
+            (0x2000, 0x3FFF, "QK_MOD_TAP"),
+            (0x4000, 0x4FFF, "QK_LAYER_TAP"),
+            (0x5220, 0x523F, "QK_MOMENTARY"),
+            (0x52A0, 0x52BF, "QK_ONE_SHOT_MOD"),
+        ]
+        .into_iter()
+        .map(|(start, end, define)| KeyCodeRange {
+            start,
+            end,
+            define: define.to_owned(),
+        })
+        .collect();
+        let find = |name: &str| match name {
+            "KC_A" => Some(0x04),
+            "KC_SPC" | "KC_SPACE" => Some(0x2C),
+            "KC_TRNS" | "_______" => Some(0x01),
+            "QK_BOOT" => Some(0x7C00),
+            _ => None,
+        };
+        let parse = |expression: &str| parse_keycode(expression, &find, &ranges);
+
+        assert_eq!(parse("KC_A").unwrap(), 0x0004);
+        assert_eq!(parse("_______").unwrap(), 0x0001);
+        assert_eq!(parse("0x5221").unwrap(), 0x5221);
+        assert_eq!(parse("LT(2, KC_SPC)").unwrap(), 0x422C);
+        assert_eq!(parse("LCTL(KC_A)").unwrap(), 0x0104);
+        assert_eq!(parse("LCTL(LSFT(KC_A))").unwrap(), 0x0304);
+        assert_eq!(parse("MT(MOD_LCTL | MOD_LSFT, KC_A)").unwrap(), 0x2304);
+        assert_eq!(parse("RSFT_T(KC_A)").unwrap(), 0x3204);
+        assert_eq!(parse("OSM(MOD_LSFT)").unwrap(), 0x52A2);
+        assert_eq!(parse("OSM(MOD_MEH)").unwrap(), 0x52A7);
+        assert_eq!(parse("OSM(MOD_HYPR)").unwrap(), 0x52AF);
+        assert_eq!(parse("MEH_T(KC_A)").unwrap(), 0x2704);
+        assert_eq!(parse("HYPR_T(KC_A)").unwrap(), 0x2F04);
+        assert_eq!(parse("C_S_T(KC_A)").unwrap(), 0x2304);
+        assert_eq!(parse("ALGR_T(KC_A)").unwrap(), 0x3404);
+        assert_eq!(parse("MEH(KC_A)").unwrap(), 0x0704);
+        assert_eq!(parse(" MO(1) ").unwrap(), 0x5221);
+
+        let errors: Vec<String> = [
+            "KC_NOPE",
+            "LT(16, KC_A)",
+            "LT(1, QK_BOOT)",
+            "MT(MOD_FOO, KC_A)",
+            "TG(3)",
+            "MO(1",
+            "XYZ(KC_A)",
+        ]
+        .into_iter()
+        .map(|expression| parse(expression).unwrap_err().to_string())
+        .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "unknown keycode KC_NOPE",
+                "16 is out of range, at most 15 is allowed",
+                "QK_BOOT is not a basic keycode",
+                "unknown modifier MOD_FOO",
+                "keycode range QK_TOGGLE_LAYER is not defined",
+                "missing closing parenthesis in MO(1",
+                "unknown keycode function XYZ in XYZ(KC_A)",
+            ]
+        );
+    }
+
+    #[test]
+    pub fn index_keycodes() {
+        let keycode = |code: u16, key: &str, aliases: &[&str]| KeyCode {
+            code,
+            key: key.to_owned(),
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+            ..Default::default()
+        };
+        let mut database = KeyCodeDatabase::new([
+            keycode(0x04, "KC_A", &[]),
+            keycode(0x28, "KC_ENTER", &["KC_ENT"]),
+            keycode(0x7E00, "SAFE_RANGE", &[]),
+        ]);
+
+        assert_eq!(
+            database.find("KC_ENT").map(|keycode| keycode.code),
+            Some(0x28)
+        );
+        assert_eq!(
+            database.get(0x04).map(|keycode| keycode.key.as_str()),
+            Some("KC_A")
+        );
+
+        database.extend([keycode(0x7E00, "CUSTOM_KEY", &["CK"])]);
+        assert_eq!(database.len(), 3);
+        assert_eq!(database.find("SAFE_RANGE"), None);
+        assert_eq!(
+            database.find("CK").map(|keycode| keycode.code),
+            Some(0x7E00)
+        );
+
+        let categories = database.categories();
+        assert_eq!(categories.len(), 1);
+        assert_eq!(categories[0].name, "other");
+        assert_eq!(categories[0].codes.len(), 3);
+
+        assert_eq!(match_rank("ent", "KC_ENT"), Some(0));
+        assert_eq!(match_rank("ent", "KC_ENTER"), Some(1));
+        assert_eq!(match_rank("ter", "KC_ENTER"), Some(2));
+        assert_eq!(match_rank("kent", "KC_ENTER"), Some(5));
+        assert_eq!(match_rank("x", "KC_ENTER"), None);
+    }
 }
@@ -7,9 +7,9 @@ use std::{
 use anyhow::Result;
 use convert_case::{Case, Casing};
 use serde::{de::Error, Deserialize, Deserializer, Serialize};
-use specta::Type;
 
-#[derive(Clone, Deserialize, Serialize, Debug, Type)]
+#[derive(Clone, Deserialize, Serialize, Debug)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct LightingEffects {
     pub groups: Option<HashMap<String, LightingGroup>>,
     #[serde(deserialize_with = "effect_from_hex_map")]
@@ -45,7 +45,8 @@ where
         .ok_or(D::Error::custom("failed to parse lighting effect table"))
 }
 
-#[derive(Clone, Deserialize, Serialize, Type, PartialEq, Debug)]
+#[derive(Clone, Deserialize, Serialize, PartialEq, Debug)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct LightingEffect {
     #[serde(default)]
     pub code: u16,
@@ -55,7 +56,8 @@ pub struct LightingEffect {
     pub label: String,
 }
 
-#[derive(Clone, Deserialize, Serialize, Type, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct LightingGroup {
     pub define: String,
 }
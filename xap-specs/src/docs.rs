@@ -0,0 +1,342 @@
+// Renders the protocol reference documentation from the specs, following the layout of the
+// documentation QMK generates for its XAP specs
+
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+use pulldown_cmark::{html, Options, Parser};
+
+use crate::spec::{BasicType, Route, RouteType, Spec, StructMember};
+
+/// Renders the documentation of a spec as Markdown, the sections are rendered in the order of
+/// its `documentation` section.
+pub fn render_markdown(spec: &Spec) -> Result<String> {
+    let mut doc = String::new();
+
+    for section in &spec.documentation.order {
+        match section.strip_prefix('!') {
+            Some("type_docs.md.j2") => render_type_docs(spec, &mut doc)?,
+            Some("term_definitions.md.j2") => render_term_definitions(spec, &mut doc)?,
+            Some("response_flags.md.j2") => render_response_flags(spec, &mut doc)?,
+            Some("routes.md.j2") => {
+                for route in spec.routes.values() {
+                    render_router(route, 3, &mut doc)?;
+                }
+            }
+            Some("broadcast_messages.md.j2") => render_broadcast_messages(spec, &mut doc)?,
+            Some(table) => bail!("unknown documentation table {table}"),
+            None => match spec.documentation.sections.get(section) {
+                Some(text) if text.trim().is_empty() => {}
+                Some(text) => writeln!(&mut doc, "{}\n", text.trim_end())?,
+                None => bail!("missing documentation section {section}"),
+            },
+        }
+    }
+
+    Ok(doc)
+}
+
+/// Renders the documentation of a spec as a standalone HTML page.
+pub fn render_html(spec: &Spec) -> Result<String> {
+    let markdown = render_markdown(spec)?;
+    Ok(html_page(&format!("QMK XAP {}", spec.version), &markdown))
+}
+
+/// Renders the overview of all versions, newest first. `extension` is the one of the linked
+/// documents, the overview of the HTML documents is a HTML page itself.
+pub fn render_index(specs: &[Spec], extension: &str) -> Result<String> {
+    let mut doc =
+        String::from("# QMK Firmware XAP Specs\n\n| Version | Documentation |\n| -- | -- |\n");
+    for spec in specs.iter().rev() {
+        writeln!(
+            &mut doc,
+            "| {version} | [xap_{version}.{extension}](xap_{version}.{extension}) |",
+            version = spec.version
+        )?;
+    }
+
+    if extension == "html" {
+        return Ok(html_page("QMK XAP Specs", &doc));
+    }
+    Ok(doc)
+}
+
+fn html_page(title: &str, markdown: &str) -> String {
+    let mut body = String::new();
+    html::push_html(&mut body, Parser::new_ext(markdown, Options::ENABLE_TABLES));
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+</head>
+<body>
+{body}</body>
+</html>
+"#
+    )
+}
+
+fn render_type_docs(spec: &Spec, doc: &mut String) -> Result<()> {
+    writeln!(doc, "| Name | Definition |\n| -- | -- |")?;
+    for (name, description) in &spec.type_docs {
+        writeln!(doc, "| _{name}_ | {} |", table_cell(description))?;
+    }
+    writeln!(doc)?;
+    Ok(())
+}
+
+fn render_term_definitions(spec: &Spec, doc: &mut String) -> Result<()> {
+    writeln!(doc, "| Name | Definition |\n| -- | -- |")?;
+    for (name, description) in &spec.term_definitions {
+        writeln!(doc, "| _{name}_ | {} |", table_cell(description))?;
+    }
+    for definition in spec.type_definitions.values() {
+        let format = if definition.r#type == BasicType::Struct {
+            definition
+                .struct_members
+                .iter()
+                .map(|member| format!("<br>`{}` - {}", format_type(&member.r#type), member.name))
+                .collect::<String>()
+        } else {
+            format!(" `{}`", format_type(&definition.r#type))
+        };
+        writeln!(
+            doc,
+            "| _{}_ | {} Takes the format:{format} |",
+            definition.name,
+            table_cell(&definition.description)
+        )?;
+    }
+    writeln!(doc)?;
+    Ok(())
+}
+
+fn render_response_flags(spec: &Spec, doc: &mut String) -> Result<()> {
+    let bits = (0..8u8).rev();
+    writeln!(
+        doc,
+        "| {} |",
+        bits.clone()
+            .map(|bit| format!("Bit {bit}"))
+            .collect::<Vec<String>>()
+            .join(" | ")
+    )?;
+    writeln!(doc, "|{}", " -- |".repeat(8))?;
+    writeln!(
+        doc,
+        "| {} |",
+        bits.map(|bit| match spec.response_flags.bits.get(&bit) {
+            Some(flag) => format!("`{}`", flag.define.as_deref().unwrap_or(&flag.name)),
+            None => "`-`".to_owned(),
+        })
+        .collect::<Vec<String>>()
+        .join(" | ")
+    )?;
+    writeln!(doc)?;
+
+    for (bit, flag) in &spec.response_flags.bits {
+        writeln!(
+            doc,
+            "* Bit {bit} (`{}`): {}",
+            flag.define.as_deref().unwrap_or(&flag.name),
+            flag.description.trim()
+        )?;
+    }
+    writeln!(doc)?;
+    Ok(())
+}
+
+/// Renders a table of the commands of the router, followed by its nested routers one heading
+/// level deeper.
+fn render_router(router: &Route, level: usize, doc: &mut String) -> Result<()> {
+    writeln!(
+        doc,
+        "{} {} - `{}`\n",
+        "#".repeat(level),
+        router.name.as_deref().unwrap_or_default().trim(),
+        format_id(&router.id)
+    )?;
+    if let Some(description) = &router.description {
+        writeln!(doc, "{}\n", description.trim_end())?;
+    }
+    if let Some(condition) = &router.enable_if_preprocessor {
+        writeln!(doc, "__Requires__ `{condition}`\n")?;
+    }
+
+    let (commands, routers): (Vec<&Route>, Vec<&Route>) = router
+        .routes
+        .values()
+        .partition(|route| matches!(route.r#type, Some(RouteType::Command)));
+
+    if !commands.is_empty() {
+        writeln!(
+            doc,
+            "| Name | Route | Since | Tags | Payloads | Description |\n| -- | -- | -- | -- | -- | -- |"
+        )?;
+        for command in commands {
+            render_command(command, doc)?;
+        }
+        writeln!(doc)?;
+    }
+
+    for router in routers {
+        render_router(router, level + 1, doc)?;
+    }
+
+    Ok(())
+}
+
+fn render_command(command: &Route, doc: &mut String) -> Result<()> {
+    let mut tags = Vec::new();
+    if command.is_secure() {
+        tags.push("__Secure__".to_owned());
+    }
+    if let Some(condition) = &command.enable_if_preprocessor {
+        tags.push(format!("__Requires__ `{condition}`"));
+    }
+
+    let payloads = [
+        format_payload(
+            "Request",
+            &command.request_type,
+            &command.request_struct_members,
+        ),
+        format_payload(
+            "Response",
+            &command.return_type,
+            &command.return_struct_members,
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<String>>();
+
+    writeln!(
+        doc,
+        "| {name} | `{id}` | {since} | {tags} | {payloads} | {description} |",
+        name = command.name.as_deref().unwrap_or_default().trim(),
+        id = format_id(&command.id),
+        since = command
+            .xap_version
+            .map(|version| version.to_string())
+            .unwrap_or_default(),
+        tags = tags.join("<br>"),
+        payloads = payloads.join("<br><br>"),
+        description = table_cell(command.description.as_deref().unwrap_or_default())
+    )?;
+    Ok(())
+}
+
+fn render_broadcast_messages(spec: &Spec, doc: &mut String) -> Result<()> {
+    let Some(broadcast_messages) = &spec.broadcast_messages else {
+        return Ok(());
+    };
+
+    for (id, message) in &broadcast_messages.messages {
+        writeln!(doc, "### {} - `0x{id:02X}`\n", message.name)?;
+        writeln!(doc, "{}\n", message.description.trim_end())?;
+        if message.return_type != BasicType::Unit {
+            writeln!(
+                doc,
+                "__Payload:__ `{}`\n",
+                format_type(&message.return_type)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn format_payload(kind: &str, ty: &BasicType, members: &[StructMember]) -> Option<String> {
+    match ty {
+        BasicType::Unit => None,
+        BasicType::Struct => Some(format!(
+            "__{kind}:__{}",
+            members
+                .iter()
+                .map(|member| format!("<br>* {}: `{}`", member.name, format_type(&member.r#type)))
+                .collect::<String>()
+        )),
+        ty => Some(format!("__{kind}:__ `{}`", format_type(ty))),
+    }
+}
+
+/// Name of the type as written in the specs.
+fn format_type(ty: &BasicType) -> String {
+    match ty {
+        BasicType::Unit => "-".to_owned(),
+        BasicType::U8 => "u8".to_owned(),
+        BasicType::U16 => "u16".to_owned(),
+        BasicType::U32 => "u32".to_owned(),
+        BasicType::U64 => "u64".to_owned(),
+        BasicType::Struct => "struct".to_owned(),
+        BasicType::String => "string".to_owned(),
+        BasicType::Array(ty, len) => format!("{}[{len}]", format_type(ty)),
+        BasicType::Predefined(name) => name.clone(),
+    }
+}
+
+fn format_id(id: &[u8]) -> String {
+    id.iter()
+        .map(|byte| format!("0x{byte:02X}"))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Markdown tables can't span multiple lines, line breaks have to be HTML.
+fn table_cell(text: &str) -> String {
+    text.trim().replace('|', "\\|").replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod test {
+    use similar_asserts::assert_eq;
+
+    use super::*;
+
+    fn specs() -> Vec<Spec> {
+        Spec::versions_from_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/assets")).unwrap()
+    }
+
+    #[test]
+    fn render_routes() {
+        let spec = specs().pop().unwrap();
+        let doc = render_markdown(&spec).unwrap();
+
+        assert!(doc.starts_with("# QMK Firmware XAP Specs\n"));
+        assert!(doc.contains("\n### XAP - `0x00`\n"));
+        assert!(doc.contains("\n#### rgbmatrix - `0x06 0x04`\n"));
+        assert!(doc.contains(
+            "\n| Set Keycode | `0x05 0x03` | 0.2.0 | __Secure__ | __Request:__<br>* Layer: `u8`"
+        ));
+        assert!(doc.contains("\n### Secure Status - `0x01`\n"));
+    }
+
+    #[test]
+    fn render_every_version() {
+        let specs = specs();
+        let versions: Vec<String> = specs.iter().map(|spec| spec.version.to_string()).collect();
+        assert_eq!(versions, vec!["0.0.1", "0.1.0", "0.2.0", "0.3.0"]);
+
+        // Broadcast messages were introduced with 0.1.0
+        assert!(!render_markdown(&specs[0])
+            .unwrap()
+            .contains("## Broadcast messages"));
+        assert!(render_markdown(&specs[1])
+            .unwrap()
+            .contains("## Broadcast messages"));
+
+        let html = render_html(&specs[3]).unwrap();
+        assert!(html.contains("<title>QMK XAP 0.3.0</title>"));
+        assert!(html.contains("<table>"));
+
+        let index = render_index(&specs, "html").unwrap();
+        assert!(index.contains("<title>QMK XAP Specs</title>"));
+        assert!(index.contains(r#"<a href="xap_0.3.0.html">xap_0.3.0.html</a>"#));
+        assert!(render_index(&specs, "md")
+            .unwrap()
+            .contains("| 0.3.0 | [xap_0.3.0.md](xap_0.3.0.md) |"));
+    }
+}
@@ -0,0 +1,472 @@
+// This file implements invoking XAP routes by name with the specs that are read at runtime,
+// requests are encoded from and responses decoded into JSON values
+
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{anyhow, Result};
+use convert_case::{Case, Casing};
+use serde_json::{Map, Value};
+
+use crate::{
+    error::{XapError, XapResult},
+    spec::{BasicType, Route, RouteCapability, RouteType, Spec, StructMember, TypeDefinition},
+    version::BcdVersion,
+};
+
+/// A command of the specs, addressed by the snake case names of its parent routers and itself
+/// joined with dots, e.g. `lighting.rgbmatrix.get_config`.
+#[derive(Debug, Clone)]
+pub struct DynamicRoute {
+    pub path: String,
+    pub id: Vec<u8>,
+    pub xap_version: BcdVersion,
+    pub secure: bool,
+    pub capabilities: Vec<RouteCapability>,
+    route: Route,
+}
+
+impl DynamicRoute {
+    pub fn description(&self) -> Option<&str> {
+        self.route.description.as_deref()
+    }
+}
+
+/// All commands of the merged specs, used to talk to firmware that implements routes the
+/// generated requests don't know about yet.
+#[derive(Debug, Clone)]
+pub struct RouteTable {
+    routes: BTreeMap<String, DynamicRoute>,
+    type_definitions: BTreeMap<String, TypeDefinition>,
+}
+
+impl RouteTable {
+    pub fn new(spec: &Spec) -> Self {
+        let mut routes = BTreeMap::new();
+        for route in spec.routes.values() {
+            collect_routes(spec, route, &mut Vec::new(), &mut routes);
+        }
+
+        Self {
+            routes,
+            type_definitions: spec.type_definitions.clone(),
+        }
+    }
+
+    /// Builds the table from the `xap*.hjson` specs in `dir`.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let spec = Spec::from_dir(dir)?.ok_or_else(|| anyhow!("no XAP specs found in {dir:?}"))?;
+        Ok(Self::new(&spec))
+    }
+
+    pub fn get(&self, path: &str) -> XapResult<&DynamicRoute> {
+        self.routes.get(path).ok_or_else(|| XapError::UnknownRoute {
+            route: path.to_owned(),
+        })
+    }
+
+    pub fn routes(&self) -> impl Iterator<Item = &DynamicRoute> {
+        self.routes.values()
+    }
+
+    /// Encodes the request payload of `route`. Struct members are passed as JSON object with
+    /// their snake case names as keys, routes without a request take `null`.
+    pub fn encode_request(&self, route: &DynamicRoute, args: &Value) -> XapResult<Vec<u8>> {
+        let mut payload = Vec::new();
+        self.encode(
+            &route.route.request_type,
+            &route.route.request_struct_members,
+            args,
+            &mut payload,
+        )
+        .map_err(|reason| XapError::InvalidArguments {
+            route: route.path.clone(),
+            reason,
+        })?;
+        Ok(payload)
+    }
+
+    /// Decodes the response payload of `route` in the same JSON layout the generated response
+    /// types are serialized to.
+    pub fn decode_response(&self, route: &DynamicRoute, payload: &[u8]) -> XapResult<Value> {
+        let mut reader = payload;
+        let value = self
+            .decode(
+                &route.route.return_type,
+                &route.route.return_struct_members,
+                &mut reader,
+            )
+            .map_err(|reason| {
+                XapError::Decode(format!("response of route {}: {reason}", route.path))
+            })?;
+
+        if route
+            .route
+            .return_purpose
+            .as_ref()
+            .is_some_and(|p| p == "bcd-version")
+        {
+            if let Some(version) = value.as_u64() {
+                let version = BcdVersion::try_from(version as u32)?;
+                return Ok(Value::String(version.to_string()));
+            }
+        }
+
+        Ok(value)
+    }
+
+    /// Arguments of `route` with all values zeroed, a starting point to write a request.
+    pub fn request_template(&self, route: &DynamicRoute) -> Value {
+        self.template(
+            &route.route.request_type,
+            &route.route.request_struct_members,
+        )
+    }
+
+    fn template(&self, ty: &BasicType, members: &[StructMember]) -> Value {
+        match ty {
+            BasicType::Unit => Value::Null,
+            BasicType::U8 | BasicType::U16 | BasicType::U32 | BasicType::U64 => Value::from(0),
+            BasicType::String => Value::from(""),
+            BasicType::Array(ty, len) => Value::Array(vec![self.template(ty, &[]); *len]),
+            BasicType::Struct => Value::Object(
+                members
+                    .iter()
+                    .map(|member| (member_name(member), self.template(&member.r#type, &[])))
+                    .collect(),
+            ),
+            BasicType::Predefined(name) => match self.type_definitions.get(name) {
+                Some(definition) => self.template(&definition.r#type, &definition.struct_members),
+                None => Value::Null,
+            },
+        }
+    }
+
+    fn encode(
+        &self,
+        ty: &BasicType,
+        members: &[StructMember],
+        value: &Value,
+        payload: &mut Vec<u8>,
+    ) -> std::result::Result<(), String> {
+        match ty {
+            BasicType::Unit if value.is_null() => Ok(()),
+            BasicType::Unit => Err(format!("expected no arguments, got {value}")),
+            BasicType::U8 => encode_integer(value, 1, payload),
+            BasicType::U16 => encode_integer(value, 2, payload),
+            BasicType::U32 => encode_integer(value, 4, payload),
+            BasicType::U64 => encode_integer(value, 8, payload),
+            BasicType::String => {
+                let string = value
+                    .as_str()
+                    .ok_or_else(|| format!("expected a string, got {value}"))?;
+                payload.extend_from_slice(string.as_bytes());
+                Ok(())
+            }
+            BasicType::Array(ty, len) => {
+                let elements = value
+                    .as_array()
+                    .filter(|elements| elements.len() == *len)
+                    .ok_or_else(|| format!("expected an array of {len} elements, got {value}"))?;
+                for element in elements {
+                    self.encode(ty, &[], element, payload)?;
+                }
+                Ok(())
+            }
+            BasicType::Struct => {
+                let object = value
+                    .as_object()
+                    .ok_or_else(|| format!("expected an object, got {value}"))?;
+                if let Some(unknown) = object
+                    .keys()
+                    .find(|key| !members.iter().any(|member| member_name(member) == **key))
+                {
+                    return Err(format!("unknown member {unknown}"));
+                }
+                for member in members {
+                    let name = member_name(member);
+                    let value = object
+                        .get(&name)
+                        .ok_or_else(|| format!("missing member {name}"))?;
+                    self.encode(&member.r#type, &[], value, payload)
+                        .map_err(|reason| format!("{name}: {reason}"))?;
+                }
+                Ok(())
+            }
+            BasicType::Predefined(name) => {
+                let definition = self.type_definition(name)?;
+                self.encode(
+                    &definition.r#type,
+                    &definition.struct_members,
+                    value,
+                    payload,
+                )
+            }
+        }
+    }
+
+    fn decode(
+        &self,
+        ty: &BasicType,
+        members: &[StructMember],
+        reader: &mut &[u8],
+    ) -> std::result::Result<Value, String> {
+        match ty {
+            BasicType::Unit => Ok(Value::Null),
+            BasicType::U8 => decode_integer(reader, 1),
+            BasicType::U16 => decode_integer(reader, 2),
+            BasicType::U32 => decode_integer(reader, 4),
+            BasicType::U64 => decode_integer(reader, 8),
+            // Strings always span the remaining payload
+            BasicType::String => {
+                let string = String::from_utf8(reader.to_vec()).map_err(|err| err.to_string())?;
+                *reader = &[];
+                Ok(Value::String(string))
+            }
+            BasicType::Array(ty, len) => (0..*len)
+                .map(|_| self.decode(ty, &[], reader))
+                .collect::<std::result::Result<_, _>>()
+                .map(Value::Array),
+            BasicType::Struct => {
+                let mut object = Map::new();
+                for member in members {
+                    let name = member_name(member);
+                    let value = self
+                        .decode(&member.r#type, &[], reader)
+                        .map_err(|reason| format!("{name}: {reason}"))?;
+                    object.insert(name, value);
+                }
+                Ok(Value::Object(object))
+            }
+            BasicType::Predefined(name) => {
+                let definition = self.type_definition(name)?;
+                self.decode(&definition.r#type, &definition.struct_members, reader)
+            }
+        }
+    }
+
+    fn type_definition(&self, name: &str) -> std::result::Result<&TypeDefinition, String> {
+        self.type_definitions
+            .get(name)
+            .ok_or_else(|| format!("missing type definition for {name}"))
+    }
+}
+
+fn collect_routes(
+    spec: &Spec,
+    route: &Route,
+    path: &mut Vec<String>,
+    routes: &mut BTreeMap<String, DynamicRoute>,
+) {
+    let name = route
+        .name
+        .as_deref()
+        .unwrap_or_default()
+        .to_case(Case::Snake);
+
+    match route.r#type.as_ref().unwrap_or(&RouteType::Router) {
+        RouteType::Router => {
+            path.push(name);
+            for subroute in route.routes.values() {
+                collect_routes(spec, subroute, path, routes);
+            }
+            path.pop();
+        }
+        RouteType::Command => {
+            let full_path = path
+                .iter()
+                .chain([&name])
+                .cloned()
+                .collect::<Vec<String>>()
+                .join(".");
+
+            routes.insert(
+                full_path.clone(),
+                DynamicRoute {
+                    path: full_path,
+                    id: route.id.clone(),
+                    xap_version: route.xap_version.unwrap_or(spec.version),
+                    secure: route.is_secure(),
+                    capabilities: spec.capabilities(&route.id),
+                    route: route.clone(),
+                },
+            );
+
+            for subroute in route.routes.values() {
+                collect_routes(spec, subroute, path, routes);
+            }
+        }
+    }
+}
+
+fn member_name(member: &StructMember) -> String {
+    member.name.to_case(Case::Snake)
+}
+
+fn encode_integer(
+    value: &Value,
+    size: usize,
+    payload: &mut Vec<u8>,
+) -> std::result::Result<(), String> {
+    let number = value
+        .as_u64()
+        .ok_or_else(|| format!("expected an unsigned integer, got {value}"))?;
+    if size < 8 && number >> (size * 8) != 0 {
+        return Err(format!("{number} doesn't fit into {size} bytes"));
+    }
+    payload.extend_from_slice(&number.to_le_bytes()[..size]);
+    Ok(())
+}
+
+fn decode_integer(reader: &mut &[u8], size: usize) -> std::result::Result<Value, String> {
+    if reader.len() < size {
+        return Err(format!(
+            "expected {size} more bytes but payload ends after {}",
+            reader.len()
+        ));
+    }
+    let (bytes, rest) = reader.split_at(size);
+    *reader = rest;
+
+    let mut number = [0; 8];
+    number[..size].copy_from_slice(bytes);
+    Ok(Value::from(u64::from_le_bytes(number)))
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use similar_asserts::assert_eq;
+
+    use super::*;
+
+    fn route_table() -> RouteTable {
+        RouteTable::from_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/assets"))
+            .expect("failed to read XAP specs")
+    }
+
+    #[test]
+    fn lookup_routes() {
+        let routes = route_table();
+
+        let get_config = routes.get("lighting.rgbmatrix.get_config").unwrap();
+        assert_eq!(get_config.id, vec![0x06, 0x04, 0x03]);
+        assert_eq!(get_config.xap_version, BcdVersion::new(0, 3, 0));
+        assert!(!get_config.secure);
+        assert_eq!(
+            get_config.capabilities,
+            vec![RouteCapability {
+                route: vec![0x06, 0x01],
+                bit: 0x04,
+                condition: "defined(RGB_MATRIX_ENABLE)".to_owned()
+            }]
+        );
+
+        assert!(routes.get("remapping.set_keycode").unwrap().secure);
+        assert_eq!(
+            routes.get("lighting.rgbmatrix").unwrap_err(),
+            XapError::UnknownRoute {
+                route: "lighting.rgbmatrix".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn encode_request() {
+        let routes = route_table();
+
+        let set_keycode = routes.get("remapping.set_keycode").unwrap();
+        assert_eq!(
+            routes
+                .encode_request(
+                    set_keycode,
+                    &json!({"layer": 1, "row": 2, "column": 3, "keycode": 0x1234})
+                )
+                .unwrap(),
+            vec![1, 2, 3, 0x34, 0x12]
+        );
+
+        let set_config = routes.get("lighting.rgbmatrix.set_config").unwrap();
+        assert_eq!(
+            routes
+                .encode_request(
+                    set_config,
+                    &json!({"enable": 1, "mode": 2, "hue": 3, "sat": 4, "val": 5, "speed": 6, "flags": 7})
+                )
+                .unwrap(),
+            vec![1, 2, 3, 4, 5, 6, 7]
+        );
+        assert_eq!(
+            routes
+                .encode_request(set_config, &json!({"enable": 1}))
+                .unwrap_err(),
+            XapError::InvalidArguments {
+                route: "lighting.rgbmatrix.set_config".to_owned(),
+                reason: "missing member mode".to_owned()
+            }
+        );
+
+        let get_config = routes.get("lighting.rgbmatrix.get_config").unwrap();
+        assert_eq!(
+            routes.encode_request(get_config, &Value::Null).unwrap(),
+            Vec::<u8>::new()
+        );
+        assert!(routes.encode_request(get_config, &json!(1)).is_err());
+        assert!(routes
+            .encode_request(
+                set_keycode,
+                &json!({"layer": 256, "row": 2, "column": 3, "keycode": 4})
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn request_template() {
+        let routes = route_table();
+
+        let set_keycode = routes.get("remapping.set_keycode").unwrap();
+        let template = routes.request_template(set_keycode);
+        assert_eq!(
+            template,
+            json!({"layer": 0, "row": 0, "column": 0, "keycode": 0})
+        );
+        assert_eq!(
+            routes.encode_request(set_keycode, &template).unwrap(),
+            vec![0; 5]
+        );
+
+        let get_config = routes.get("lighting.rgbmatrix.get_config").unwrap();
+        assert_eq!(routes.request_template(get_config), Value::Null);
+    }
+
+    #[test]
+    fn decode_response() {
+        let routes = route_table();
+
+        let get_config = routes.get("lighting.rgbmatrix.get_config").unwrap();
+        assert_eq!(
+            routes
+                .decode_response(get_config, &[1, 2, 3, 4, 5, 6, 7])
+                .unwrap(),
+            json!({"enable": 1, "mode": 2, "hue": 3, "sat": 4, "val": 5, "speed": 6, "flags": 7})
+        );
+        assert!(matches!(
+            routes.decode_response(get_config, &[1, 2, 3]),
+            Err(XapError::Decode(_))
+        ));
+
+        let version = routes.get("xap.version").unwrap();
+        assert_eq!(
+            routes
+                .decode_response(version, &[0x00, 0x00, 0x03, 0x00])
+                .unwrap(),
+            json!("0.3.0")
+        );
+
+        let product_name = routes.get("qmk.product_name").unwrap();
+        assert_eq!(
+            routes.decode_response(product_name, b"XAP Board").unwrap(),
+            json!("XAP Board")
+        );
+    }
+}
@@ -0,0 +1,102 @@
+// This file defines the errors of the XAP protocol layer
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::version::BcdVersion;
+
+pub type XapResult<T> = Result<T, XapError>;
+
+/// Structured failure of a XAP operation, serialized with a `kind` tag so the frontend can react
+/// on specific failures.
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[serde(tag = "kind", content = "data")]
+pub enum XapError {
+    #[error("timeout waiting for response to route {route:02X?}")]
+    Timeout { route: Vec<u8> },
+    #[error("device is locked, route can only be accessed after unlocking")]
+    DeviceLocked,
+    #[error("route {route:02X?} is secure, the device has to be unlocked first")]
+    SecureRouteLocked { route: Vec<u8> },
+    #[error("unlock sequence was not completed on the device")]
+    UnlockFailed,
+    #[error("secure action failed")]
+    SecureActionFailed,
+    #[error("device failed to handle the request")]
+    RequestFailed,
+    #[error("unknown response flags {flags:#04X}")]
+    UnknownResponseFlags { flags: u8 },
+    #[error("route {route:02X?} requires XAP version {required} but device implements {device}")]
+    UnsupportedByVersion {
+        route: Vec<u8>,
+        required: BcdVersion,
+        device: BcdVersion,
+    },
+    #[error("route {route:02X?} is not supported by the device, it requires {condition}")]
+    UnsupportedByCapability { route: Vec<u8>, condition: String },
+    #[error("unknown route {route}")]
+    UnknownRoute { route: String },
+    #[error("invalid arguments for route {route}: {reason}")]
+    InvalidArguments { route: String, reason: String },
+    #[error("malformed XAP message: {0}")]
+    Decode(String),
+    #[error("transport error: {0}")]
+    Transport(String),
+    #[error("unknown device id: {0}")]
+    UnknownDevice(Uuid),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl From<binrw::Error> for XapError {
+    fn from(err: binrw::Error) -> Self {
+        // Errors raised by our own BinRead implementations are passed through unchanged
+        match err.custom_err::<XapError>() {
+            Some(err) => err.clone(),
+            None => Self::Decode(err.to_string()),
+        }
+    }
+}
+
+impl From<anyhow::Error> for XapError {
+    fn from(err: anyhow::Error) -> Self {
+        // XAP errors that were propagated as anyhow errors keep their kind
+        match err.downcast::<XapError>() {
+            Ok(err) => err,
+            Err(err) => Self::Other(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::anyhow;
+    use similar_asserts::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn serialize_with_kind_tag() {
+        assert_eq!(
+            serde_json::to_string(&XapError::DeviceLocked).unwrap(),
+            r#"{"kind":"DeviceLocked"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&XapError::UnknownResponseFlags { flags: 0x80 }).unwrap(),
+            r#"{"kind":"UnknownResponseFlags","data":{"flags":128}}"#
+        );
+    }
+
+    #[test]
+    fn keep_kind_through_anyhow() {
+        let err: anyhow::Error = XapError::Timeout { route: vec![1, 2] }.into();
+        assert_eq!(XapError::from(err), XapError::Timeout { route: vec![1, 2] });
+
+        assert_eq!(
+            XapError::from(anyhow!("layout not found")),
+            XapError::Other("layout not found".to_owned())
+        );
+    }
+}
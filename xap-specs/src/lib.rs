@@ -1,17 +1,23 @@
 use std::fmt::Display;
 
-use anyhow::anyhow;
 use binrw::{BinRead, BinReaderExt, BinResult, Endian};
 use serde::Serialize;
-use specta::Type;
 
 pub mod broadcast;
 pub mod constants;
+pub mod docs;
+pub mod dynamic;
+pub mod error;
+pub mod lint;
+pub mod metadata;
 pub mod request;
 pub mod response;
+pub mod spec;
 pub mod token;
+pub mod version;
 
-#[derive(Debug, Serialize, Clone, Copy, Type)]
+#[derive(Debug, Serialize, Clone, Copy)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub enum XapSecureStatus {
     Locked,
     Unlocking,
@@ -60,28 +66,3 @@ impl Display for XapSecureStatus {
         }
     }
 }
-
-#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
-pub struct XapVersion(u32);
-
-impl TryFrom<u32> for XapVersion {
-    type Error = anyhow::Error;
-
-    fn try_from(value: u32) -> Result<Self, Self::Error> {
-        match value {
-            0x01 | 0x0100 | 0x0200 | 0x0300 => Ok(Self(value)),
-            _ => Err(anyhow!(
-                "{value:06X} is not a valid BCD encoded Xap version"
-            )),
-        }
-    }
-}
-
-impl Display for XapVersion {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for digit in self.0.to_be_bytes() {
-            write!(f, "{digit:02X}")?;
-        }
-        Ok(())
-    }
-}
@@ -0,0 +1,445 @@
+// Checks the XAP specs for mistakes that would otherwise only surface as generated code that
+// doesn't compile, or as routes that silently misbehave
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    fs,
+    path::Path,
+};
+
+use anyhow::Result;
+use convert_case::{Case, Casing};
+use serde::{de::IgnoredAny, Deserialize};
+
+use crate::{
+    spec::{spec_files, BasicType, Entries, Route, Spec, ENABLED_SUBSYSTEMS_ROUTE},
+    version::BcdVersion,
+};
+
+/// A mistake found in the specs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecIssue {
+    /// Spec file the issue was found in, `None` for issues of the merged specs.
+    pub file: Option<String>,
+    pub message: String,
+}
+
+impl Display for SpecIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{file}: {}", self.message),
+            None => write!(f, "merged specs: {}", self.message),
+        }
+    }
+}
+
+/// The parts of a spec file that are lost when the ids are parsed into maps.
+#[derive(Deserialize)]
+struct RawSpec {
+    // Quoteless versions are mistaken for numbers if they are skipped like any other field
+    #[serde(rename = "version")]
+    _version: String,
+    #[serde(default)]
+    routes: Entries<RawRoute>,
+    broadcast_messages: Option<RawBroadcastMessages>,
+}
+
+#[derive(Deserialize)]
+struct RawRoute {
+    #[serde(default)]
+    routes: Entries<RawRoute>,
+}
+
+#[derive(Deserialize)]
+struct RawBroadcastMessages {
+    #[serde(default)]
+    messages: Entries<IgnoredAny>,
+}
+
+/// Checks every `xap*.hjson` spec in `dir` on its own and the merged latest spec.
+pub fn check_dir(dir: impl AsRef<Path>) -> Result<Vec<SpecIssue>> {
+    let mut issues = Vec::new();
+    let mut specs = Vec::new();
+
+    for path in spec_files(dir)? {
+        let file = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        let content = fs::read_to_string(&path)?;
+
+        let messages = match deser_hjson::from_str::<RawSpec>(&content) {
+            Ok(raw) => check_ids(&raw),
+            Err(err) => vec![format!("failed to parse spec: {err}")],
+        };
+        issues.extend(messages.into_iter().map(|message| SpecIssue {
+            file: Some(file.clone()),
+            message,
+        }));
+
+        match Spec::from_file(&path) {
+            Ok(spec) => specs.push((file, spec)),
+            // Errors of the raw spec are already reported
+            Err(_)
+                if issues
+                    .iter()
+                    .any(|issue| issue.file.as_ref() == Some(&file)) => {}
+            Err(err) => issues.push(SpecIssue {
+                file: Some(file),
+                message: format!("failed to parse spec: {err}"),
+            }),
+        }
+    }
+
+    issues.extend(check_versions(&specs));
+
+    let spec = Spec::merge_versions(specs.into_iter().map(|(_, spec)| spec).collect()).pop();
+    if let Some(spec) = spec {
+        issues.extend(check_spec(&spec).into_iter().map(|message| SpecIssue {
+            file: None,
+            message,
+        }));
+    }
+
+    Ok(issues)
+}
+
+/// Route and broadcast ids have to be valid hex numbers that are unique within their map.
+fn check_ids(spec: &RawSpec) -> Vec<String> {
+    fn check<T>(entries: &Entries<T>, location: &str, messages: &mut Vec<String>) -> Vec<u8> {
+        let mut ids = Vec::new();
+        for (key, _) in &entries.0 {
+            match u8::from_str_radix(key.trim_start_matches("0x"), 16) {
+                Ok(id) if ids.contains(&id) => {
+                    messages.push(format!("{location} 0x{id:02x} is defined more than once"))
+                }
+                Ok(id) => ids.push(id),
+                Err(_) => messages.push(format!("{location} has an invalid id {key}")),
+            }
+        }
+        ids
+    }
+
+    fn check_routes(routes: &Entries<RawRoute>, parent_id: &[u8], messages: &mut Vec<String>) {
+        let location = if parent_id.is_empty() {
+            "route".to_owned()
+        } else {
+            format!("route {} child", format_id(parent_id))
+        };
+        let ids = check(routes, &location, messages);
+
+        for ((_, route), id) in routes.0.iter().zip(ids) {
+            check_routes(&route.routes, &[parent_id, &[id]].concat(), messages);
+        }
+    }
+
+    let mut messages = Vec::new();
+    check_routes(&spec.routes, &[], &mut messages);
+    if let Some(broadcast_messages) = &spec.broadcast_messages {
+        check(
+            &broadcast_messages.messages,
+            "broadcast message",
+            &mut messages,
+        );
+    }
+    messages
+}
+
+/// Specs are named after their version and there is only one spec per version, otherwise it's
+/// ambiguous in which order they are merged.
+fn check_versions(specs: &[(String, Spec)]) -> Vec<SpecIssue> {
+    let mut issues = Vec::new();
+    let mut versions: BTreeMap<BcdVersion, &str> = BTreeMap::new();
+
+    for (file, spec) in specs {
+        let named_version = file
+            .strip_prefix("xap_")
+            .and_then(|name| name.strip_suffix(".hjson"))
+            .and_then(|version| version.parse::<BcdVersion>().ok());
+
+        let message = match named_version {
+            None => Some("file name does not contain a version like xap_0.1.0.hjson".to_owned()),
+            Some(named_version) if named_version != spec.version => Some(format!(
+                "declares version {} but is named after version {named_version}",
+                spec.version
+            )),
+            Some(_) => None,
+        };
+        if let Some(message) = message {
+            issues.push(SpecIssue {
+                file: Some(file.clone()),
+                message,
+            });
+        }
+
+        if let Some(other) = versions.insert(spec.version, file) {
+            issues.push(SpecIssue {
+                file: Some(file.clone()),
+                message: format!("declares the same version {} as {other}", spec.version),
+            });
+        }
+    }
+
+    issues
+}
+
+fn check_spec(spec: &Spec) -> Vec<String> {
+    let mut messages = Vec::new();
+
+    for route in spec.routes.values() {
+        check_route(spec, route, &mut messages);
+    }
+
+    // Every top level route is reported by the enabled subsystems query
+    if let Some(capabilities) = route_at(spec, &ENABLED_SUBSYSTEMS_ROUTE) {
+        check_capabilities(capabilities, &spec.routes, &mut messages);
+    }
+
+    if let Err(err) = spec.struct_definitions() {
+        messages.push(format!("type definitions: {err}"));
+    }
+
+    if let Some(broadcast_messages) = &spec.broadcast_messages {
+        let mut names: HashMap<String, u8> = HashMap::new();
+        for (id, message) in &broadcast_messages.messages {
+            check_type(spec, &message.return_type, &mut messages, || {
+                format!("broadcast message 0x{id:02x}")
+            });
+            if let Some(other) = names.insert(message.name.to_case(Case::Pascal), *id) {
+                messages.push(format!(
+                    "broadcast messages 0x{other:02x} and 0x{id:02x} generate the same name {}",
+                    message.name.to_case(Case::Pascal)
+                ));
+            }
+        }
+    }
+
+    messages
+}
+
+fn check_route(spec: &Spec, route: &Route, messages: &mut Vec<String>) {
+    let id = format_id(&route.id);
+
+    if route.name.as_deref().unwrap_or_default().trim().is_empty() {
+        messages.push(format!("route {id} has no name"));
+    }
+    if route.r#type.is_none() {
+        messages.push(format!("route {id} has no type"));
+    }
+
+    let location = || format!("route {id}");
+    check_type(spec, &route.request_type, messages, location);
+    check_type(spec, &route.return_type, messages, location);
+    for member in route
+        .request_struct_members
+        .iter()
+        .chain(&route.return_struct_members)
+    {
+        check_type(spec, &member.r#type, messages, || {
+            format!("member {} of route {id}", member.name)
+        });
+    }
+
+    match route.routes.values().find(|route| route.is_capabilities()) {
+        Some(capabilities) => check_capabilities(capabilities, &route.routes, messages),
+        None => {
+            if let Some(conditional) = route
+                .routes
+                .values()
+                .find(|route| route.enable_if_preprocessor.is_some())
+            {
+                messages.push(format!(
+                    "route {} is conditional, but route {id} has no capabilities route to report it",
+                    format_id(&conditional.id)
+                ));
+            }
+        }
+    }
+
+    for route in route.routes.values() {
+        check_route(spec, route, messages);
+    }
+}
+
+/// The capabilities route reports its sibling routes by their id, all of them have to fit into
+/// the flags.
+fn check_capabilities(
+    capabilities: &Route,
+    routes: &BTreeMap<u8, Route>,
+    messages: &mut Vec<String>,
+) {
+    let bits = match capabilities.return_type {
+        BasicType::U8 => 8,
+        BasicType::U16 => 16,
+        BasicType::U32 => 32,
+        BasicType::U64 => 64,
+        _ => {
+            messages.push(format!(
+                "capabilities route {} does not return an integer",
+                format_id(&capabilities.id)
+            ));
+            return;
+        }
+    };
+
+    for route in routes
+        .values()
+        .filter(|route| route.id.last() >= Some(&bits))
+    {
+        messages.push(format!(
+            "route {} does not fit into the {bits} bit flags of capabilities route {}",
+            format_id(&route.id),
+            format_id(&capabilities.id)
+        ));
+    }
+}
+
+fn check_type(
+    spec: &Spec,
+    ty: &BasicType,
+    messages: &mut Vec<String>,
+    location: impl Fn() -> String,
+) {
+    if let Some(name) = ty.predefined() {
+        if !spec.type_definitions.contains_key(name) {
+            messages.push(format!("{} refers to undefined type {name}", location()));
+        }
+    }
+}
+
+fn route_at<'a>(spec: &'a Spec, id: &[u8]) -> Option<&'a Route> {
+    let (first, rest) = id.split_first()?;
+    let mut route = spec.routes.get(first)?;
+    for id in rest {
+        route = route.routes.get(id)?;
+    }
+    Some(route)
+}
+
+fn format_id(id: &[u8]) -> String {
+    id.iter()
+        .map(|byte| format!("0x{byte:02x}"))
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use similar_asserts::assert_eq;
+
+    use super::*;
+
+    fn spec_dir(files: &[(&str, &str)]) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "qmk-xap-lint-{}-{}",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        fs::create_dir_all(&directory).unwrap();
+        for (name, content) in files {
+            fs::write(directory.join(name), content).unwrap();
+        }
+        directory
+    }
+
+    fn messages(dir: &Path) -> Vec<String> {
+        check_dir(dir)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn bundled_specs_are_valid() {
+        let issues = check_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/assets")).unwrap();
+        assert_eq!(issues, Vec::new());
+    }
+
+    #[test]
+    fn report_broken_specs() {
+        let dir = spec_dir(&[
+            (
+                "xap_0.0.1.hjson",
+                r#"{
+                    version: 0.0.1
+                    routes: {
+                        0x00: {
+                            type: router
+                            name: XAP
+                            routes: {
+                                0x00: {
+                                    type: command
+                                    name: Capabilities
+                                    return_type: u8
+                                    return_purpose: capabilities
+                                }
+                                0x01: {
+                                    type: command
+                                    name: Original
+                                }
+                                0x03: {
+                                    type: command
+                                    return_type: missing_type
+                                }
+                                0x08: {
+                                    type: command
+                                    name: Conditional
+                                    enable_if_preprocessor: defined(FOO)
+                                }
+                                0x1: {
+                                    type: command
+                                    name: Duplicate
+                                }
+                            }
+                        }
+                        0x01: {
+                            type: router
+                            name: QMK
+                            routes: {
+                                0x00: {
+                                    type: command
+                                    name: Conditional
+                                    enable_if_preprocessor: defined(BAR)
+                                }
+                            }
+                        }
+                    }
+                    broadcast_messages: {
+                        messages: {
+                            0x00: {
+                                name: Log
+                                description: Log
+                            }
+                            0x01: {
+                                name: log
+                                description: Log
+                            }
+                        }
+                    }
+                }"#,
+            ),
+            ("xap_0.0.2.hjson", "{\n version: 0.0.1\n}"),
+        ]);
+
+        assert_eq!(
+            messages(&dir),
+            vec![
+                "xap_0.0.1.hjson: route 0x00 child 0x01 is defined more than once",
+                "xap_0.0.2.hjson: declares version 0.0.1 but is named after version 0.0.2",
+                "xap_0.0.2.hjson: declares the same version 0.0.1 as xap_0.0.1.hjson",
+                "merged specs: route 0x00.0x08 does not fit into the 8 bit flags of capabilities route 0x00.0x00",
+                "merged specs: route 0x00.0x03 has no name",
+                "merged specs: route 0x00.0x03 refers to undefined type missing_type",
+                "merged specs: route 0x01.0x00 is conditional, but route 0x01 has no capabilities route to report it",
+                "merged specs: broadcast messages 0x00 and 0x01 generate the same name Log",
+            ]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,85 @@
+// Static registry of all routes of the spec, lets the UI, CLI and docs enumerate routes and show
+// their documentation without hardcoding any route knowledge
+
+use serde::Serialize;
+
+use crate::{request::XapCapability, version::BcdVersion};
+
+mod registry;
+
+pub use registry::ROUTES;
+
+/// Metadata of a command route, as generated from the specs by `xap-specs-codegen`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub struct RouteMetadata {
+    /// Full id of the route, starting with the ids of the routers it belongs to.
+    pub id: &'static [u8],
+    /// Module of the generated request in `xap_client::spec`, e.g. `lighting::rgblight`.
+    pub module: &'static str,
+    /// Path of the route as used by the dynamic route table, e.g. `lighting.rgblight.get_config`.
+    pub path: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    /// First XAP version that supports the route.
+    pub xap_version: BcdVersion,
+    pub secure: bool,
+    /// Name of the generated type of the request payload.
+    pub request_type: &'static str,
+    /// Name of the generated type of the response payload.
+    pub response_type: &'static str,
+    /// Capability bits the device has to report for the route to be usable.
+    pub capabilities: &'static [XapCapability],
+}
+
+pub fn route(id: &[u8]) -> Option<&'static RouteMetadata> {
+    ROUTES.iter().find(|route| route.id == id)
+}
+
+pub fn route_by_path(path: &str) -> Option<&'static RouteMetadata> {
+    ROUTES.iter().find(|route| route.path == path)
+}
+
+#[cfg(test)]
+mod test {
+    use similar_asserts::assert_eq;
+
+    use super::*;
+    use crate::dynamic::RouteTable;
+
+    #[test]
+    fn lookup_routes() {
+        let route = route_by_path("lighting.rgbmatrix.get_config").unwrap();
+        assert_eq!(super::route(route.id), Some(route));
+        assert_eq!(route.module, "lighting::rgbmatrix");
+        assert_eq!(route.response_type, "RgbMatrixConfig");
+        assert_eq!(
+            route.capabilities.last().unwrap().condition,
+            "defined(RGB_MATRIX_ENABLE)"
+        );
+
+        assert_eq!(route_by_path("lighting.missing"), None);
+    }
+
+    /// The generated registry has to describe the same routes as the specs read at runtime.
+    #[test]
+    fn registry_matches_specs() {
+        let routes = RouteTable::from_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/assets")).unwrap();
+
+        let mut paths: Vec<&str> = ROUTES.iter().map(|route| route.path).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            routes
+                .routes()
+                .map(|route| route.path.as_str())
+                .collect::<Vec<_>>()
+        );
+        for route in routes.routes() {
+            let metadata = route_by_path(&route.path).unwrap();
+            assert_eq!(metadata.id, route.id.as_slice(), "{}", route.path);
+            assert_eq!(metadata.xap_version, route.xap_version, "{}", route.path);
+            assert_eq!(metadata.secure, route.secure, "{}", route.path);
+        }
+    }
+}
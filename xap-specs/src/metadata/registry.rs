@@ -0,0 +1,536 @@
+// This file was generated by xap-specs, do not edit manually
+
+use super::RouteMetadata;
+use crate::request::XapCapability;
+use crate::version::BcdVersion;
+
+pub static ROUTES: &[RouteMetadata] = &[
+    RouteMetadata {
+        id: &[0x00, 0x00],
+        module: "xap",
+        path: "xap.version",
+        name: "Version",
+        description: "XAP protocol version query.\n\n* Returns the BCD-encoded version in the format of XX.YY.ZZZZ => `0xXXYYZZZZ`\n    * e.g. 3.2.115 will match `0x03020115`, or bytes {0x15,0x01,0x02,0x03}.",
+        xap_version: BcdVersion::new(0, 0, 1),
+        secure: false,
+        request_type: "()",
+        response_type: "XapVersionResponse",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x00, 0x01],
+        module: "xap",
+        path: "xap.capabilities",
+        name: "capabilities",
+        description: "XAP subsystem capabilities query. Each bit should be considered as a \"usable\" route within this subsystem.",
+        xap_version: BcdVersion::new(0, 1, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "XapCapabilitiesFlags",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x00, 0x02],
+        module: "xap",
+        path: "xap.enabled_subsystem_capabilities",
+        name: "Enabled subsystem capabilities",
+        description: "XAP protocol subsystem query. Each bit should be considered as a \"usable\" subsystem. For example, checking `(value & (1 << XAP_ROUTE_QMK) != 0)` means the QMK subsystem is enabled and available for querying.",
+        xap_version: BcdVersion::new(0, 1, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "XapEnabledSubsystemCapabilitiesFlags",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x00, 0x03],
+        module: "xap",
+        path: "xap.secure_status",
+        name: "Secure Status",
+        description: "Query secure route status\n\n* 0 means secure routes are disabled\n* 1 means unlock sequence initiated but incomplete\n* 2 means secure routes are allowed\n* any other value should be interpreted as disabled",
+        xap_version: BcdVersion::new(0, 1, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "XapSecureStatusResponse",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x00, 0x04],
+        module: "xap",
+        path: "xap.secure_unlock",
+        name: "Secure Unlock",
+        description: "Initiate secure route unlock sequence",
+        xap_version: BcdVersion::new(0, 1, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "()",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x00, 0x05],
+        module: "xap",
+        path: "xap.secure_lock",
+        name: "Secure Lock",
+        description: "Disable secure routes",
+        xap_version: BcdVersion::new(0, 1, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "()",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x01, 0x00],
+        module: "qmk",
+        path: "qmk.version",
+        name: "Version",
+        description: "QMK protocol version query.\n\n* Returns the BCD-encoded version in the format of XX.YY.ZZZZ => `0xXXYYZZZZ`\n    * e.g. 3.2.115 will match `0x03020115`, or bytes {0x15,0x01,0x02,0x03}.",
+        xap_version: BcdVersion::new(0, 1, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "QmkVersionResponse",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x01, 0x01],
+        module: "qmk",
+        path: "qmk.capabilities",
+        name: "capabilities",
+        description: "QMK subsystem capabilities query. Each bit should be considered as a \"usable\" route within this subsystem.",
+        xap_version: BcdVersion::new(0, 1, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "QmkCapabilitiesFlags",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x01, 0x02],
+        module: "qmk",
+        path: "qmk.board_identifiers",
+        name: "Board identifiers",
+        description: "Retrieves the set of identifying information for the board.",
+        xap_version: BcdVersion::new(0, 1, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "QmkBoardIdentifiersResponse",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x01, 0x03],
+        module: "qmk",
+        path: "qmk.board_manufacturer",
+        name: "Board Manufacturer",
+        description: "Retrieves the name of the manufacturer",
+        xap_version: BcdVersion::new(0, 1, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "QmkBoardManufacturerResponse",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x01, 0x04],
+        module: "qmk",
+        path: "qmk.product_name",
+        name: "Product Name",
+        description: "Retrieves the product name",
+        xap_version: BcdVersion::new(0, 1, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "QmkProductNameResponse",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x01, 0x05],
+        module: "qmk",
+        path: "qmk.config_blob_length",
+        name: "Config Blob Length",
+        description: "Retrieves the length of the configuration data bundled within the firmware",
+        xap_version: BcdVersion::new(0, 1, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "QmkConfigBlobLengthResponse",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x01, 0x06],
+        module: "qmk",
+        path: "qmk.config_blob_chunk",
+        name: "Config Blob Chunk",
+        description: "Retrieves a chunk of the configuration data bundled within the firmware",
+        xap_version: BcdVersion::new(0, 1, 0),
+        secure: false,
+        request_type: "u16",
+        response_type: "QmkConfigBlobChunkResponse",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x01, 0x07],
+        module: "qmk",
+        path: "qmk.jump_to_bootloader",
+        name: "Jump to bootloader",
+        description: "Jump to bootloader\n\nMay not be present - if QMK capabilities query returns “true”, then jump to bootloader is supported\n\n* 0 means secure routes are disabled, and should be considered as a failure\n* 1 means successful, board will jump to bootloader",
+        xap_version: BcdVersion::new(0, 1, 0),
+        secure: true,
+        request_type: "()",
+        response_type: "QmkJumpToBootloaderResponse",
+        capabilities: &[XapCapability { route: &[0x01, 0x01], bit: 7, condition: "defined(BOOTLOADER_JUMP_SUPPORTED)" }],
+    },
+    RouteMetadata {
+        id: &[0x01, 0x08],
+        module: "qmk",
+        path: "qmk.hardware_identifier",
+        name: "Hardware Identifier",
+        description: "Retrieves a unique identifier for the board.",
+        xap_version: BcdVersion::new(0, 1, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "QmkHardwareIdentifierResponse",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x01, 0x09],
+        module: "qmk",
+        path: "qmk.reinitialize_eeprom",
+        name: "Reinitialize EEPROM",
+        description: "Reinitializes the keyboard's EEPROM (persistent memory)\n\nMay not be present - if QMK capabilities query returns “true”, then reinitialize is supported\n\n* 0 means secure routes are disabled, and should be considered as a failure\n* 1 means successful, board will reinitialize and then reboot",
+        xap_version: BcdVersion::new(0, 1, 0),
+        secure: true,
+        request_type: "()",
+        response_type: "QmkReinitializeEepromResponse",
+        capabilities: &[XapCapability { route: &[0x01, 0x01], bit: 9, condition: "!defined(NO_RESET)" }],
+    },
+    RouteMetadata {
+        id: &[0x04, 0x01],
+        module: "keymap",
+        path: "keymap.capabilities",
+        name: "capabilities",
+        description: "Keymap subsystem capabilities query. Each bit should be considered as a \"usable\" route within this subsystem.",
+        xap_version: BcdVersion::new(0, 2, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "KeymapCapabilitiesFlags",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x04, 0x02],
+        module: "keymap",
+        path: "keymap.get_layer_count",
+        name: "Get Layer Count",
+        description: "Query maximum number of layers that can be addressed within the keymap.",
+        xap_version: BcdVersion::new(0, 2, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "KeymapGetLayerCountResponse",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x04, 0x03],
+        module: "keymap",
+        path: "keymap.get_keycode",
+        name: "Get Keycode",
+        description: "Query the Keycode at the requested location.",
+        xap_version: BcdVersion::new(0, 2, 0),
+        secure: false,
+        request_type: "KeymapGetKeycodeArg",
+        response_type: "KeymapGetKeycodeResponse",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x04, 0x04],
+        module: "keymap",
+        path: "keymap.get_encoder_keycode",
+        name: "Get Encoder Keycode",
+        description: "Query the Keycode at the requested location.",
+        xap_version: BcdVersion::new(0, 2, 0),
+        secure: false,
+        request_type: "KeymapGetEncoderKeycodeArg",
+        response_type: "KeymapGetEncoderKeycodeResponse",
+        capabilities: &[XapCapability { route: &[0x04, 0x01], bit: 4, condition: "defined(ENCODER_MAP_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x05, 0x01],
+        module: "remapping",
+        path: "remapping.capabilities",
+        name: "capabilities",
+        description: "Remapping subsystem capabilities query. Each bit should be considered as a \"usable\" route within this subsystem.",
+        xap_version: BcdVersion::new(0, 2, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "RemappingCapabilitiesFlags",
+        capabilities: &[XapCapability { route: &[0x00, 0x02], bit: 5, condition: "defined(DYNAMIC_KEYMAP_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x05, 0x02],
+        module: "remapping",
+        path: "remapping.get_layer_count",
+        name: "Get Layer Count",
+        description: "Query maximum number of layers that can be addressed within the keymap.",
+        xap_version: BcdVersion::new(0, 2, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "RemappingGetLayerCountResponse",
+        capabilities: &[XapCapability { route: &[0x00, 0x02], bit: 5, condition: "defined(DYNAMIC_KEYMAP_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x05, 0x03],
+        module: "remapping",
+        path: "remapping.set_keycode",
+        name: "Set Keycode",
+        description: "Modify the Keycode at the requested location.",
+        xap_version: BcdVersion::new(0, 2, 0),
+        secure: true,
+        request_type: "RemappingSetKeycodeArg",
+        response_type: "()",
+        capabilities: &[XapCapability { route: &[0x00, 0x02], bit: 5, condition: "defined(DYNAMIC_KEYMAP_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x05, 0x04],
+        module: "remapping",
+        path: "remapping.set_encoder_keycode",
+        name: "Set Encoder Keycode",
+        description: "Modify the Keycode at the requested location.",
+        xap_version: BcdVersion::new(0, 2, 0),
+        secure: true,
+        request_type: "RemappingSetEncoderKeycodeArg",
+        response_type: "()",
+        capabilities: &[XapCapability { route: &[0x00, 0x02], bit: 5, condition: "defined(DYNAMIC_KEYMAP_ENABLE)" }, XapCapability { route: &[0x05, 0x01], bit: 4, condition: "defined(ENCODER_MAP_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x06, 0x01],
+        module: "lighting",
+        path: "lighting.capabilities",
+        name: "capabilities",
+        description: "Lighting subsystem capabilities query. Each bit should be considered as a \"usable\" route within this subsystem.",
+        xap_version: BcdVersion::new(0, 2, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "LightingCapabilitiesFlags",
+        capabilities: &[],
+    },
+    RouteMetadata {
+        id: &[0x06, 0x02, 0x01],
+        module: "lighting::backlight",
+        path: "lighting.backlight.capabilities",
+        name: "capabilities",
+        description: "backlight subsystem capabilities query. Each bit should be considered as a \"usable\" route within this subsystem.",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "BacklightCapabilitiesFlags",
+        capabilities: &[XapCapability { route: &[0x06, 0x01], bit: 2, condition: "defined(BACKLIGHT_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x06, 0x02, 0x02],
+        module: "lighting::backlight",
+        path: "lighting.backlight.get_enabled_effects",
+        name: "Get Enabled Effects",
+        description: "Each bit should be considered as a \"usable\" effect id",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "BacklightGetEnabledEffectsResponse",
+        capabilities: &[XapCapability { route: &[0x06, 0x01], bit: 2, condition: "defined(BACKLIGHT_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x06, 0x02, 0x03],
+        module: "lighting::backlight",
+        path: "lighting.backlight.get_config",
+        name: "Get Config",
+        description: "Query the current config.",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "BacklightConfig",
+        capabilities: &[XapCapability { route: &[0x06, 0x01], bit: 2, condition: "defined(BACKLIGHT_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x06, 0x02, 0x04],
+        module: "lighting::backlight",
+        path: "lighting.backlight.set_config",
+        name: "Set Config",
+        description: "Set the current config.",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "BacklightConfig",
+        response_type: "()",
+        capabilities: &[XapCapability { route: &[0x06, 0x01], bit: 2, condition: "defined(BACKLIGHT_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x06, 0x02, 0x05],
+        module: "lighting::backlight",
+        path: "lighting.backlight.save_config",
+        name: "Save Config",
+        description: "Save the current config.",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "()",
+        capabilities: &[XapCapability { route: &[0x06, 0x01], bit: 2, condition: "defined(BACKLIGHT_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x06, 0x03, 0x01],
+        module: "lighting::rgblight",
+        path: "lighting.rgblight.capabilities",
+        name: "capabilities",
+        description: "rgblight subsystem capabilities query. Each bit should be considered as a \"usable\" route within this subsystem.",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "RgblightCapabilitiesFlags",
+        capabilities: &[XapCapability { route: &[0x06, 0x01], bit: 3, condition: "defined(RGBLIGHT_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x06, 0x03, 0x02],
+        module: "lighting::rgblight",
+        path: "lighting.rgblight.get_enabled_effects",
+        name: "Get Enabled Effects",
+        description: "Each bit should be considered as a \"usable\" effect id",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "RgblightGetEnabledEffectsResponse",
+        capabilities: &[XapCapability { route: &[0x06, 0x01], bit: 3, condition: "defined(RGBLIGHT_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x06, 0x03, 0x03],
+        module: "lighting::rgblight",
+        path: "lighting.rgblight.get_config",
+        name: "Get Config",
+        description: "Query the current config.",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "RgbLightConfig",
+        capabilities: &[XapCapability { route: &[0x06, 0x01], bit: 3, condition: "defined(RGBLIGHT_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x06, 0x03, 0x04],
+        module: "lighting::rgblight",
+        path: "lighting.rgblight.set_config",
+        name: "Set Config",
+        description: "Set the current config.",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "RgbLightConfig",
+        response_type: "()",
+        capabilities: &[XapCapability { route: &[0x06, 0x01], bit: 3, condition: "defined(RGBLIGHT_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x06, 0x03, 0x05],
+        module: "lighting::rgblight",
+        path: "lighting.rgblight.save_config",
+        name: "Save Config",
+        description: "Save the current config.",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "()",
+        capabilities: &[XapCapability { route: &[0x06, 0x01], bit: 3, condition: "defined(RGBLIGHT_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x06, 0x04, 0x01],
+        module: "lighting::rgbmatrix",
+        path: "lighting.rgbmatrix.capabilities",
+        name: "capabilities",
+        description: "rgb matrix subsystem capabilities query. Each bit should be considered as a \"usable\" route within this subsystem.",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "RgbmatrixCapabilitiesFlags",
+        capabilities: &[XapCapability { route: &[0x06, 0x01], bit: 4, condition: "defined(RGB_MATRIX_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x06, 0x04, 0x02],
+        module: "lighting::rgbmatrix",
+        path: "lighting.rgbmatrix.get_enabled_effects",
+        name: "Get Enabled Effects",
+        description: "Each bit should be considered as a \"usable\" effect id",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "RgbmatrixGetEnabledEffectsResponse",
+        capabilities: &[XapCapability { route: &[0x06, 0x01], bit: 4, condition: "defined(RGB_MATRIX_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x06, 0x04, 0x03],
+        module: "lighting::rgbmatrix",
+        path: "lighting.rgbmatrix.get_config",
+        name: "Get Config",
+        description: "Query the current config.",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "RgbMatrixConfig",
+        capabilities: &[XapCapability { route: &[0x06, 0x01], bit: 4, condition: "defined(RGB_MATRIX_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x06, 0x04, 0x04],
+        module: "lighting::rgbmatrix",
+        path: "lighting.rgbmatrix.set_config",
+        name: "Set Config",
+        description: "Set the current config.",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "RgbMatrixConfig",
+        response_type: "()",
+        capabilities: &[XapCapability { route: &[0x06, 0x01], bit: 4, condition: "defined(RGB_MATRIX_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x06, 0x04, 0x05],
+        module: "lighting::rgbmatrix",
+        path: "lighting.rgbmatrix.save_config",
+        name: "Save Config",
+        description: "Save the current config.",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "()",
+        capabilities: &[XapCapability { route: &[0x06, 0x01], bit: 4, condition: "defined(RGB_MATRIX_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x07, 0x01],
+        module: "audio",
+        path: "audio.capabilities",
+        name: "capabilities",
+        description: "Audio subsystem capabilities query. Each bit should be considered as a \"usable\" route within this subsystem.",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "AudioCapabilitiesFlags",
+        capabilities: &[XapCapability { route: &[0x00, 0x02], bit: 7, condition: "defined(AUDIO_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x07, 0x03],
+        module: "audio",
+        path: "audio.get_config",
+        name: "Get Config",
+        description: "Query the current config.",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "AudioConfig",
+        capabilities: &[XapCapability { route: &[0x00, 0x02], bit: 7, condition: "defined(AUDIO_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x07, 0x04],
+        module: "audio",
+        path: "audio.set_config",
+        name: "Set Config",
+        description: "Set the current config.",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "AudioConfig",
+        response_type: "()",
+        capabilities: &[XapCapability { route: &[0x00, 0x02], bit: 7, condition: "defined(AUDIO_ENABLE)" }],
+    },
+    RouteMetadata {
+        id: &[0x07, 0x05],
+        module: "audio",
+        path: "audio.save_config",
+        name: "Save Config",
+        description: "Save the current config.",
+        xap_version: BcdVersion::new(0, 3, 0),
+        secure: false,
+        request_type: "()",
+        response_type: "()",
+        capabilities: &[XapCapability { route: &[0x00, 0x02], bit: 7, condition: "defined(AUDIO_ENABLE)" }],
+    },
+];
@@ -2,19 +2,35 @@ use core::fmt::Debug;
 use std::io::{Seek, Write};
 
 use binrw::{BinRead, BinResult, BinWrite, BinWriterExt, Endian};
+use serde::Serialize;
 
-use crate::token::Token;
+use crate::{token::Token, version::BcdVersion};
 
 pub trait XapRequest: Sized + Debug + for<'a> BinWrite<Args<'a> = ()> {
     type Response: for<'a> BinRead<Args<'a> = ()>;
 
     fn id() -> &'static [u8];
 
-    fn xap_version() -> u32;
+    fn xap_version() -> BcdVersion;
 
     fn is_secure() -> bool {
         false
     }
+
+    /// Capability bits that have to be reported by the device for this request to be usable.
+    fn capabilities() -> &'static [XapCapability] {
+        &[]
+    }
+}
+
+/// A bit in the response of the capabilities query at `route`, it is set if the firmware was
+/// compiled with `condition` being true.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub struct XapCapability {
+    pub route: &'static [u8],
+    pub bit: u8,
+    pub condition: &'static str,
 }
 
 pub struct RawRequest<T: XapRequest> {
@@ -26,9 +42,14 @@ impl<T> RawRequest<T>
 where
     T: XapRequest,
 {
-    pub fn new(payload: T) -> Self {
+    pub fn new(token: Token, payload: T) -> Self {
+        Self { token, payload }
+    }
+
+    /// Creates a "fire and forget" request, the device doesn't send a response for it.
+    pub fn without_response(payload: T) -> Self {
         Self {
-            token: Token::regular_token(),
+            token: Token::WithoutResponse,
             payload,
         }
     }
@@ -50,22 +71,61 @@ where
         _endian: Endian,
         _args: Self::Args<'_>,
     ) -> BinResult<()> {
-        writer.write_le(&self.token)?;
-        // Dummy write of the payload length, which is not known at this point.
-        writer.write_le(&0_u8)?;
-        writer.write_le(&T::id())?;
-        writer.write_le(&self.payload)?;
-
-        // Calculate payload size from current position in the writer stream,
-        // which points at the end of payload and contains the Token and payload
-        // lenght field itself. These have to be substracted to get the total
-        // size of the payload.
-        let payload_length = writer.stream_position()?
-            - std::mem::size_of::<u16>() as u64 // Token
-            - std::mem::size_of::<u8>() as u64; // payload length field
-
-        // Position our writer on the payload_length field again and write the correct value.
-        writer.seek(std::io::SeekFrom::Start(2))?;
-        writer.write_le(&(payload_length as u8))
+        write_request(writer, &self.token, T::id(), &self.payload)
     }
 }
+
+/// A request to a route that is only known at runtime, with an already encoded payload.
+#[derive(Debug)]
+pub struct DynamicRequest<'a> {
+    token: Token,
+    id: &'a [u8],
+    payload: &'a [u8],
+}
+
+impl<'a> DynamicRequest<'a> {
+    pub fn new(token: Token, id: &'a [u8], payload: &'a [u8]) -> Self {
+        Self { token, id, payload }
+    }
+
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+}
+
+impl BinWrite for DynamicRequest<'_> {
+    type Args<'a> = ();
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        _endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> BinResult<()> {
+        write_request(writer, &self.token, self.id, &self.payload)
+    }
+}
+
+fn write_request<W, P>(writer: &mut W, token: &Token, id: &[u8], payload: &P) -> BinResult<()>
+where
+    W: Write + Seek,
+    P: for<'a> BinWrite<Args<'a> = ()>,
+{
+    writer.write_le(token)?;
+    // Dummy write of the payload length, which is not known at this point.
+    writer.write_le(&0_u8)?;
+    writer.write_le(&id)?;
+    writer.write_le(payload)?;
+
+    // Calculate payload size from current position in the writer stream,
+    // which points at the end of payload and contains the Token and payload
+    // lenght field itself. These have to be substracted to get the total
+    // size of the payload.
+    let payload_length = writer.stream_position()?
+        - std::mem::size_of::<u16>() as u64 // Token
+        - std::mem::size_of::<u8>() as u64; // payload length field
+
+    // Position our writer on the payload_length field again and write the correct value.
+    writer.seek(std::io::SeekFrom::Start(2))?;
+    writer.write_le(&(payload_length as u8))
+}
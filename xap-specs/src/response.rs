@@ -1,15 +1,16 @@
 use core::fmt::Debug;
 use std::io::{Cursor, Read, Seek};
 
-use anyhow::anyhow;
-use anyhow::Result;
 use binrw::{binread, BinRead, BinResult, Endian};
 use bitflags::bitflags;
 use log::trace;
 use serde::Serialize;
-use specta::Type;
 
-use crate::{request::XapRequest, token::Token};
+use crate::{
+    error::{XapError, XapResult},
+    request::XapRequest,
+    token::Token,
+};
 
 #[derive(Serialize, BinRead, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ResponseFlags(u8);
@@ -33,7 +34,7 @@ pub struct RawResponse {
 }
 
 impl RawResponse {
-    pub fn from_raw_report(report: &[u8]) -> Result<Self> {
+    pub fn from_raw_report(report: &[u8]) -> XapResult<Self> {
         let mut reader = Cursor::new(report);
         let response = RawResponse::read_le(&mut reader)?;
 
@@ -41,8 +42,11 @@ impl RawResponse {
 
         match response.flags {
             ResponseFlags::SUCCESS => Ok(response),
-            ResponseFlags::SECURE_FAILURE => Err(anyhow!("device is locked")),
-            _ => Err(anyhow!("unknown response flag {:?}", response.flags)),
+            ResponseFlags::SECURE_FAILURE => Err(XapError::DeviceLocked),
+            flags if flags.is_empty() => Err(XapError::RequestFailed),
+            flags => Err(XapError::UnknownResponseFlags {
+                flags: flags.bits(),
+            }),
         }
     }
 
@@ -54,7 +58,7 @@ impl RawResponse {
         &self.payload
     }
 
-    pub fn into_xap_response<T>(self) -> Result<T::Response>
+    pub fn into_xap_response<T>(self) -> XapResult<T::Response>
     where
         T: XapRequest,
     {
@@ -64,7 +68,8 @@ impl RawResponse {
     }
 }
 
-#[derive(Debug, Default, Clone, Serialize, Type)]
+#[derive(Debug, Default, Clone, Serialize)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
 pub struct UTF8String(pub String);
 
 impl BinRead for UTF8String {
@@ -82,10 +87,10 @@ impl BinRead for UTF8String {
 #[derive(BinRead, Debug)]
 pub struct SecureActionResponse(u8);
 
-impl From<SecureActionResponse> for Result<()> {
+impl From<SecureActionResponse> for XapResult<()> {
     fn from(val: SecureActionResponse) -> Self {
         if val.0 == 0 {
-            Err(anyhow!("secure action failed"))
+            Err(XapError::SecureActionFailed)
         } else {
             Ok(())
         }
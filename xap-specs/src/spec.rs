@@ -0,0 +1,644 @@
+// This file defines the model of the XAP specifications in the hjson format, shared by the code
+// generator and the dynamic route table
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, read_dir},
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use log::info;
+use serde::{
+    de::{MapAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+
+use crate::version::BcdVersion;
+
+/// Capabilities query of the XAP subsystem that reports the enabled subsystems.
+pub const ENABLED_SUBSYSTEMS_ROUTE: [u8; 2] = [0x00, 0x02];
+
+#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BasicType {
+    #[default]
+    Unit,
+    U8,
+    U16,
+    U32,
+    U64,
+    Struct,
+    String,
+    Array(Box<BasicType>, usize),
+    Predefined(String),
+}
+
+impl BasicType {
+    /// Name of the type definition this type refers to, also for arrays of predefined types.
+    pub fn predefined(&self) -> Option<&str> {
+        match self {
+            BasicType::Predefined(name) => Some(name),
+            BasicType::Array(ty, _) => ty.predefined(),
+            _ => None,
+        }
+    }
+}
+
+struct BasicTypeVisitor;
+
+impl<'de> Visitor<'de> for BasicTypeVisitor {
+    type Value = BasicType;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(
+            "a string representing a basic type like u8, u16, u32, u64, string, array or struct",
+        )
+    }
+
+    fn visit_str<E>(self, value: &str) -> std::result::Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        if let Some((ty, len)) = value.split_once('[') {
+            let len = len
+                .strip_suffix(']')
+                .ok_or_else(|| E::custom("array type missing closing bracket"))?;
+            let len = len
+                .parse::<usize>()
+                .map_err(|_| E::custom("array type length is not a number"))?;
+
+            return Ok(BasicType::Array(Box::new(self.visit_str(ty)?), len));
+        }
+
+        match value {
+            "u8" => Ok(BasicType::U8),
+            "u16" => Ok(BasicType::U16),
+            "u32" => Ok(BasicType::U32),
+            "u64" => Ok(BasicType::U64),
+            "struct" => Ok(BasicType::Struct),
+            "string" => Ok(BasicType::String),
+            _ => Ok(BasicType::Predefined(value.to_owned())),
+            // _ => Err(E::custom(format!("unknown basic type: {value}"))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BasicType {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(BasicTypeVisitor)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct StructMember {
+    pub name: String,
+    pub r#type: BasicType,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteType {
+    #[default]
+    Router,
+    Command,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RoutePermissions {
+    Secure,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Route {
+    #[serde(skip_deserializing)]
+    pub id: Vec<u8>,
+    #[serde(skip_deserializing)]
+    pub xap_version: Option<BcdVersion>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub r#type: Option<RouteType>,
+    #[serde(deserialize_with = "deserialize_routes")]
+    pub routes: BTreeMap<u8, Route>,
+    pub return_type: BasicType,
+    pub return_struct_members: Vec<StructMember>,
+    pub return_purpose: Option<String>,
+    pub request_type: BasicType,
+    pub request_struct_members: Vec<StructMember>,
+    pub permissions: Option<RoutePermissions>,
+    pub enable_if_preprocessor: Option<String>,
+}
+
+impl Route {
+    /// Returns true if the route reports which of its sibling routes are compiled in.
+    pub fn is_capabilities(&self) -> bool {
+        self.return_purpose
+            .as_ref()
+            .is_some_and(|p| p == "capabilities")
+    }
+
+    pub fn is_secure(&self) -> bool {
+        self.permissions == Some(RoutePermissions::Secure)
+    }
+
+    pub fn merge(&mut self, other: &Route) {
+        if other.name.is_some() {
+            self.name.clone_from(&other.name);
+        }
+        if other.description.is_some() {
+            self.description.clone_from(&other.description);
+        }
+        if other.r#type.is_some() {
+            self.r#type.clone_from(&other.r#type);
+        }
+        if other.permissions.is_some() {
+            self.permissions.clone_from(&other.permissions);
+        }
+        if other.enable_if_preprocessor.is_some() {
+            self.enable_if_preprocessor
+                .clone_from(&other.enable_if_preprocessor);
+        }
+        for (key, other_route) in &other.routes {
+            if let Some(route) = self.routes.get_mut(key) {
+                route.merge(other_route);
+            } else {
+                self.routes.insert(*key, other_route.clone());
+            }
+        }
+    }
+
+    fn expand_ids(&mut self, parent_id: &mut Vec<u8>) {
+        parent_id.extend_from_slice(&self.id);
+        self.id.clone_from(parent_id);
+
+        for route in self.routes.values_mut() {
+            route.expand_ids(&mut parent_id.clone());
+        }
+    }
+
+    fn expand_xap_specs(&mut self, xap_version: BcdVersion) {
+        if self.xap_version.is_none() || self.xap_version > Some(xap_version) {
+            self.xap_version.replace(xap_version);
+        }
+
+        for route in &mut self.routes.values_mut() {
+            route.expand_xap_specs(xap_version);
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TypeDefinition {
+    pub name: String,
+    pub description: String,
+    pub r#type: BasicType,
+    pub struct_length: Option<usize>,
+    #[serde(default)]
+    pub struct_members: Vec<StructMember>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Message {
+    pub name: String,
+    pub define: Option<String>,
+    pub description: String,
+    /// Type of the payload, messages without one leave their payload to the firmware.
+    #[serde(default)]
+    pub return_type: BasicType,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct BroadcastMessages {
+    #[serde(default, deserialize_with = "deserialize_ids")]
+    pub messages: BTreeMap<u8, Message>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ResponseFlag {
+    pub name: String,
+    pub define: Option<String>,
+    pub description: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ResponseFlags {
+    #[serde(default, deserialize_with = "deserialize_ids")]
+    pub bits: BTreeMap<u8, ResponseFlag>,
+}
+
+/// Prose of the protocol documentation.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Documentation {
+    /// Order of the sections in the documentation, entries starting with `!` refer to the tables
+    /// rendered from the spec, e.g. `!routes.md.j2`.
+    #[serde(default)]
+    pub order: Vec<String>,
+    #[serde(flatten)]
+    pub sections: BTreeMap<String, String>,
+}
+
+impl Documentation {
+    /// Sections of the older documentation come first, newer sections replace older ones.
+    fn merge(&mut self, older: &Documentation) {
+        let mut order = older.order.clone();
+        order.extend(
+            self.order
+                .iter()
+                .filter(|section| !older.order.contains(section))
+                .cloned(),
+        );
+        self.order = order;
+
+        for (name, section) in &older.sections {
+            self.sections
+                .entry(name.clone())
+                .or_insert_with(|| section.clone());
+        }
+    }
+}
+
+/// A capability bit in the response of the capabilities query at `route`, it is set if the
+/// firmware was compiled with `condition` being true.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteCapability {
+    pub route: Vec<u8>,
+    pub bit: u8,
+    pub condition: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Spec {
+    pub version: BcdVersion,
+    #[serde(default, deserialize_with = "deserialize_routes")]
+    pub routes: BTreeMap<u8, Route>,
+    pub broadcast_messages: Option<BroadcastMessages>,
+    #[serde(default)]
+    pub type_definitions: BTreeMap<String, TypeDefinition>,
+    #[serde(default)]
+    pub documentation: Documentation,
+    /// Descriptions of the basic types, in the order of the spec.
+    #[serde(default, deserialize_with = "deserialize_ordered")]
+    pub type_docs: Vec<(String, String)>,
+    /// Terms used across the protocol, in the order of the spec.
+    #[serde(default, deserialize_with = "deserialize_ordered")]
+    pub term_definitions: Vec<(String, String)>,
+    #[serde(default)]
+    pub response_flags: ResponseFlags,
+}
+
+impl Spec {
+    pub fn merge(&mut self, other: &Spec) {
+        for (id, route) in &other.routes {
+            match self.routes.get_mut(id) {
+                Some(existing_route) => {
+                    existing_route.merge(route);
+                }
+                None => {
+                    self.routes.insert(*id, route.clone());
+                }
+            }
+        }
+
+        for route in &mut self.routes.values_mut() {
+            route.expand_xap_specs(self.version)
+        }
+
+        if let Some(broadcast_messages) = &other.broadcast_messages {
+            if let Some(existing_broadcast_messages) = &mut self.broadcast_messages {
+                // Messages of the newer spec take precedence over the merged older ones
+                for (id, message) in &broadcast_messages.messages {
+                    existing_broadcast_messages
+                        .messages
+                        .entry(*id)
+                        .or_insert_with(|| message.clone());
+                }
+            } else {
+                self.broadcast_messages = Some(broadcast_messages.clone());
+            }
+        }
+
+        self.type_definitions.extend(other.type_definitions.clone());
+
+        self.documentation.merge(&other.documentation);
+        merge_ordered(&mut self.type_docs, &other.type_docs);
+        merge_ordered(&mut self.term_definitions, &other.term_definitions);
+        for (bit, flag) in &other.response_flags.bits {
+            self.response_flags
+                .bits
+                .entry(*bit)
+                .or_insert_with(|| flag.clone());
+        }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Spec> {
+        let content = fs::read_to_string(path)?;
+
+        let mut spec = deser_hjson::from_str::<Spec>(&content)?;
+
+        for route in &mut spec.routes.values_mut() {
+            route.expand_ids(&mut Vec::new());
+            route.expand_xap_specs(spec.version)
+        }
+
+        Ok(spec)
+    }
+
+    /// Reads all `xap*.hjson` specs in `dir` and merges them into the latest one, which then
+    /// contains all previous iterations. Returns `None` if there are no specs.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Option<Spec>> {
+        Ok(Spec::versions_from_dir(dir)?.pop())
+    }
+
+    /// Reads all `xap*.hjson` specs in `dir`, every version is merged with all previous ones.
+    /// The specs are in ascending order of their version.
+    pub fn versions_from_dir(dir: impl AsRef<Path>) -> Result<Vec<Spec>> {
+        let specs = spec_files(dir)?
+            .into_iter()
+            .map(Spec::from_file)
+            .collect::<Result<Vec<Spec>>>()?;
+
+        Ok(Spec::merge_versions(specs))
+    }
+
+    /// Merges every spec with all previous versions, the last one then contains all iterations.
+    pub fn merge_versions(mut specs: Vec<Spec>) -> Vec<Spec> {
+        // Make sure we process specs in ascending order, as they build upon eachother
+        specs.sort_by_key(|spec| spec.version);
+
+        for i in 1..specs.len() {
+            let spec_lower_version = specs[i - 1].clone();
+            specs[i].merge(&spec_lower_version);
+        }
+
+        specs
+    }
+
+    /// Struct type definitions ordered so that every struct comes after the structs it is
+    /// composed of. Fails if a member refers to a missing type definition or a struct contains
+    /// itself.
+    pub fn struct_definitions(&self) -> Result<Vec<(&str, &TypeDefinition)>> {
+        fn visit<'a>(
+            spec: &'a Spec,
+            name: &'a str,
+            visiting: &mut Vec<&'a str>,
+            sorted: &mut Vec<(&'a str, &'a TypeDefinition)>,
+        ) -> Result<()> {
+            if sorted.iter().any(|(sorted, _)| *sorted == name) {
+                return Ok(());
+            }
+            if visiting.contains(&name) {
+                bail!("type definition {name} contains itself");
+            }
+            let Some((name, definition)) = spec.type_definitions.get_key_value(name) else {
+                bail!("missing type definition for {name}");
+            };
+            if definition.r#type != BasicType::Struct {
+                return Ok(());
+            }
+
+            visiting.push(name);
+            for member in &definition.struct_members {
+                if let Some(dependency) = member.r#type.predefined() {
+                    visit(spec, dependency, visiting, sorted)?;
+                }
+            }
+            visiting.pop();
+
+            sorted.push((name, definition));
+            Ok(())
+        }
+
+        let mut sorted = Vec::new();
+        for name in self.type_definitions.keys() {
+            visit(self, name, &mut Vec::new(), &mut sorted)?;
+        }
+
+        Ok(sorted)
+    }
+
+    /// Capability bits the route with `id` depends on. Every route on the path to this route
+    /// that is only compiled in under a preprocessor condition has to be reported by the
+    /// capabilities query of its parent.
+    pub fn capabilities(&self, id: &[u8]) -> Vec<RouteCapability> {
+        let mut capabilities = Vec::new();
+        let mut capabilities_route = ENABLED_SUBSYSTEMS_ROUTE.to_vec();
+        let mut routes = &self.routes;
+
+        for bit in id {
+            let Some(route) = routes.get(bit) else {
+                break;
+            };
+
+            if let Some(condition) = &route.enable_if_preprocessor {
+                capabilities.push(RouteCapability {
+                    route: capabilities_route.clone(),
+                    bit: *bit,
+                    condition: condition.clone(),
+                });
+            }
+
+            if let Some(route) = route.routes.values().find(|route| route.is_capabilities()) {
+                capabilities_route.clone_from(&route.id);
+            }
+            routes = &route.routes;
+        }
+
+        capabilities
+    }
+}
+
+/// Paths of all `xap*.hjson` specs in `dir`.
+pub(crate) fn spec_files(dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    for entry in read_dir(dir)?.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+
+        if path.is_dir() {
+            continue;
+        }
+
+        if let Some(filename) = path.file_name().map(|name| name.to_string_lossy()) {
+            if !filename.starts_with("xap") || !filename.ends_with(".hjson") {
+                info!("skipping file {filename}");
+                continue;
+            }
+            info!("reading spec {filename}");
+            files.push(path);
+        } else {
+            info!("skipping file without filename {path:?}");
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Keeps the order of the older entries, entries of the newer spec replace them or are appended.
+fn merge_ordered(newer: &mut Vec<(String, String)>, older: &[(String, String)]) {
+    let mut merged = older.to_vec();
+    for (key, value) in newer.drain(..) {
+        match merged.iter_mut().find(|(existing, _)| *existing == key) {
+            Some((_, existing)) => *existing = value,
+            None => merged.push((key, value)),
+        }
+    }
+    *newer = merged;
+}
+
+/// Map that keeps every entry in the order of the file, including duplicate keys.
+pub(crate) struct Entries<T>(pub Vec<(String, T)>);
+
+impl<T> Default for Entries<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Entries<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for EntriesVisitor<T> {
+            type Value = Entries<T>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a map")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Entries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor(PhantomData))
+    }
+}
+
+fn deserialize_ordered<'de, D>(d: D) -> Result<Vec<(String, String)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Entries::deserialize(d)?.0)
+}
+
+fn deserialize_ids<'de, D, T>(d: D) -> Result<BTreeMap<u8, T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let entries: BTreeMap<String, T> = Deserialize::deserialize(d)?;
+
+    entries
+        .into_iter()
+        .map(|(id, entry)| {
+            let id = u8::from_str_radix(id.trim_start_matches("0x"), 16)
+                .map_err(serde::de::Error::custom)?;
+            Ok((id, entry))
+        })
+        .collect()
+}
+
+fn deserialize_routes<'de, D>(d: D) -> Result<BTreeMap<u8, Route>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let routes: BTreeMap<String, Route> = Deserialize::deserialize(d)?;
+
+    routes
+        .into_iter()
+        .map(|(id, mut route)| {
+            let id = u8::from_str_radix(id.trim_start_matches("0x"), 16)
+                .map_err(serde::de::Error::custom)?;
+
+            route.name = route
+                .name
+                .as_mut()
+                .map(|name| name.replace("query", "").replace("Query", ""));
+
+            if route.is_capabilities() {
+                route.name = route.name.as_mut().map(|name| {
+                    let mut name = name.replace("capabilities", "").replace("Capabilities", "");
+                    name.push_str("capabilities");
+                    name
+                })
+            }
+            route.id = vec![id];
+            Ok((id, route))
+        })
+        .collect::<Result<BTreeMap<u8, Route>, D::Error>>()
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Value};
+    use similar_asserts::assert_eq;
+
+    use super::*;
+
+    fn spec(type_definitions: Value) -> Spec {
+        serde_json::from_value(json!({
+            "version": "0.0.1",
+            "type_definitions": type_definitions,
+        }))
+        .unwrap()
+    }
+
+    fn struct_definition(members: Value) -> Value {
+        json!({
+            "name": "Struct",
+            "description": "Struct",
+            "type": "struct",
+            "struct_members": members,
+        })
+    }
+
+    #[test]
+    fn struct_definitions_in_dependency_order() {
+        let spec = spec(json!({
+            "a_outer": struct_definition(json!([
+                { "name": "inner", "type": "b_inner[2]" },
+                { "name": "id", "type": "identifier" },
+            ])),
+            "b_inner": struct_definition(json!([{ "name": "value", "type": "u8" }])),
+            "identifier": { "name": "Identifier", "description": "Identifier", "type": "u8" },
+        }));
+
+        let names: Vec<&str> = spec
+            .struct_definitions()
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, vec!["b_inner", "a_outer"]);
+    }
+
+    #[test]
+    fn struct_definitions_fail_on_invalid_members() {
+        let missing = spec(json!({
+            "outer": struct_definition(json!([{ "name": "inner", "type": "missing" }])),
+        }));
+        assert_eq!(
+            missing.struct_definitions().unwrap_err().to_string(),
+            "missing type definition for missing"
+        );
+
+        let recursive = spec(json!({
+            "outer": struct_definition(json!([{ "name": "outer", "type": "outer" }])),
+        }));
+        assert_eq!(
+            recursive.struct_definitions().unwrap_err().to_string(),
+            "type definition outer contains itself"
+        );
+    }
+}
@@ -1,23 +1,62 @@
 // This file defines the different kind of tokens
 
-use anyhow::anyhow;
 use binrw::{prelude::*, Endian};
-use rand::distributions::{Distribution, Uniform};
+use rand::{
+    distributions::{Distribution, Uniform},
+    rngs::StdRng,
+    SeedableRng,
+};
+
+use crate::error::XapError;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[binwrite]
 #[br(repr = u16)]
 pub enum Token {
     WithResponse(u16),
-    #[br(magic = 0xFFFE)]
+    #[bw(magic = 0xFFFE_u16)]
     WithoutResponse,
-    #[br(magic = 0xFFFF)]
+    #[bw(magic = 0xFFFF_u16)]
     Broadcast,
 }
 
-impl Token {
-    pub(crate) fn regular_token() -> Token {
-        Self::WithResponse(Uniform::from(0x0100..=0xFFFD).sample(&mut rand::thread_rng()))
+/// Hands out random request tokens that are unique among the requests still waiting for their
+/// response.
+#[derive(Debug)]
+pub struct TokenAllocator {
+    rng: StdRng,
+}
+
+impl TokenAllocator {
+    pub fn new() -> Self {
+        Self {
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Creates an allocator that hands out the same sequence of tokens for the same seed.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Draws random tokens until one is found that `is_in_flight` reports as unused.
+    pub fn allocate(&mut self, is_in_flight: impl Fn(&Token) -> bool) -> Token {
+        let range = Uniform::from(0x0100..=0xFFFD);
+
+        loop {
+            let token = Token::WithResponse(range.sample(&mut self.rng));
+            if !is_in_flight(&token) {
+                return token;
+            }
+        }
+    }
+}
+
+impl Default for TokenAllocator {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -37,8 +76,49 @@ impl BinRead for Token {
             0xFFFF => Ok(Token::Broadcast),
             _ => Err(binrw::Error::Custom {
                 pos: 0,
-                err: Box::new(anyhow!("XAP token has invalid value of {}", raw)),
+                err: Box::new(XapError::Decode(format!(
+                    "XAP token has invalid value of {raw}"
+                ))),
             }),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashSet, io::Cursor};
+
+    use super::*;
+
+    #[test]
+    fn write_reserved_tokens() {
+        let mut writer = Cursor::new(Vec::new());
+        writer.write_le(&Token::WithResponse(0x1234)).unwrap();
+        writer.write_le(&Token::WithoutResponse).unwrap();
+        writer.write_le(&Token::Broadcast).unwrap();
+
+        assert_eq!(writer.into_inner(), [0x34, 0x12, 0xFE, 0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn seeded_allocation_is_deterministic() {
+        let mut lhs = TokenAllocator::with_seed(42);
+        let mut rhs = TokenAllocator::with_seed(42);
+
+        for _ in 0..100 {
+            assert_eq!(lhs.allocate(|_| false), rhs.allocate(|_| false));
+        }
+    }
+
+    #[test]
+    fn allocation_skips_tokens_in_flight() {
+        let mut allocator = TokenAllocator::with_seed(0);
+        let mut in_flight = HashSet::new();
+
+        for _ in 0..1000 {
+            let token = allocator.allocate(|token| in_flight.contains(token));
+            assert!(matches!(token, Token::WithResponse(0x0100..=0xFFFD)));
+            assert!(in_flight.insert(token));
+        }
+    }
+}
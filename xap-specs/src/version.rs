@@ -0,0 +1,196 @@
+// This file defines the BCD encoded version numbers used by XAP
+
+use std::{fmt::Display, str::FromStr};
+
+use binrw::{BinRead, BinReaderExt, BinResult, Endian};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::XapError;
+
+/// Semantic version encoded as BCD in the `0xXXYYZZZZ` format, which XAP uses for its own
+/// protocol version and the QMK firmware version. E.g. `0x00240008` is version "0.24.8".
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "specta", specta(transparent))]
+pub struct BcdVersion(#[cfg_attr(feature = "specta", specta(type = String))] u32);
+
+impl BcdVersion {
+    /// Creates a version from its decimal components, panics if a component has more digits
+    /// than the BCD encoding can hold.
+    pub const fn new(major: u8, minor: u8, patch: u16) -> Self {
+        assert!(major <= 99 && minor <= 99 && patch <= 9999);
+
+        Self(to_bcd(major as u32) << 24 | to_bcd(minor as u32) << 16 | to_bcd(patch as u32))
+    }
+
+    pub const fn major(&self) -> u8 {
+        from_bcd(self.0 >> 24) as u8
+    }
+
+    pub const fn minor(&self) -> u8 {
+        from_bcd((self.0 >> 16) & 0xFF) as u8
+    }
+
+    pub const fn patch(&self) -> u16 {
+        from_bcd(self.0 & 0xFFFF) as u16
+    }
+
+    /// The raw BCD encoded version as sent over the wire.
+    pub const fn bcd(&self) -> u32 {
+        self.0
+    }
+}
+
+const fn to_bcd(mut value: u32) -> u32 {
+    let mut bcd = 0;
+    let mut shift = 0;
+    while value > 0 {
+        bcd |= (value % 10) << shift;
+        value /= 10;
+        shift += 4;
+    }
+    bcd
+}
+
+const fn from_bcd(mut bcd: u32) -> u32 {
+    let mut value = 0;
+    let mut factor = 1;
+    while bcd > 0 {
+        value += (bcd & 0xF) * factor;
+        bcd >>= 4;
+        factor *= 10;
+    }
+    value
+}
+
+impl TryFrom<u32> for BcdVersion {
+    type Error = XapError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        if (0..8).any(|digit| (value >> (digit * 4)) & 0xF > 9) {
+            return Err(XapError::Decode(format!(
+                "{value:08X} is not a valid BCD encoded version"
+            )));
+        }
+        Ok(Self(value))
+    }
+}
+
+impl FromStr for BcdVersion {
+    type Err = XapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || XapError::Decode(format!("{s} is not a valid version"));
+
+        let mut components = s.split('.').map(|component| component.parse::<u16>());
+        let (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch)), None) = (
+            components.next(),
+            components.next(),
+            components.next(),
+            components.next(),
+        ) else {
+            return Err(invalid());
+        };
+
+        if major > 99 || minor > 99 || patch > 9999 {
+            return Err(invalid());
+        }
+
+        Ok(Self::new(major as u8, minor as u8, patch))
+    }
+}
+
+impl Display for BcdVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major(), self.minor(), self.patch())
+    }
+}
+
+impl Serialize for BcdVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for BcdVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw: String = Deserialize::deserialize(deserializer)?;
+        raw.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl BinRead for BcdVersion {
+    type Args<'a> = ();
+
+    fn read_options<R: std::io::Read + std::io::Seek>(
+        reader: &mut R,
+        _endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> BinResult<Self> {
+        let pos = reader.stream_position()?;
+        let raw: u32 = reader.read_le()?;
+
+        Self::try_from(raw).map_err(|err| binrw::Error::Custom {
+            pos,
+            err: Box::new(err),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use similar_asserts::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn decode_bcd() {
+        let version = BcdVersion::try_from(0x0024_0008).unwrap();
+        assert_eq!(version.major(), 0);
+        assert_eq!(version.minor(), 24);
+        assert_eq!(version.patch(), 8);
+        assert_eq!(version.to_string(), "0.24.8");
+
+        assert_eq!(
+            BcdVersion::try_from(0x0003_0000).unwrap().to_string(),
+            "0.3.0"
+        );
+        assert_eq!(BcdVersion::new(12, 34, 5678).bcd(), 0x1234_5678);
+    }
+
+    #[test]
+    fn reject_invalid_bcd() {
+        assert!(BcdVersion::try_from(0x000A_0000).is_err());
+
+        let mut reader = Cursor::new([0x00, 0x00, 0x0F, 0x00]);
+        assert!(matches!(
+            XapError::from(BcdVersion::read_le(&mut reader).unwrap_err()),
+            XapError::Decode(_)
+        ));
+    }
+
+    #[test]
+    fn order_by_components() {
+        assert!(BcdVersion::new(0, 9, 0) < BcdVersion::new(0, 10, 0));
+        assert!(BcdVersion::new(0, 3, 0) < BcdVersion::new(1, 0, 0));
+        assert!(BcdVersion::new(0, 0, 1) < BcdVersion::new(0, 1, 0));
+    }
+
+    #[test]
+    fn parse_and_serialize() {
+        let version: BcdVersion = "0.24.8".parse().unwrap();
+        assert_eq!(version, BcdVersion::new(0, 24, 8));
+
+        assert!("0.24".parse::<BcdVersion>().is_err());
+        assert!("0.100.0".parse::<BcdVersion>().is_err());
+        assert!("0.1.0.0".parse::<BcdVersion>().is_err());
+
+        assert_eq!(serde_json::to_string(&version).unwrap(), r#""0.24.8""#);
+        assert_eq!(
+            serde_json::from_str::<BcdVersion>(r#""0.3.0""#).unwrap(),
+            BcdVersion::new(0, 3, 0)
+        );
+    }
+}